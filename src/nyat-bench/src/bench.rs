@@ -0,0 +1,730 @@
+use nyat_sat::proof::{check_drup, DrupChecker, ProofStep};
+use nyat_sat::sat::{SatProblem, SatSolver};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// interval of polling a worker process
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// argument of a command that stands for the path of its proof
+const PROOF: &str = "{proof}";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Runner {
+    /// solver of nyat-sat in a worker thread, with the options of nyat that it supports
+    Thread {
+        seed: u64,
+        randomize: bool,
+        restart_interval: Option<usize>,
+    },
+    /// command in a worker process, which is given the CNF file after its arguments, where `{proof}` is
+    /// the path of the DRUP proof of an UNSAT answer, and prints `SAT` or `s SATISFIABLE` with the model,
+    /// or `UNSAT` or `s UNSATISFIABLE`
+    Process(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub name: String,
+    pub runner: Runner,
+}
+
+impl Config {
+    /// Parses `name=args`, where the arguments are a command and its arguments, or else options of nyat,
+    /// which are given to `default_command` in a process if there is one, or else to a solver in a thread.
+    /// The options of nyat are followed by `--proof {proof}`, unless nyat writes no proof with them.
+    pub fn parse(s: &str, default_command: Option<&str>) -> Config {
+        let (name, args) = match s.find('=') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s, ""),
+        };
+        let args: Vec<String> = args.split_whitespace().map(|x| x.to_string()).collect();
+        let runner = match (args.first(), default_command) {
+            (Some(x), _) if !x.starts_with("--") => Runner::Process(args),
+            (_, Some(command)) => {
+                // nyat writes no proof in threads or cubes
+                let proof = !args.windows(2).any(|x| {
+                    (x[0] == "--threads" && x[1] != "1") || (x[0] == "--mode" && x[1] == "cube")
+                });
+                let mut res = vec![command.to_string()];
+                res.extend(args);
+                if proof {
+                    res.extend(["--proof".to_string(), PROOF.to_string()]);
+                }
+                Runner::Process(res)
+            }
+            (_, None) => {
+                let (mut seed, mut randomize, mut restart_interval) = (0, false, None);
+                let mut iter = args.iter();
+                while let Some(arg) = iter.next() {
+                    let mut value = || iter.next().expect("an option needs a number");
+                    match arg.as_str() {
+                        "--seed" => seed = value().parse().unwrap(),
+                        "--randomize" => randomize = true,
+                        "--restart-interval" => restart_interval = Some(value().parse().unwrap()),
+                        _ => panic!("unsupported option in a thread: {}", arg),
+                    }
+                }
+                Runner::Thread {
+                    seed,
+                    randomize,
+                    restart_interval,
+                }
+            }
+        };
+        assert!(!name.is_empty(), "a configuration needs a name");
+        Config {
+            name: name.to_string(),
+            runner,
+        }
+    }
+    /// Returns whether the runs write DRUP proofs.
+    pub fn writes_proofs(&self) -> bool {
+        match &self.runner {
+            Runner::Thread { .. } => true,
+            Runner::Process(args) => args.iter().any(|x| x == PROOF),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Sat,
+    Unsat,
+    /// timed out or gave no answer
+    Unknown,
+}
+
+impl Answer {
+    fn name(self) -> &'static str {
+        match self {
+            Answer::Sat => "SAT",
+            Answer::Unsat => "UNSAT",
+            Answer::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// the model satisfies the clauses, or the proof refutes them
+    Verified,
+    /// there is nothing to check, or an UNSAT answer of a configuration without proofs
+    /// or of a problem with XOR constraints
+    Unchecked,
+    /// the model or the proof is wrong, or an UNSAT answer has no proof that its configuration writes
+    Wrong,
+}
+
+impl Check {
+    fn name(self) -> &'static str {
+        match self {
+            Check::Verified => "verified",
+            Check::Unchecked => "unchecked",
+            Check::Wrong => "wrong",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub instance: String,
+    pub config: String,
+    pub answer: Answer,
+    pub check: Check,
+    /// wall-clock time up to the timeout, without the check
+    pub time: Duration,
+}
+
+impl Run {
+    /// Returns whether it answered, and the answer is not wrong.
+    pub fn solved(&self) -> bool {
+        self.answer != Answer::Unknown && self.check != Check::Wrong
+    }
+    /// Returns the time of a solved run, or twice the timeout.
+    pub fn par2(&self, timeout: Duration) -> f64 {
+        if self.solved() {
+            self.time.as_secs_f64()
+        } else {
+            2.0 * timeout.as_secs_f64()
+        }
+    }
+}
+
+/// Returns the CNF files of a directory, which end with `.cnf` or `.dimacs`, in the order of their paths.
+pub fn instances(dir: &Path) -> Vec<PathBuf> {
+    let mut res: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|x| {
+            x.is_file()
+                && x.extension()
+                    .is_some_and(|ext| ext == "cnf" || ext == "dimacs")
+        })
+        .collect();
+    res.sort();
+    res
+}
+
+/// Parses the answer and the model of the output of a solver.
+fn parse_output(s: &str) -> (Answer, Vec<i64>) {
+    let mut answer = Answer::Unknown;
+    let mut model = vec![];
+    for line in s.lines().map(|x| x.trim()) {
+        match line {
+            "SAT" | "s SATISFIABLE" => answer = Answer::Sat,
+            "UNSAT" | "s UNSATISFIABLE" => answer = Answer::Unsat,
+            _ if answer == Answer::Sat => {
+                let literals = line.strip_prefix('v').unwrap_or(line);
+                let literals: Result<Vec<i64>, _> =
+                    literals.split_whitespace().map(|x| x.parse()).collect();
+                if let Ok(literals) = literals {
+                    model.extend(literals);
+                }
+            }
+            _ => {}
+        }
+    }
+    (answer, model)
+}
+
+/// Checks a model of DIMACS literals, where the variables it misses are false.
+fn check_model(problem: &SatProblem, literals: &[i64]) -> Check {
+    let mut model = vec![false; problem.n_variables()];
+    for &x in literals {
+        if x != 0 && x.unsigned_abs() as usize <= model.len() {
+            model[x.unsigned_abs() as usize - 1] = x > 0;
+        }
+    }
+    let value = |x: i64| model[x.unsigned_abs() as usize - 1] == (x > 0);
+    let satisfied = problem
+        .clauses()
+        .all(|clause| clause.iter().any(|&x| value(x)))
+        && problem
+            .xors()
+            .all(|xor| xor.iter().filter(|&&x| value(x)).count() % 2 == 1);
+    if satisfied {
+        Check::Verified
+    } else {
+        Check::Wrong
+    }
+}
+
+/// Benchmark of configurations over CNF files with a timeout for every run, where `jobs` workers
+/// take the runs one by one. The models are checked by the clauses and the UNSAT answers by their
+/// DRUP proofs, which are not taken for the problems of XOR constraints.
+pub struct Bench {
+    configs: Vec<Config>,
+    timeout: Duration,
+    jobs: usize,
+    /// whether the UNSAT answers are checked by their proofs
+    proofs: bool,
+}
+
+impl Bench {
+    pub fn new(configs: Vec<Config>, timeout: Duration) -> Bench {
+        assert!(!configs.is_empty());
+        Bench {
+            configs,
+            timeout,
+            jobs: 1,
+            proofs: true,
+        }
+    }
+    pub fn set_jobs(&mut self, jobs: usize) {
+        assert!(jobs > 0);
+        self.jobs = jobs;
+    }
+    /// Sets whether the UNSAT answers are checked by their proofs, which is the default.
+    /// Without proofs, `{proof}` is left out of the arguments of a command with the `--proof` before it.
+    /// The models are checked either way.
+    pub fn set_proofs(&mut self, proofs: bool) {
+        self.proofs = proofs;
+    }
+    pub fn configs(&self) -> &[Config] {
+        &self.configs
+    }
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+    /// Runs every configuration over the instances, calls `on_run` as every run finishes,
+    /// and returns the runs by configuration and then by instance.
+    pub fn run<F: Fn(&Run) + Sync>(&self, instances: &[PathBuf], on_run: F) -> Vec<Run> {
+        let n_runs = self.configs.len() * instances.len();
+        let next = AtomicUsize::new(0);
+        let runs = Mutex::new(vec![None; n_runs]);
+        std::thread::scope(|scope| {
+            for _ in 0..self.jobs {
+                scope.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    if i >= n_runs {
+                        break;
+                    }
+                    let config = &self.configs[i / instances.len()];
+                    let run = self.run_one(config, &instances[i % instances.len()], i);
+                    on_run(&run);
+                    runs.lock().unwrap()[i] = Some(run);
+                });
+            }
+        });
+        runs.into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|x| x.unwrap())
+            .collect()
+    }
+    fn run_one(&self, config: &Config, instance: &Path, i: usize) -> Run {
+        let (answer, check, time) = match &config.runner {
+            Runner::Thread {
+                seed,
+                randomize,
+                restart_interval,
+            } => {
+                let start = Instant::now();
+                let problem =
+                    SatProblem::new_from_dimacs(&std::fs::read_to_string(instance).unwrap());
+                let prove = self.proofs && problem.xors().count() == 0;
+                let mut steps: Vec<(bool, Vec<i64>)> = vec![];
+                let deadline = start + self.timeout;
+                let (res, interrupted) = {
+                    let mut solver = SatSolver::new(&problem);
+                    solver.set_seed(*seed);
+                    if *randomize {
+                        solver.shuffle_decision_order();
+                        solver.randomize_phases();
+                    }
+                    if let Some(interval) = restart_interval {
+                        solver.set_restart_interval(*interval);
+                    }
+                    solver.set_terminate(move || Instant::now() >= deadline);
+                    if prove {
+                        solver.set_proof(|step| match step {
+                            ProofStep::Add(xs) => steps.push((false, xs.to_vec())),
+                            ProofStep::Delete(xs) => steps.push((true, xs.to_vec())),
+                        });
+                    }
+                    let res = solver.solve();
+                    (res, solver.interrupted())
+                };
+                let time = start.elapsed().min(self.timeout);
+                match res {
+                    _ if interrupted => (Answer::Unknown, Check::Unchecked, time),
+                    Some(model) => {
+                        let check = if problem.check_assignment(&model) {
+                            Check::Verified
+                        } else {
+                            Check::Wrong
+                        };
+                        (Answer::Sat, check, time)
+                    }
+                    None if prove => {
+                        let mut checker = DrupChecker::new(&problem);
+                        let mut valid = true;
+                        for (delete, literals) in &steps {
+                            if *delete {
+                                checker.delete(literals);
+                            } else if !checker.add(literals) {
+                                valid = false;
+                                break;
+                            }
+                        }
+                        let check = if valid && checker.is_refuted() {
+                            Check::Verified
+                        } else {
+                            Check::Wrong
+                        };
+                        (Answer::Unsat, check, time)
+                    }
+                    None => (Answer::Unsat, Check::Unchecked, time),
+                }
+            }
+            Runner::Process(command) => {
+                let proof = std::env::temp_dir().join(format!(
+                    "nyat-bench-{}-{}.drup",
+                    std::process::id(),
+                    i
+                ));
+                let mut args = vec![];
+                for (k, arg) in command.iter().enumerate().skip(1) {
+                    if arg == PROOF {
+                        if self.proofs {
+                            args.push(proof.display().to_string());
+                        } else if command[k - 1] == "--proof" {
+                            args.pop();
+                        }
+                    } else {
+                        args.push(arg.clone());
+                    }
+                }
+                let prove = self.proofs && config.writes_proofs();
+                let start = Instant::now();
+                let mut child = Command::new(&command[0])
+                    .args(&args)
+                    .arg(instance)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .unwrap_or_else(|e| panic!("failed to run {}: {}", command[0], e));
+                // the output is read meanwhile, so that the process never blocks on a full pipe
+                let mut stdout = child.stdout.take().unwrap();
+                let reader = std::thread::spawn(move || {
+                    let mut res = String::new();
+                    let _ = stdout.read_to_string(&mut res);
+                    res
+                });
+                let mut finished = false;
+                while start.elapsed() < self.timeout {
+                    if child.try_wait().unwrap().is_some() {
+                        finished = true;
+                        break;
+                    }
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+                if !finished {
+                    let _ = child.kill();
+                }
+                child.wait().unwrap();
+                let time = start.elapsed().min(self.timeout);
+                // the children of a killed process may keep the pipe open, so its output is left
+                let (answer, model) = if finished {
+                    parse_output(&reader.join().unwrap())
+                } else {
+                    (Answer::Unknown, vec![])
+                };
+                let check = match answer {
+                    Answer::Sat | Answer::Unsat => {
+                        let problem = SatProblem::new_from_dimacs(
+                            &std::fs::read_to_string(instance).unwrap(),
+                        );
+                        match std::fs::read_to_string(&proof) {
+                            _ if answer == Answer::Sat => check_model(&problem, &model),
+                            _ if !prove || problem.xors().count() > 0 => Check::Unchecked,
+                            Ok(text) if check_drup(&problem, &text) => Check::Verified,
+                            // a missing proof is an error as well
+                            _ => Check::Wrong,
+                        }
+                    }
+                    Answer::Unknown => Check::Unchecked,
+                };
+                let _ = std::fs::remove_file(&proof);
+                (answer, check, time)
+            }
+        };
+        Run {
+            instance: instance.display().to_string(),
+            config: config.name.clone(),
+            answer,
+            check,
+            time,
+        }
+    }
+}
+
+/// Returns the runs of a configuration.
+fn runs_of<'r>(runs: &'r [Run], config: &str) -> Vec<&'r Run> {
+    runs.iter().filter(|x| x.config == config).collect()
+}
+
+/// Returns the times of the solved runs in increasing order, which are the points of a cactus plot.
+fn cactus(runs: &[&Run]) -> Vec<f64> {
+    let mut res: Vec<f64> = runs
+        .iter()
+        .filter(|x| x.solved())
+        .map(|x| x.time.as_secs_f64())
+        .collect();
+    res.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    res
+}
+
+/// Returns the mean of the PAR-2 scores.
+fn par2(runs: &[&Run], timeout: Duration) -> f64 {
+    if runs.is_empty() {
+        0.0
+    } else {
+        runs.iter().map(|x| x.par2(timeout)).sum::<f64>() / runs.len() as f64
+    }
+}
+
+/// Formats a table of the solved runs, the wrong answers and the mean PAR-2 score of every configuration.
+pub fn summary(runs: &[Run], configs: &[Config], timeout: Duration) -> String {
+    let mut res = format!(
+        "{:<16} {:>7} {:>7} {:>7} {:>7} {:>7} {:>10}\n",
+        "config", "solved", "SAT", "UNSAT", "unknown", "wrong", "PAR-2"
+    );
+    for config in configs {
+        let xs = runs_of(runs, &config.name);
+        let count = |f: &dyn Fn(&Run) -> bool| xs.iter().filter(|&&x| f(x)).count();
+        res.push_str(&format!(
+            "{:<16} {:>7} {:>7} {:>7} {:>7} {:>7} {:>10.3}\n",
+            config.name,
+            count(&|x| x.solved()),
+            count(&|x| x.solved() && x.answer == Answer::Sat),
+            count(&|x| x.solved() && x.answer == Answer::Unsat),
+            count(&|x| x.answer == Answer::Unknown),
+            count(&|x| x.check == Check::Wrong),
+            par2(&xs, timeout)
+        ));
+    }
+    res
+}
+
+/// Compares two configurations instance by instance: the instances that only one of them solves,
+/// and the ones that either solves faster by the PAR-2 score.
+pub fn compare(runs: &[Run], a: &str, b: &str, timeout: Duration) -> String {
+    let (xs, ys) = (runs_of(runs, a), runs_of(runs, b));
+    let mut only = (vec![], vec![]);
+    let mut faster = (0, 0);
+    for x in &xs {
+        let y = match ys.iter().find(|y| y.instance == x.instance) {
+            Some(y) => y,
+            None => continue,
+        };
+        match (x.solved(), y.solved()) {
+            (true, false) => only.0.push(x.instance.as_str()),
+            (false, true) => only.1.push(x.instance.as_str()),
+            _ => {}
+        }
+        let (s, t) = (x.par2(timeout), y.par2(timeout));
+        if s < t {
+            faster.0 += 1;
+        } else if t < s {
+            faster.1 += 1;
+        }
+    }
+    let mut res = format!("{} vs {}\n", a, b);
+    res.push_str(&format!("faster: {} vs {}\n", faster.0, faster.1));
+    res.push_str(&format!(
+        "PAR-2: {:.3} vs {:.3}\n",
+        par2(&xs, timeout),
+        par2(&ys, timeout)
+    ));
+    for (name, instances) in [(a, &only.0), (b, &only.1)].iter() {
+        for instance in instances.iter() {
+            res.push_str(&format!("only {}: {}\n", name, instance));
+        }
+    }
+    res
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains(',') || s.contains('"') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Formats every run as a line of CSV with a header.
+pub fn to_csv(runs: &[Run]) -> String {
+    let mut res = String::from("instance,config,answer,check,time\n");
+    for run in runs {
+        res.push_str(&format!(
+            "{},{},{},{},{}\n",
+            csv_field(&run.instance),
+            csv_field(&run.config),
+            run.answer.name(),
+            run.check.name(),
+            run.time.as_secs_f64()
+        ));
+    }
+    res
+}
+
+/// Formats the runs and the cactus data of every configuration, which is its sorted times of the solved runs.
+pub fn to_json(runs: &[Run], configs: &[Config], timeout: Duration) -> String {
+    let mut res = String::new();
+    res.push_str("{\n");
+    res.push_str(&format!("  \"timeout\": {},\n", timeout.as_secs_f64()));
+    res.push_str("  \"configs\": [\n");
+    for (i, config) in configs.iter().enumerate() {
+        let xs = runs_of(runs, &config.name);
+        let cactus: Vec<String> = cactus(&xs).iter().map(|x| x.to_string()).collect();
+        res.push_str(&format!(
+            "    {{\"name\": {}, \"solved\": {}, \"par2\": {}, \"cactus\": [{}]}}{}\n",
+            json_string(&config.name),
+            xs.iter().filter(|x| x.solved()).count(),
+            par2(&xs, timeout),
+            cactus.join(", "),
+            if i + 1 < configs.len() { "," } else { "" }
+        ));
+    }
+    res.push_str("  ],\n");
+    res.push_str("  \"runs\": [\n");
+    for (i, run) in runs.iter().enumerate() {
+        res.push_str(&format!(
+            "    {{\"instance\": {}, \"config\": {}, \"answer\": \"{}\", \"check\": \"{}\", \"time\": {}}}{}\n",
+            json_string(&run.instance),
+            json_string(&run.config),
+            run.answer.name(),
+            run.check.name(),
+            run.time.as_secs_f64(),
+            if i + 1 < runs.len() { "," } else { "" }
+        ));
+    }
+    res.push_str("  ]\n");
+    res.push_str("}\n");
+    res
+}
+
+/// Returns a new directory for the instances of a test.
+#[cfg(test)]
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nyat-bench-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_parse_config() {
+    let thread = |seed, randomize, restart_interval| Runner::Thread {
+        seed,
+        randomize,
+        restart_interval,
+    };
+    assert_eq!(Config::parse("a", None).runner, thread(0, false, None));
+    let config = Config::parse("b=--seed 3 --randomize --restart-interval 50", None);
+    assert_eq!(config.name, "b");
+    assert_eq!(config.runner, thread(3, true, Some(50)));
+    let process = |xs: &[&str]| Runner::Process(xs.iter().map(|x| x.to_string()).collect());
+    assert_eq!(
+        Config::parse("c=--seed 3", Some("./nyat")).runner,
+        process(&["./nyat", "--seed", "3", "--proof", "{proof}"])
+    );
+    assert_eq!(
+        Config::parse("d=/bin/solver -q", Some("./nyat")).runner,
+        process(&["/bin/solver", "-q"])
+    );
+    // nyat writes no proofs in threads or cubes
+    assert!(Config::parse("e=--threads 1", Some("./nyat")).writes_proofs());
+    assert!(!Config::parse("f=--threads 4", Some("./nyat")).writes_proofs());
+    assert!(!Config::parse("g=--mode cube", Some("./nyat")).writes_proofs());
+    assert!(!Config::parse("h=/bin/solver -q", None).writes_proofs());
+    assert!(Config::parse("i=/bin/solver -q {proof}", None).writes_proofs());
+}
+
+#[test]
+fn test_parse_output() {
+    assert_eq!(
+        parse_output("c stats\nSAT\n1 -2 0\n"),
+        (Answer::Sat, vec![1, -2, 0])
+    );
+    assert_eq!(
+        parse_output("s SATISFIABLE\nv 1 -2\nv 3 0\n"),
+        (Answer::Sat, vec![1, -2, 3, 0])
+    );
+    assert_eq!(parse_output("s UNSATISFIABLE\n"), (Answer::Unsat, vec![]));
+    assert_eq!(parse_output("c interrupted\n"), (Answer::Unknown, vec![]));
+}
+
+#[test]
+fn test_bench_in_threads() {
+    let dir = test_dir("threads");
+    // 4 pigeons in 3 holes, and 2 pigeons in 2 holes
+    let sat = "p cnf 4 4\n1 2 0\n3 4 0\n-1 -3 0\n-2 -4 0\n";
+    let unsat =
+        "p cnf 6 9\n1 2 0\n3 4 0\n5 6 0\n-1 -3 0\n-1 -5 0\n-3 -5 0\n-2 -4 0\n-2 -6 0\n-4 -6 0\n";
+    std::fs::write(dir.join("sat.cnf"), sat).unwrap();
+    std::fs::write(dir.join("unsat.dimacs"), unsat).unwrap();
+    std::fs::write(dir.join("notes.txt"), "not an instance").unwrap();
+    let instances = instances(&dir);
+    assert_eq!(instances.len(), 2);
+    let configs = vec![
+        Config::parse("default", None),
+        Config::parse("random=--seed 1 --randomize", None),
+    ];
+    let timeout = Duration::from_secs(10);
+    let mut bench = Bench::new(configs.clone(), timeout);
+    bench.set_jobs(2);
+    let n_calls = AtomicUsize::new(0);
+    let runs = bench.run(&instances, |_| {
+        n_calls.fetch_add(1, Ordering::SeqCst);
+    });
+    assert_eq!(n_calls.into_inner(), 4);
+    assert_eq!(runs.len(), 4);
+    assert_eq!(runs[1].config, "default");
+    assert_eq!(runs[2].config, "random");
+    for run in &runs {
+        let expected = if run.instance.ends_with("sat.cnf") {
+            Answer::Sat
+        } else {
+            Answer::Unsat
+        };
+        assert_eq!(run.answer, expected);
+        assert_eq!(run.check, Check::Verified);
+    }
+    assert!(summary(&runs, &configs, timeout).contains("default"));
+    assert!(compare(&runs, "default", "random", timeout).starts_with("default vs random\n"));
+    let csv = to_csv(&runs);
+    assert_eq!(csv.lines().count(), 5);
+    assert!(csv
+        .lines()
+        .nth(2)
+        .unwrap()
+        .contains(",default,UNSAT,verified,"));
+    let json = to_json(&runs, &configs, timeout);
+    assert!(json.contains("\"name\": \"random\", \"solved\": 2,"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_bench_in_processes() {
+    let dir = test_dir("processes");
+    // every assignment of x1 and x2 falsifies a clause
+    let unsat = "p cnf 2 4\n1 2 0\n1 -2 0\n-1 2 0\n-1 -2 0\n";
+    std::fs::write(dir.join("unsat.cnf"), unsat).unwrap();
+    let instances = instances(&dir);
+    // the path of the proof, if there is one, and the instance are the arguments of the script
+    let script = |name: &str, script: &str, proof: bool| {
+        let mut args = vec!["sh", "-c", script, "sh"];
+        if proof {
+            args.extend(["--proof", "{proof}"]);
+        }
+        Config {
+            name: name.to_string(),
+            runner: Runner::Process(args.iter().map(|x| x.to_string()).collect()),
+        }
+    };
+    let configs = vec![
+        script("proof", "printf '1 0\\n0\\n' > \"$2\"; echo UNSAT", true),
+        script("no-proof", "echo UNSAT", true),
+        script("wrong-proof", "echo 0 > \"$2\"; echo UNSAT", true),
+        script("without-proofs", "echo UNSAT", false),
+        script("wrong-model", "echo 's SATISFIABLE'; echo 'v 1 0'", false),
+        script("timeout", "sleep 5", false),
+    ];
+    let timeout = Duration::from_millis(200);
+    let mut bench = Bench::new(configs.clone(), timeout);
+    let runs = bench.run(&instances, |_| {});
+    let results: Vec<(Answer, Check)> = runs.iter().map(|x| (x.answer, x.check)).collect();
+    assert_eq!(
+        results,
+        vec![
+            (Answer::Unsat, Check::Verified),
+            (Answer::Unsat, Check::Wrong),
+            (Answer::Unsat, Check::Wrong),
+            (Answer::Unsat, Check::Unchecked),
+            (Answer::Sat, Check::Wrong),
+            (Answer::Unknown, Check::Unchecked),
+        ]
+    );
+    assert_eq!(runs[5].time, timeout);
+    assert_eq!(runs[5].par2(timeout), 0.4);
+    assert!(summary(&runs, &configs, timeout).contains("proof"));
+    // without proofs, `--proof {proof}` is left out, and the models are still checked
+    bench.set_proofs(false);
+    let runs = bench.run(&instances, |_| {});
+    let results: Vec<(Answer, Check)> = runs.iter().map(|x| (x.answer, x.check)).collect();
+    assert_eq!(results[1], (Answer::Unsat, Check::Unchecked));
+    assert_eq!(results[4], (Answer::Sat, Check::Wrong));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,116 @@
+use nyat_bench::bench::*;
+use std::path::PathBuf;
+use std::time::Duration;
+
+struct Options {
+    dir: String,
+    timeout: Duration,
+    jobs: usize,
+    /// `name=args` of every configuration
+    configs: Vec<String>,
+    /// whether the configurations run in worker processes of nyat
+    processes: bool,
+    /// whether the UNSAT answers are checked by their proofs
+    proofs: bool,
+    csv: Option<String>,
+    json: Option<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Options {
+        let mut options = Options {
+            dir: String::new(),
+            timeout: Duration::from_secs(60),
+            jobs: 1,
+            configs: vec![],
+            processes: false,
+            proofs: true,
+            csv: None,
+            json: None,
+        };
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            let mut value = |name: &str| -> String {
+                iter.next()
+                    .unwrap_or_else(|| panic!("{} needs a value", name))
+                    .clone()
+            };
+            match arg.as_str() {
+                "--timeout" => {
+                    options.timeout = Duration::from_secs_f64(value(arg).parse().unwrap())
+                }
+                "--jobs" => options.jobs = value(arg).parse().unwrap(),
+                "--config" => options.configs.push(value(arg)),
+                "--processes" => options.processes = true,
+                "--no-proofs" => options.proofs = false,
+                "--csv" => options.csv = Some(value(arg)),
+                "--json" => options.json = Some(value(arg)),
+                _ => {
+                    assert!(options.dir.is_empty(), "unexpected argument: {}", arg);
+                    options.dir = arg.clone();
+                }
+            }
+        }
+        assert!(!options.dir.is_empty(), "no directory of instances");
+        if options.configs.is_empty() {
+            options.configs.push("nyat=".to_string());
+        }
+        options
+    }
+}
+
+/// Returns the nyat binary next to this one.
+fn nyat_command() -> String {
+    let mut path: PathBuf = std::env::current_exe().unwrap();
+    path.set_file_name(format!("nyat{}", std::env::consts::EXE_SUFFIX));
+    path.display().to_string()
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let options = Options::parse(&args);
+    let command = if options.processes {
+        Some(nyat_command())
+    } else {
+        None
+    };
+    let configs: Vec<Config> = options
+        .configs
+        .iter()
+        .map(|x| Config::parse(x, command.as_deref()))
+        .collect();
+    let instances = instances(options.dir.as_ref());
+    let mut bench = Bench::new(configs, options.timeout);
+    bench.set_jobs(options.jobs);
+    bench.set_proofs(options.proofs);
+    let runs = bench.run(&instances, |run| {
+        println!(
+            "{} {} {:?} {:?} {:.3}",
+            run.config,
+            run.instance,
+            run.answer,
+            run.check,
+            run.time.as_secs_f64()
+        )
+    });
+    println!();
+    print!("{}", summary(&runs, bench.configs(), bench.timeout()));
+    for config in &bench.configs()[1..] {
+        println!();
+        print!(
+            "{}",
+            compare(
+                &runs,
+                &bench.configs()[0].name,
+                &config.name,
+                bench.timeout()
+            )
+        );
+    }
+    if let Some(path) = &options.csv {
+        std::fs::write(path, to_csv(&runs)).unwrap();
+    }
+    if let Some(path) = &options.json {
+        std::fs::write(path, to_json(&runs, bench.configs(), bench.timeout())).unwrap();
+    }
+}
@@ -0,0 +1,301 @@
+use nyat_sat::aiger::Aig;
+use nyat_sat::encodings::TseitinEncoding;
+use nyat_sat::sat::{SatAssignments, SatSolver};
+
+/// Counterexample of a safety property: the initial values of the latches and the inputs of every step,
+/// after which the bad state property is true in the last step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    /// index of the bad state property
+    pub property: usize,
+    pub latches: Vec<bool>,
+    pub inputs: Vec<Vec<bool>>,
+}
+
+impl Trace {
+    /// Formats the trace as an AIGER witness.
+    pub fn to_witness(&self) -> String {
+        let bits =
+            |xs: &[bool]| -> String { xs.iter().map(|&x| if x { '1' } else { '0' }).collect() };
+        let mut res = format!("1\nb{}\n{}\n", self.property, bits(&self.latches));
+        for inputs in &self.inputs {
+            res.push_str(&bits(inputs));
+            res.push('\n');
+        }
+        res.push_str(".\n");
+        res
+    }
+}
+
+#[derive(Debug)]
+pub enum BmcResult {
+    Counterexample(Trace),
+    /// no bad state is reachable within the bound
+    BoundReached,
+}
+
+/// Bounded model checker, which unrolls the circuit into a single solver one frame at a time,
+/// and checks every bound by assuming a bad state property in its last frame.
+/// The properties are the bad state properties, or the outputs if there are none.
+pub struct Bmc<'a> {
+    aig: Aig,
+    properties: Vec<u64>,
+    solver: SatSolver<'a>,
+    /// DIMACS literal of every variable in every frame, which is false for the constants
+    frames: Vec<Vec<i64>>,
+    /// literal of every frame that enables its invariant constraints and those of the frames before
+    activations: Vec<i64>,
+    /// false variable
+    constant: i64,
+}
+
+impl<'a> Bmc<'a> {
+    pub fn new(aig: &Aig) -> Bmc<'a> {
+        let mut solver = SatSolver::new(&nyat_sat::sat::SatProblem::new(0));
+        let constant = solver.new_variable();
+        solver.add_clause(&[-constant]);
+        let properties = if aig.bad.is_empty() {
+            aig.outputs.clone()
+        } else {
+            aig.bad.clone()
+        };
+        Bmc {
+            aig: aig.clone(),
+            properties,
+            solver,
+            frames: vec![],
+            activations: vec![],
+            constant,
+        }
+    }
+    pub fn n_frames(&self) -> usize {
+        self.frames.len()
+    }
+    fn literal(&self, frame: usize, x: u64) -> i64 {
+        let y = self.frames[frame][x as usize / 2];
+        if x & 1 == 1 {
+            -y
+        } else {
+            y
+        }
+    }
+    /// Adds the frame of the next step, where the latches are the next states of the last frame,
+    /// or their initial values in the first one. The invariant constraints of the frame hold
+    /// when its activation literal is assumed, which implies those of the frames before.
+    pub fn add_frame(&mut self) {
+        let mut frame = vec![self.constant; self.aig.max_variable as usize + 1];
+        for &x in &self.aig.inputs {
+            frame[x as usize / 2] = self.solver.new_variable();
+        }
+        let k = self.frames.len();
+        for latch in &self.aig.latches {
+            frame[latch.literal as usize / 2] = match (k, latch.reset) {
+                (0, Some(false)) => self.constant,
+                (0, Some(true)) => -self.constant,
+                (0, None) => self.solver.new_variable(),
+                _ => self.literal(k - 1, latch.next),
+            };
+        }
+        for gate in &self.aig.ands {
+            frame[gate.lhs as usize / 2] = self.solver.new_variable();
+        }
+        // the next states are substituted into the next frame in either polarity
+        let mut roots: Vec<u64> = self.properties.clone();
+        roots.extend(&self.aig.constraints);
+        roots.extend(self.aig.latches.iter().map(|x| x.next));
+        self.aig
+            .encode(&mut self.solver, &frame, &roots, TseitinEncoding::Full);
+        self.frames.push(frame);
+        let activation = self.solver.new_variable();
+        if k > 0 {
+            self.solver
+                .add_clause(&[-activation, self.activations[k - 1]]);
+        }
+        self.activations.push(activation);
+        for i in 0..self.aig.constraints.len() {
+            let x = self.literal(k, self.aig.constraints[i]);
+            self.solver.add_clause(&[-activation, x]);
+        }
+    }
+    /// Returns a trace to a bad state in exactly `k` steps, adding the frames up to `k` if needed.
+    /// Only the invariant constraints of the frames up to `k` are assumed, so the bounds may be checked in any order.
+    /// A property that is refuted at the bound stays false there under them, as a clause for the later checks.
+    pub fn check(&mut self, k: usize) -> Option<Trace> {
+        while self.frames.len() <= k {
+            self.add_frame();
+        }
+        let activation = self.activations[k];
+        for property in 0..self.properties.len() {
+            let bad = self.literal(k, self.properties[property]);
+            match self.solver.solve_with_assumptions(&[activation, bad]) {
+                Some(model) => return Some(self.trace(property, k, &model)),
+                None => self.solver.add_clause(&[-activation, -bad]),
+            }
+        }
+        None
+    }
+    /// Checks the bounds from 0 to `max_bound` in order, so a counterexample is one of the shortest.
+    pub fn run(&mut self, max_bound: usize) -> BmcResult {
+        for k in 0..=max_bound {
+            if let Some(trace) = self.check(k) {
+                return BmcResult::Counterexample(trace);
+            }
+        }
+        BmcResult::BoundReached
+    }
+    fn trace(&self, property: usize, k: usize, model: &SatAssignments) -> Trace {
+        let value = |x: i64| model[x.unsigned_abs() as usize - 1] == (x > 0);
+        Trace {
+            property,
+            latches: self
+                .aig
+                .latches
+                .iter()
+                .map(|x| value(self.literal(0, x.literal)))
+                .collect(),
+            inputs: (0..=k)
+                .map(|t| {
+                    self.aig
+                        .inputs
+                        .iter()
+                        .map(|&x| value(self.literal(t, x)))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Returns whether the trace reaches a bad state of its property in its last step,
+/// under the invariant constraints in every step.
+#[cfg(test)]
+fn simulate(aig: &Aig, properties: &[u64], trace: &Trace) -> bool {
+    let value = |values: &[bool], x: u64| values[x as usize / 2] != (x & 1 == 1);
+    let mut latches = trace.latches.clone();
+    for (t, inputs) in trace.inputs.iter().enumerate() {
+        let values = aig.evaluate(inputs, &latches);
+        if !aig.constraints.iter().all(|&x| value(&values, x)) {
+            return false;
+        }
+        if t + 1 == trace.inputs.len() {
+            return value(&values, properties[trace.property]);
+        }
+        latches = aig.latches.iter().map(|x| value(&values, x.next)).collect();
+    }
+    false
+}
+
+#[test]
+fn test_counter() {
+    // a 2-bit counter that counts the steps with the input, and is bad at 3
+    let s = "aag 11 1 2 0 7 1\n2\n4 14\n6 20\n22\n\
+             8 4 2\n12 5 3\n14 9 13\n16 6 8\n18 7 9\n20 17 19\n22 4 6\n";
+    let aig = Aig::parse(s.as_bytes());
+    let mut bmc = Bmc::new(&aig);
+    for k in 0..3 {
+        assert!(bmc.check(k).is_none());
+    }
+    let trace = bmc.check(3).unwrap();
+    assert_eq!(trace.latches, vec![false, false]);
+    assert_eq!(&trace.inputs[..3], &[vec![true], vec![true], vec![true]]);
+    assert!(simulate(&aig, &aig.bad, &trace));
+    assert!(trace.to_witness().starts_with("1\nb0\n00\n1\n1\n1\n"));
+    assert!(trace.to_witness().ends_with("\n.\n"));
+    match Bmc::new(&aig).run(2) {
+        BmcResult::BoundReached => {}
+        x => panic!("{:?}", x),
+    }
+}
+
+#[test]
+fn test_check_ignores_later_frames() {
+    // the input is bad, and the latch of the last input is constrained to be false
+    let aig = Aig::parse("aag 2 1 1 0 0 1 1\n2\n4 2\n2\n5\n".as_bytes());
+    let mut bmc = Bmc::new(&aig);
+    bmc.add_frame();
+    bmc.add_frame();
+    let trace = bmc.check(0).unwrap();
+    assert!(simulate(&aig, &aig.bad, &trace));
+    assert!(bmc.check(1).is_some());
+}
+
+#[test]
+fn test_bmc_against_explicit_states() {
+    use nyat_sat::aiger::{AndGate, Latch};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashSet;
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..50 {
+        // 2 inputs, 3 latches and random gates
+        let n_gates = rng.gen_range(1, 10);
+        let max_variable = 5 + n_gates;
+        let n_literals = 2 * max_variable + 2;
+        let mut aig = Aig {
+            max_variable,
+            inputs: vec![2, 4],
+            latches: vec![],
+            outputs: vec![],
+            bad: vec![],
+            constraints: vec![],
+            ands: vec![],
+        };
+        for v in 6..=max_variable {
+            aig.ands.push(AndGate {
+                lhs: 2 * v,
+                rhs0: rng.gen_range(0, 2 * v),
+                rhs1: rng.gen_range(0, 2 * v),
+            });
+        }
+        for i in 0..3 {
+            let reset = [Some(false), Some(true), None][rng.gen_range(0, 3)];
+            aig.latches.push(Latch {
+                literal: 6 + 2 * i,
+                next: rng.gen_range(0, n_literals),
+                reset,
+            });
+        }
+        aig.bad.push(rng.gen_range(2, n_literals));
+        // the shortest depth of a bad state by breadth-first search over the states
+        let value = |values: &[bool], x: u64| values[x as usize / 2] != (x & 1 == 1);
+        let mut states: HashSet<Vec<bool>> = HashSet::new();
+        for bits in 0..8u32 {
+            let state: Vec<bool> = (0..3).map(|i| bits >> i & 1 == 1).collect();
+            if aig
+                .latches
+                .iter()
+                .zip(&state)
+                .all(|(x, &y)| x.reset != Some(!y))
+            {
+                states.insert(state);
+            }
+        }
+        let mut expected = None;
+        let mut seen = states.clone();
+        for depth in 0..10 {
+            let mut next = HashSet::new();
+            for state in &states {
+                for bits in 0..4u32 {
+                    let inputs = vec![bits & 1 == 1, bits & 2 == 2];
+                    let values = aig.evaluate(&inputs, state);
+                    if value(&values, aig.bad[0]) && expected.is_none() {
+                        expected = Some(depth);
+                    }
+                    let successor: Vec<bool> =
+                        aig.latches.iter().map(|x| value(&values, x.next)).collect();
+                    if seen.insert(successor.clone()) {
+                        next.insert(successor);
+                    }
+                }
+            }
+            states = next;
+        }
+        match Bmc::new(&aig).run(9) {
+            BmcResult::Counterexample(trace) => {
+                assert_eq!(Some(trace.inputs.len() - 1), expected);
+                assert!(simulate(&aig, &aig.bad, &trace));
+            }
+            BmcResult::BoundReached => assert_eq!(expected, None),
+        }
+    }
+}
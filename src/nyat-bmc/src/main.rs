@@ -0,0 +1,58 @@
+extern crate env_logger;
+
+use nyat_bmc::bmc::Bmc;
+use nyat_sat::aiger::Aig;
+
+struct Options {
+    input: String,
+    max_bound: usize,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Options {
+        let mut input = None;
+        let mut max_bound = 100;
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--max-bound" => {
+                    let n = iter.next().expect("--max-bound needs a number");
+                    max_bound = n.parse::<usize>().unwrap();
+                }
+                _ => {
+                    assert!(input.is_none(), "unexpected argument: {}", arg);
+                    input = Some(arg.clone());
+                }
+            }
+        }
+        Options {
+            input: input.expect("no input file"),
+            max_bound,
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().collect();
+    let options = Options::parse(&args);
+    let bytes = std::fs::read(options.input.as_str()).unwrap();
+    let aig = Aig::parse(&bytes);
+    println!(
+        "c {} inputs, {} latches, {} gates",
+        aig.inputs.len(),
+        aig.latches.len(),
+        aig.ands.len()
+    );
+    let mut bmc = Bmc::new(&aig);
+    for k in 0..=options.max_bound {
+        if let Some(trace) = bmc.check(k) {
+            print!("{}", trace.to_witness());
+            return;
+        }
+        println!("c no counterexample of length {}", k);
+    }
+    // unknown, since only the bounds are checked
+    println!("2\nb0\n.");
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+/// Unsigned integer of arbitrary size, stored as little endian 32-bit limbs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigUint(Vec<u32>);
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint(vec![])
+    }
+    pub fn one() -> BigUint {
+        BigUint::from_u64(1)
+    }
+    pub fn from_u64(x: u64) -> BigUint {
+        let mut res = BigUint(vec![x as u32, (x >> 32) as u32]);
+        res.normalize();
+        res
+    }
+    /// Returns 2^k.
+    pub fn pow2(k: usize) -> BigUint {
+        let mut xs = vec![0; k / 32 + 1];
+        xs[k / 32] = 1 << (k % 32);
+        BigUint(xs)
+    }
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+    fn normalize(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+    pub fn add(&self, other: &BigUint) -> BigUint {
+        let n = self.0.len().max(other.0.len());
+        let mut xs = Vec::with_capacity(n + 1);
+        let mut carry = 0u64;
+        for i in 0..n {
+            let a = *self.0.get(i).unwrap_or(&0) as u64;
+            let b = *other.0.get(i).unwrap_or(&0) as u64;
+            let t = a + b + carry;
+            xs.push(t as u32);
+            carry = t >> 32;
+        }
+        xs.push(carry as u32);
+        let mut res = BigUint(xs);
+        res.normalize();
+        res
+    }
+    pub fn mul(&self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+        let mut xs = vec![0u32; self.0.len() + other.0.len()];
+        for (i, &a) in self.0.iter().enumerate() {
+            let mut carry = 0u64;
+            for (k, &b) in other.0.iter().enumerate() {
+                let t = xs[i + k] as u64 + a as u64 * b as u64 + carry;
+                xs[i + k] = t as u32;
+                carry = t >> 32;
+            }
+            xs[i + other.0.len()] = carry as u32;
+        }
+        let mut res = BigUint(xs);
+        res.normalize();
+        res
+    }
+    /// Divides in place by a small number and returns the remainder.
+    fn div_rem_small(&mut self, d: u32) -> u32 {
+        let mut rem = 0u64;
+        for x in self.0.iter_mut().rev() {
+            let t = (rem << 32) | *x as u64;
+            *x = (t / d as u64) as u32;
+            rem = t % d as u64;
+        }
+        self.normalize();
+        rem as u32
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut x = self.clone();
+        let mut digits = vec![];
+        while !x.is_zero() {
+            digits.push(x.div_rem_small(1_000_000_000));
+        }
+        write!(f, "{}", digits.pop().unwrap())?;
+        for d in digits.iter().rev() {
+            write!(f, "{:09}", d)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_biguint_arithmetic() {
+    let a = BigUint::from_u64(u64::MAX);
+    let b = a.add(&BigUint::one());
+    assert_eq!(b, BigUint::pow2(64));
+    assert_eq!(b.to_string(), "18446744073709551616");
+    assert_eq!(
+        b.mul(&b).to_string(),
+        "340282366920938463463374607431768211456"
+    );
+    assert_eq!(BigUint::pow2(100).mul(&BigUint::zero()), BigUint::zero());
+    assert_eq!(BigUint::zero().to_string(), "0");
+    assert_eq!(BigUint::from_u64(1_000_000_000).to_string(), "1000000000");
+}
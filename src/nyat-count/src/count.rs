@@ -0,0 +1,376 @@
+use crate::bigint::BigUint;
+use nyat_sat::sat::{SatProblem, SatSolver};
+use std::collections::{HashMap, HashSet};
+
+/// Exact model counter that branches on variables, splits the residual formula
+/// into independent components and caches the count of every component it solves.
+/// Components with no models are cached as well, so a failed subformula is not searched again.
+/// The decisions are propagated by `SatSolver`, which learns a clause from every conflict.
+/// The learnt clauses only propagate, and the components are made of the original clauses.
+pub struct ModelCounter {
+    clauses: Vec<Vec<i64>>,
+    occurrences: Vec<Vec<usize>>,
+    projected: Vec<bool>,
+    values: Vec<Option<bool>>,
+    trail: Vec<usize>,
+    /// literals assigned by `propagate` whose implications are on the trail
+    decisions: Vec<i64>,
+    solver: SatSolver<'static>,
+    cache: HashMap<Vec<i64>, BigUint>,
+    /// keys in the order in which they are cached
+    cached: Vec<Vec<i64>>,
+    n_decisions: usize,
+    n_cache_hits: usize,
+    n_learnt_clauses: usize,
+}
+
+fn var_of(literal: i64) -> usize {
+    literal.unsigned_abs() as usize - 1
+}
+
+impl ModelCounter {
+    pub fn new(problem: &SatProblem, projection: Option<&[usize]>) -> ModelCounter {
+        // the components are split by clauses, so the XOR constraints are expanded into them,
+        // whose fresh variables are determined by the others and change no count
+        let problem = &problem.expand_xors();
+        let n_variables = problem.n_variables();
+        let clauses: Vec<Vec<i64>> = problem.clauses().collect();
+        let mut occurrences = vec![vec![]; n_variables];
+        for (clause_id, clause) in clauses.iter().enumerate() {
+            for &literal in clause {
+                if !occurrences[var_of(literal)].contains(&clause_id) {
+                    occurrences[var_of(literal)].push(clause_id);
+                }
+            }
+        }
+        let projected = match projection {
+            Some(projection) => {
+                let mut projected = vec![false; n_variables];
+                for &id in projection {
+                    projected[id] = true;
+                }
+                projected
+            }
+            None => vec![true; n_variables],
+        };
+        ModelCounter {
+            clauses,
+            occurrences,
+            projected,
+            values: vec![None; n_variables],
+            trail: vec![],
+            decisions: vec![],
+            solver: SatSolver::new(problem),
+            cache: HashMap::new(),
+            cached: vec![],
+            n_decisions: 0,
+            n_cache_hits: 0,
+            n_learnt_clauses: 0,
+        }
+    }
+    pub fn n_decisions(&self) -> usize {
+        self.n_decisions
+    }
+    pub fn n_cache_hits(&self) -> usize {
+        self.n_cache_hits
+    }
+    pub fn n_learnt_clauses(&self) -> usize {
+        self.n_learnt_clauses
+    }
+    fn value(&self, literal: i64) -> Option<bool> {
+        self.values[var_of(literal)].map(|x| x == (literal > 0))
+    }
+    fn is_satisfied(&self, clause_id: usize) -> bool {
+        self.clauses[clause_id]
+            .iter()
+            .any(|&x| self.value(x) == Some(true))
+    }
+    fn assign(&mut self, literal: i64) {
+        self.values[var_of(literal)] = Some(literal > 0);
+        self.trail.push(var_of(literal));
+    }
+    fn undo(&mut self, trail_len: usize) {
+        while self.trail.len() > trail_len {
+            let id = self.trail.pop().unwrap();
+            self.values[id] = None;
+        }
+        let values = &self.values;
+        self.decisions.retain(|&x| values[var_of(x)].is_some());
+    }
+    /// Assigns the literals with the decisions so far, and propagates them with the solver.
+    /// Returns false on a conflict, after the solver learns the negation of the decisions on which it depends.
+    fn propagate(&mut self, literals: &[i64]) -> bool {
+        self.decisions.extend_from_slice(literals);
+        match self.solver.propagate(&self.decisions) {
+            Ok(implied) => {
+                for literal in implied {
+                    if self.value(literal).is_none() {
+                        self.assign(literal);
+                    }
+                }
+                true
+            }
+            Err(core) => {
+                let clause: Vec<i64> = core.iter().map(|&x| -x).collect();
+                self.solver.add_clause(&clause);
+                self.n_learnt_clauses += 1;
+                false
+            }
+        }
+    }
+    /// Counts the models over `variables` of the clauses that are not satisfied yet.
+    fn count_variables(&mut self, variables: &[usize]) -> BigUint {
+        let mut res = BigUint::one();
+        let mut seen_variables = HashSet::new();
+        let mut seen_clauses = HashSet::new();
+        let mut n_free = 0;
+        let n_cached = self.cached.len();
+        for &id in variables {
+            if self.values[id].is_some() || seen_variables.contains(&id) {
+                continue;
+            }
+            // collect the component of `id`
+            let mut component = vec![id];
+            let mut clause_ids = vec![];
+            seen_variables.insert(id);
+            let mut head = 0;
+            while head < component.len() {
+                let x = component[head];
+                head += 1;
+                for &clause_id in &self.occurrences[x] {
+                    if seen_clauses.contains(&clause_id) || self.is_satisfied(clause_id) {
+                        continue;
+                    }
+                    seen_clauses.insert(clause_id);
+                    clause_ids.push(clause_id);
+                    for &literal in &self.clauses[clause_id] {
+                        let y = var_of(literal);
+                        if self.values[y].is_none() && seen_variables.insert(y) {
+                            component.push(y);
+                        }
+                    }
+                }
+            }
+            if clause_ids.is_empty() {
+                if self.projected[id] {
+                    n_free += 1;
+                }
+                continue;
+            }
+            let position = self.cached.len();
+            let count = self.count_component(&component, clause_ids);
+            if count.is_zero() {
+                // the learnt clauses may cut the models of the components counted before, which is sound
+                // only if this one has models, so their counts and the ones under them are not kept
+                for key in self.cached.drain(n_cached..position) {
+                    self.cache.remove(&key);
+                }
+                return count;
+            }
+            res = res.mul(&count);
+        }
+        res.mul(&BigUint::pow2(n_free))
+    }
+    fn component_key(&self, clause_ids: &[usize]) -> Vec<i64> {
+        let mut reduced: Vec<Vec<i64>> = clause_ids
+            .iter()
+            .map(|&clause_id| {
+                let mut xs: Vec<i64> = self.clauses[clause_id]
+                    .iter()
+                    .cloned()
+                    .filter(|&x| self.value(x).is_none())
+                    .collect();
+                xs.sort();
+                xs
+            })
+            .collect();
+        reduced.sort();
+        reduced.dedup();
+        let mut key = vec![];
+        for xs in reduced {
+            key.extend(xs);
+            key.push(0);
+        }
+        key
+    }
+    fn is_satisfiable(&self, key: &[i64]) -> bool {
+        let mut problem = SatProblem::new(0);
+        let mut ids = HashMap::new();
+        let mut clause = vec![];
+        for &x in key {
+            if x == 0 {
+                problem.add_clause(&clause);
+                clause.clear();
+                continue;
+            }
+            let n = ids.len() as i64;
+            let id = *ids.entry(var_of(x)).or_insert(n + 1);
+            clause.push(if x > 0 { id } else { -id });
+        }
+        let mut solver = SatSolver::new(&problem);
+        solver.solve().is_some()
+    }
+    fn count_component(&mut self, component: &[usize], clause_ids: Vec<usize>) -> BigUint {
+        let key = self.component_key(&clause_ids);
+        if let Some(count) = self.cache.get(&key) {
+            self.n_cache_hits += 1;
+            return count.clone();
+        }
+        let res = if component.iter().all(|&x| !self.projected[x]) {
+            if self.is_satisfiable(&key) {
+                BigUint::one()
+            } else {
+                BigUint::zero()
+            }
+        } else {
+            let id = self.choose_variable(component, &clause_ids);
+            let mut res = BigUint::zero();
+            for &sign in [true, false].iter() {
+                self.n_decisions += 1;
+                let trail_len = self.trail.len();
+                let literal = if sign {
+                    id as i64 + 1
+                } else {
+                    -(id as i64 + 1)
+                };
+                if self.propagate(&[literal]) {
+                    res = res.add(&self.count_variables(component));
+                }
+                self.undo(trail_len);
+            }
+            res
+        };
+        self.cache.insert(key.clone(), res.clone());
+        self.cached.push(key);
+        res
+    }
+    fn choose_variable(&self, component: &[usize], clause_ids: &[usize]) -> usize {
+        let mut score = HashMap::new();
+        for &clause_id in clause_ids {
+            for &literal in &self.clauses[clause_id] {
+                let id = var_of(literal);
+                if self.values[id].is_none() && self.projected[id] {
+                    *score.entry(id).or_insert(0) += 1;
+                }
+            }
+        }
+        *component
+            .iter()
+            .filter(|&&x| self.projected[x])
+            .max_by_key(|&&x| (score.get(&x).cloned().unwrap_or(0), usize::MAX - x))
+            .unwrap()
+    }
+    pub fn count(&mut self) -> BigUint {
+        if self.clauses.iter().any(|x| x.is_empty()) {
+            return BigUint::zero();
+        }
+        let units: Vec<i64> = self
+            .clauses
+            .iter()
+            .filter(|x| x.len() == 1)
+            .map(|x| x[0])
+            .collect();
+        if !self.propagate(&units) {
+            return BigUint::zero();
+        }
+        let variables: Vec<usize> = (0..self.values.len()).collect();
+        self.count_variables(&variables)
+    }
+}
+
+/// Counts the models of `problem`.
+pub fn count(problem: &SatProblem) -> BigUint {
+    ModelCounter::new(problem, None).count()
+}
+
+/// Counts the distinct restrictions of the models of `problem` to `projection`.
+pub fn count_projected(problem: &SatProblem, projection: &[usize]) -> BigUint {
+    ModelCounter::new(problem, Some(projection)).count()
+}
+
+/// Reads the projection variables from `c ind ... 0` or `c p show ... 0` lines.
+/// Returns 0-based variable ids, or `None` if the input declares no projection.
+pub fn parse_projection(s: &str) -> Option<Vec<usize>> {
+    let mut res = None;
+    for line in s.lines() {
+        let mut iter = line.split_whitespace();
+        if iter.next() != Some("c") {
+            continue;
+        }
+        let rest: Vec<&str> = match iter.next() {
+            Some("ind") => iter.collect(),
+            Some("p") if iter.next() == Some("show") => iter.collect(),
+            _ => continue,
+        };
+        let xs: &mut Vec<usize> = res.get_or_insert_with(Vec::new);
+        for t in rest {
+            let u = t.parse::<usize>().unwrap();
+            if u == 0 {
+                break;
+            }
+            xs.push(u - 1);
+        }
+    }
+    res
+}
+
+#[test]
+fn test_count() {
+    let problem = SatProblem::new_from_dimacs("p cnf 3 1\n1 2 3 0\n");
+    assert_eq!(count(&problem).to_string(), "7");
+    let problem = SatProblem::new_from_dimacs("p cnf 4 2\n1 0\n-1 0\n");
+    assert_eq!(count(&problem).to_string(), "0");
+    // two independent components and a free variable
+    let problem = SatProblem::new_from_dimacs("p cnf 5 2\n1 2 0\n-3 -4 0\n");
+    assert_eq!(count(&problem).to_string(), "18");
+    // an odd number of 6 variables, which is split by a fresh variable
+    let problem = SatProblem::new_from_dimacs("p cnf 6 2\nx1 2 3 4 5 6 0\n1 2 0\n");
+    assert_eq!(count(&problem).to_string(), "24");
+}
+
+#[test]
+fn test_count_large() {
+    // 100 independent clauses over 2 variables each
+    let mut problem = SatProblem::new(0);
+    for i in 0..100 {
+        problem.add_clause(&[2 * i + 1, 2 * i + 2]);
+    }
+    let mut expected = BigUint::one();
+    for _ in 0..100 {
+        expected = expected.mul(&BigUint::from_u64(3));
+    }
+    assert_eq!(count(&problem), expected);
+}
+
+#[test]
+fn test_count_projected() {
+    let s = "c p show 1 2 0\np cnf 4 3\n1 3 0\n2 4 0\n-3 -4 0\n";
+    let projection = parse_projection(s).unwrap();
+    assert_eq!(projection, vec![0, 1]);
+    let problem = SatProblem::new_from_dimacs(s);
+    assert_eq!(count_projected(&problem, &projection).to_string(), "3");
+    assert_eq!(count(&problem).to_string(), "5");
+}
+
+#[test]
+fn test_count_learns_from_conflicts() {
+    let mut n_learnt_clauses = 0;
+    for seed in 0..50 {
+        let problem = SatProblem::gen_random_sat(12, 45, 3, 0.3, seed);
+        let mut solver = SatSolver::new(&problem);
+        let n = solver.enumerate().count();
+        let mut counter = ModelCounter::new(&problem, None);
+        assert_eq!(counter.count(), BigUint::from_u64(n as u64));
+        n_learnt_clauses += counter.n_learnt_clauses();
+    }
+    assert!(n_learnt_clauses > 0);
+}
+
+#[test]
+fn test_count_against_enumeration() {
+    let s = "p cnf 6 6\n1 -2 3 0\n-1 4 0\n2 5 -6 0\n-3 -5 0\n4 6 0\n-4 -6 1 0\n";
+    let problem = SatProblem::new_from_dimacs(s);
+    let mut solver = SatSolver::new(&problem);
+    let n = solver.enumerate().count();
+    assert_eq!(count(&problem), BigUint::from_u64(n as u64));
+}
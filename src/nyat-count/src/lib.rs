@@ -0,0 +1,2 @@
+pub mod bigint;
+pub mod count;
@@ -0,0 +1,25 @@
+extern crate env_logger;
+
+use nyat_count::count::*;
+use nyat_sat::sat::*;
+
+fn main() {
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().collect();
+    let s = std::fs::read_to_string(args[1].as_str()).unwrap();
+    let problem = SatProblem::new_from_dimacs(s.as_str());
+    let res = match parse_projection(s.as_str()) {
+        Some(projection) => {
+            println!("c projected on {} variables", projection.len());
+            count_projected(&problem, &projection)
+        }
+        None => count(&problem),
+    };
+    if res.is_zero() {
+        println!("s UNSATISFIABLE");
+    } else {
+        println!("s SATISFIABLE");
+    }
+    println!("c s exact arb int {}", res);
+}
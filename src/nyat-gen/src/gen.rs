@@ -0,0 +1,432 @@
+use nyat_sat::sat::SatProblem;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+
+/// Adds the pairwise clauses that at most one of the literals is true.
+fn at_most_one(problem: &mut SatProblem, literals: &[i64]) {
+    for (i, &x) in literals.iter().enumerate() {
+        for &y in &literals[i + 1..] {
+            problem.add_clause(&[-x, -y]);
+        }
+    }
+}
+
+/// Adds the clauses that an odd number of the variables are true if `parity`, or else an even one,
+/// which block every assignment of the other parity.
+fn parity_clauses(problem: &mut SatProblem, variables: &[i64], parity: bool) {
+    assert!(variables.len() <= 20, "too many variables in a parity");
+    for bits in 0..1u32 << variables.len() {
+        if (bits.count_ones() % 2 == 1) != parity {
+            let clause: Vec<i64> = variables
+                .iter()
+                .enumerate()
+                .map(|(i, &x)| if (bits >> i) & 1 == 1 { -x } else { x })
+                .collect();
+            problem.add_clause(&clause);
+        }
+    }
+}
+
+/// Uniform random k-SAT of `(ratio * n_variables).round()` clauses without a planted model,
+/// where every clause has `k` distinct variables with random signs.
+pub fn uniform(n_variables: usize, ratio: f64, k: usize, seed: u64) -> SatProblem {
+    assert!(k <= n_variables);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let n_clauses = (ratio * n_variables as f64).round() as usize;
+    let ids: Vec<i64> = (1..=n_variables as i64).collect();
+    let mut problem = SatProblem::new(n_variables);
+    for _ in 0..n_clauses {
+        let clause: Vec<i64> = ids.choose_multiple(&mut rng, k).cloned().collect();
+        let clause: Vec<i64> = clause
+            .into_iter()
+            .map(|x| if rng.gen() { x } else { -x })
+            .collect();
+        problem.add_clause(&clause);
+    }
+    problem
+}
+
+/// Pigeonhole principle of `n_holes + 1` pigeons in `n_holes` holes, which is unsatisfiable.
+pub fn pigeonhole(n_holes: usize) -> SatProblem {
+    let var = |pigeon: usize, hole: usize| (pigeon * n_holes + hole + 1) as i64;
+    let mut problem = SatProblem::new((n_holes + 1) * n_holes);
+    for pigeon in 0..=n_holes {
+        let clause: Vec<i64> = (0..n_holes).map(|hole| var(pigeon, hole)).collect();
+        problem.add_clause(&clause);
+    }
+    for hole in 0..n_holes {
+        let literals: Vec<i64> = (0..=n_holes).map(|pigeon| var(pigeon, hole)).collect();
+        at_most_one(&mut problem, &literals);
+    }
+    problem
+}
+
+/// Urquhart's parity formula over a random connected graph of `n_vertices` vertices and about
+/// `n_vertices * degree / 2` edges, which are the variables. The parity of the edges of every vertex
+/// is its random charge, and the sum of the charges is odd, so it is unsatisfiable.
+/// With `xor`, the parities are XOR constraints instead of clauses.
+pub fn urquhart(n_vertices: usize, degree: usize, xor: bool, seed: u64) -> SatProblem {
+    assert!(n_vertices >= 2);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut edges = BTreeSet::new();
+    // a random spanning tree makes it connected
+    for v in 1..n_vertices {
+        edges.insert((rng.gen_range(0, v), v));
+    }
+    let n_edges = (n_vertices * degree / 2).min(n_vertices * (n_vertices - 1) / 2);
+    while edges.len() < n_edges {
+        let (u, v) = (rng.gen_range(0, n_vertices), rng.gen_range(0, n_vertices));
+        if u != v {
+            edges.insert((u.min(v), u.max(v)));
+        }
+    }
+    let mut incident = vec![vec![]; n_vertices];
+    for (i, &(u, v)) in edges.iter().enumerate() {
+        incident[u].push(i as i64 + 1);
+        incident[v].push(i as i64 + 1);
+    }
+    let mut charges: Vec<bool> = (0..n_vertices).map(|_| rng.gen()).collect();
+    if charges.iter().filter(|&&x| x).count() % 2 == 0 {
+        charges[0] = !charges[0];
+    }
+    let mut problem = SatProblem::new(edges.len());
+    for (variables, &charge) in incident.iter().zip(&charges) {
+        if xor {
+            // an odd number of the literals are true
+            let mut literals = variables.clone();
+            if !charge {
+                literals[0] = -literals[0];
+            }
+            problem.add_xor(&literals);
+        } else {
+            parity_clauses(&mut problem, variables, charge);
+        }
+    }
+    problem
+}
+
+/// Parses a graph in the DIMACS format of `p edge <vertices> <edges>` and `e <u> <v>` lines,
+/// and returns the number of vertices and the edges between vertices from 0.
+pub fn parse_edges(s: &str) -> (usize, Vec<(usize, usize)>) {
+    let mut n_vertices = None;
+    let mut edges = vec![];
+    for line in s.lines() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("p") => {
+                assert_eq!(tokens.next(), Some("edge"));
+                n_vertices = Some(tokens.next().unwrap().parse::<usize>().unwrap());
+            }
+            Some("e") => {
+                let n = n_vertices.expect("an edge before the header");
+                let mut vertex = || {
+                    let v = tokens.next().unwrap().parse::<usize>().unwrap();
+                    assert!(1 <= v && v <= n, "no vertex {}", v);
+                    v - 1
+                };
+                edges.push((vertex(), vertex()));
+            }
+            Some("c") | None => {}
+            Some(x) => panic!("unexpected line: {}", x),
+        }
+    }
+    (n_vertices.expect("no header"), edges)
+}
+
+/// Colors the vertices from 0 with `n_colors` colors, where the variable of the vertex `v` and
+/// the color `c` is `v * n_colors + c + 1`.
+pub fn coloring(n_vertices: usize, edges: &[(usize, usize)], n_colors: usize) -> SatProblem {
+    let var = |vertex: usize, color: usize| (vertex * n_colors + color + 1) as i64;
+    let mut problem = SatProblem::new(n_vertices * n_colors);
+    for v in 0..n_vertices {
+        let literals: Vec<i64> = (0..n_colors).map(|c| var(v, c)).collect();
+        problem.add_clause(&literals);
+        at_most_one(&mut problem, &literals);
+    }
+    for &(u, v) in edges {
+        for c in 0..n_colors {
+            problem.add_clause(&[-var(u, c), -var(v, c)]);
+        }
+    }
+    problem
+}
+
+/// N-queens, where the variable of the row `r` and the column `c` is `r * n + c + 1`.
+pub fn queens(n: usize) -> SatProblem {
+    let var = |r: usize, c: usize| (r * n + c + 1) as i64;
+    let mut problem = SatProblem::new(n * n);
+    for r in 0..n {
+        let literals: Vec<i64> = (0..n).map(|c| var(r, c)).collect();
+        problem.add_clause(&literals);
+        at_most_one(&mut problem, &literals);
+    }
+    for c in 0..n {
+        let literals: Vec<i64> = (0..n).map(|r| var(r, c)).collect();
+        at_most_one(&mut problem, &literals);
+    }
+    // the diagonals of a constant r - c and of a constant r + c
+    for d in 0..2 * n - 1 {
+        let literals: Vec<i64> = (0..n)
+            .filter(|&r| r + n > d && r + n - 1 - d < n)
+            .map(|r| var(r, r + n - 1 - d))
+            .collect();
+        at_most_one(&mut problem, &literals);
+        let literals: Vec<i64> = (0..n)
+            .filter(|&r| d >= r && d - r < n)
+            .map(|r| var(r, d - r))
+            .collect();
+        at_most_one(&mut problem, &literals);
+    }
+    problem
+}
+
+/// Sudoku of boxes of `box_size` by `box_size` cells with the givens of (row, column, digit) from 0.
+/// With `n = box_size * box_size`, the variable of a digit `d` in a cell is `(row * n + column) * n + d + 1`.
+pub fn sudoku(box_size: usize, givens: &[(usize, usize, usize)]) -> SatProblem {
+    let n = box_size * box_size;
+    let var = |r: usize, c: usize, d: usize| ((r * n + c) * n + d + 1) as i64;
+    let mut problem = SatProblem::new(n * n * n);
+    let mut exactly_one = |literals: Vec<i64>| {
+        problem.add_clause(&literals);
+        at_most_one(&mut problem, &literals);
+    };
+    for i in 0..n {
+        for k in 0..n {
+            // a digit in the cell (i, k), and the digit k in the row, the column and the box i
+            exactly_one((0..n).map(|d| var(i, k, d)).collect());
+            exactly_one((0..n).map(|c| var(i, c, k)).collect());
+            exactly_one((0..n).map(|r| var(r, i, k)).collect());
+            let (r, c) = (i / box_size * box_size, i % box_size * box_size);
+            exactly_one(
+                (0..n)
+                    .map(|j| var(r + j / box_size, c + j % box_size, k))
+                    .collect(),
+            );
+        }
+    }
+    for &(r, c, d) in givens {
+        assert!(r < n && c < n && d < n);
+        problem.add_clause(&[var(r, c, d)]);
+    }
+    problem
+}
+
+/// Returns the rows or the columns of a Sudoku in a random order that keeps the bands of boxes.
+fn shuffled_lines(box_size: usize, rng: &mut StdRng) -> Vec<usize> {
+    let mut bands: Vec<usize> = (0..box_size).collect();
+    bands.shuffle(rng);
+    let mut res = vec![];
+    for band in bands {
+        let mut lines: Vec<usize> = (0..box_size).map(|i| band * box_size + i).collect();
+        lines.shuffle(rng);
+        res.extend(lines);
+    }
+    res
+}
+
+/// Returns `n_givens` givens of a random solved Sudoku, so that the puzzle is satisfiable.
+pub fn random_sudoku_givens(
+    box_size: usize,
+    n_givens: usize,
+    seed: u64,
+) -> Vec<(usize, usize, usize)> {
+    let n = box_size * box_size;
+    assert!(n_givens <= n * n);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut digits: Vec<usize> = (0..n).collect();
+    digits.shuffle(&mut rng);
+    let rows = shuffled_lines(box_size, &mut rng);
+    let columns = shuffled_lines(box_size, &mut rng);
+    // a solved grid shifted by a box from band to band and by a row within a band
+    let digit = |r: usize, c: usize| digits[(box_size * (r % box_size) + r / box_size + c) % n];
+    let mut cells: Vec<(usize, usize)> = (0..n).flat_map(|r| (0..n).map(move |c| (r, c))).collect();
+    cells.shuffle(&mut rng);
+    cells[..n_givens]
+        .iter()
+        .map(|&(r, c)| (r, c, digit(rows[r], columns[c])))
+        .collect()
+}
+
+/// Community attachment of Giráldez-Cru and Levy, which resembles industrial instances.
+/// The variables are split into `n_communities` communities of consecutive variables, and every one of
+/// `(ratio * n_variables).round()` clauses has `k` variables of a community with the probability
+/// `modularity + 1 / n_communities`, which is from 0 to 1, or else of `k` distinct communities, with random signs.
+pub fn community(
+    n_variables: usize,
+    ratio: f64,
+    k: usize,
+    n_communities: usize,
+    modularity: f64,
+    seed: u64,
+) -> SatProblem {
+    assert!(n_communities >= k && n_variables / n_communities >= k);
+    let p = modularity + 1.0 / n_communities as f64;
+    assert!((0.0..=1.0).contains(&p), "modularity out of range");
+    let mut rng = StdRng::seed_from_u64(seed);
+    let n_clauses = (ratio * n_variables as f64).round() as usize;
+    let start = |j: usize| j * n_variables / n_communities;
+    let all: Vec<usize> = (0..n_communities).collect();
+    let mut problem = SatProblem::new(n_variables);
+    for _ in 0..n_clauses {
+        let ids: Vec<usize> = if rng.gen::<f64>() < p {
+            let j = rng.gen_range(0, n_communities);
+            let members: Vec<usize> = (start(j)..start(j + 1)).collect();
+            members.choose_multiple(&mut rng, k).cloned().collect()
+        } else {
+            let communities: Vec<usize> = all.choose_multiple(&mut rng, k).cloned().collect();
+            communities
+                .into_iter()
+                .map(|j| rng.gen_range(start(j), start(j + 1)))
+                .collect()
+        };
+        let clause: Vec<i64> = ids
+            .into_iter()
+            .map(|id| {
+                if rng.gen() {
+                    id as i64 + 1
+                } else {
+                    -(id as i64) - 1
+                }
+            })
+            .collect();
+        problem.add_clause(&clause);
+    }
+    problem
+}
+
+/// Renames the variables by a random permutation, flips their signs at random, and shuffles the clauses
+/// and their literals, which keeps the satisfiability and the number of models.
+pub fn scramble(problem: &SatProblem, seed: u64) -> SatProblem {
+    let n = problem.n_variables();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut names: Vec<i64> = (1..=n as i64).collect();
+    names.shuffle(&mut rng);
+    for name in names.iter_mut() {
+        if rng.gen() {
+            *name = -*name;
+        }
+    }
+    let rename = |x: i64| names[x.unsigned_abs() as usize - 1] * x.signum();
+    let mut clauses: Vec<Vec<i64>> = problem
+        .clauses()
+        .map(|clause| clause.into_iter().map(rename).collect())
+        .collect();
+    clauses.shuffle(&mut rng);
+    let mut res = SatProblem::new(n);
+    for mut clause in clauses {
+        clause.shuffle(&mut rng);
+        res.add_clause(&clause);
+    }
+    for xor in problem.xors() {
+        let xor: Vec<i64> = xor.into_iter().map(rename).collect();
+        res.add_xor(&xor);
+    }
+    res
+}
+
+#[cfg(test)]
+fn n_models(problem: &SatProblem) -> usize {
+    nyat_sat::sat::SatSolver::new(problem).enumerate().count()
+}
+
+#[cfg(test)]
+fn is_satisfiable(problem: &SatProblem) -> bool {
+    nyat_sat::sat::SatSolver::new(problem).solve().is_some()
+}
+
+#[test]
+fn test_uniform() {
+    let problem = uniform(100, 4.26, 3, 1);
+    assert_eq!(problem.n_clauses(), 426);
+    assert!(problem.clauses().all(|clause| {
+        let ids: BTreeSet<u64> = clause.iter().map(|x| x.unsigned_abs()).collect();
+        ids.len() == 3
+    }));
+    assert_eq!(uniform(100, 4.26, 3, 1).to_dimacs(), problem.to_dimacs());
+    assert_ne!(uniform(100, 4.26, 3, 2).to_dimacs(), problem.to_dimacs());
+    // far below and above the threshold
+    assert!(is_satisfiable(&uniform(50, 2.0, 3, 0)));
+    assert!(!is_satisfiable(&uniform(50, 10.0, 3, 0)));
+}
+
+#[test]
+fn test_pigeonhole() {
+    assert!(!is_satisfiable(&pigeonhole(5)));
+    assert_eq!(pigeonhole(3).n_clauses(), 4 + 3 * 6);
+}
+
+#[test]
+fn test_urquhart() {
+    for seed in 0..10 {
+        for &xor in [false, true].iter() {
+            let problem = urquhart(10, 3, xor, seed);
+            assert_eq!(problem.n_variables(), 15);
+            assert!(!is_satisfiable(&problem));
+        }
+    }
+}
+
+#[test]
+fn test_coloring() {
+    let (n, edges) = parse_edges("c K4\np edge 4 6\ne 1 2\ne 1 3\ne 1 4\ne 2 3\ne 2 4\ne 3 4\n");
+    assert_eq!(n, 4);
+    assert_eq!(edges[5], (2, 3));
+    assert!(!is_satisfiable(&coloring(n, &edges, 3)));
+    // the colors of every vertex are all different
+    assert_eq!(n_models(&coloring(n, &edges, 4)), 24);
+}
+
+#[test]
+fn test_queens() {
+    assert_eq!(n_models(&queens(6)), 4);
+    assert_eq!(n_models(&queens(8)), 92);
+    assert!(!is_satisfiable(&queens(3)));
+}
+
+#[test]
+fn test_sudoku() {
+    for seed in 0..5 {
+        let givens = random_sudoku_givens(3, 30, seed);
+        assert_eq!(givens.len(), 30);
+        let problem = sudoku(3, &givens);
+        assert!(is_satisfiable(&problem));
+    }
+    // every cell is given
+    let givens = random_sudoku_givens(2, 16, 0);
+    assert_eq!(n_models(&sudoku(2, &givens)), 1);
+    assert!(!is_satisfiable(&sudoku(2, &[(0, 0, 0), (0, 1, 0)])));
+}
+
+#[test]
+fn test_community() {
+    // every clause is in a community at the highest modularity
+    let problem = community(100, 4.0, 3, 10, 0.9, 0);
+    assert_eq!(problem.n_clauses(), 400);
+    assert!(problem.clauses().all(|clause| {
+        let communities: BTreeSet<u64> = clause.iter().map(|x| (x.abs() - 1) as u64 / 10).collect();
+        communities.len() == 1
+    }));
+    // and none at the lowest one
+    let problem = community(100, 4.0, 3, 10, -0.1, 0);
+    assert!(problem.clauses().all(|clause| {
+        let communities: BTreeSet<u64> = clause.iter().map(|x| (x.abs() - 1) as u64 / 10).collect();
+        communities.len() == 3
+    }));
+    assert_eq!(
+        community(100, 4.0, 3, 10, 0.5, 3).to_dimacs(),
+        community(100, 4.0, 3, 10, 0.5, 3).to_dimacs()
+    );
+}
+
+#[test]
+fn test_scramble() {
+    let problem = queens(6);
+    let scrambled = scramble(&problem, 0);
+    assert_ne!(scrambled.to_dimacs(), problem.to_dimacs());
+    assert_eq!(scramble(&problem, 0).to_dimacs(), scrambled.to_dimacs());
+    assert_eq!(n_models(&scrambled), 4);
+    let problem = urquhart(8, 3, true, 0);
+    assert!(!is_satisfiable(&scramble(&problem, 1)));
+}
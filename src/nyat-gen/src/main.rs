@@ -0,0 +1,145 @@
+use nyat_gen::gen::*;
+use nyat_sat::sat::SatProblem;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Family {
+    Uniform,
+    Pigeonhole,
+    Urquhart,
+    Coloring,
+    Queens,
+    Sudoku,
+    Community,
+}
+
+struct Options {
+    family: Family,
+    /// graph of the coloring
+    input: Option<String>,
+    output: Option<String>,
+    seed: u64,
+    variables: usize,
+    ratio: f64,
+    k: usize,
+    holes: usize,
+    vertices: usize,
+    degree: usize,
+    xor: bool,
+    colors: usize,
+    size: usize,
+    box_size: usize,
+    givens: usize,
+    communities: usize,
+    modularity: f64,
+    shuffle: bool,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Options {
+        let family = match args.get(1).map(|x| x.as_str()) {
+            Some("uniform") => Family::Uniform,
+            Some("pigeonhole") => Family::Pigeonhole,
+            Some("urquhart") => Family::Urquhart,
+            Some("coloring") => Family::Coloring,
+            Some("queens") => Family::Queens,
+            Some("sudoku") => Family::Sudoku,
+            Some("community") => Family::Community,
+            x => panic!(
+                "the family is uniform, pigeonhole, urquhart, coloring, queens, sudoku or community: {:?}",
+                x
+            ),
+        };
+        let mut options = Options {
+            family,
+            input: None,
+            output: None,
+            seed: 0,
+            variables: 100,
+            ratio: 4.26,
+            k: 3,
+            holes: 8,
+            vertices: 20,
+            degree: 4,
+            xor: false,
+            colors: 3,
+            size: 8,
+            box_size: 3,
+            givens: 30,
+            communities: 10,
+            modularity: 0.8,
+            shuffle: false,
+        };
+        let mut iter = args.iter().skip(2);
+        while let Some(arg) = iter.next() {
+            let mut value = |name: &str| -> String {
+                iter.next()
+                    .unwrap_or_else(|| panic!("{} needs a value", name))
+                    .clone()
+            };
+            match arg.as_str() {
+                "--output" => options.output = Some(value(arg)),
+                "--seed" => options.seed = value(arg).parse().unwrap(),
+                "--variables" => options.variables = value(arg).parse().unwrap(),
+                "--ratio" => options.ratio = value(arg).parse().unwrap(),
+                "--k" => options.k = value(arg).parse().unwrap(),
+                "--holes" => options.holes = value(arg).parse().unwrap(),
+                "--vertices" => options.vertices = value(arg).parse().unwrap(),
+                "--degree" => options.degree = value(arg).parse().unwrap(),
+                "--xor" => options.xor = true,
+                "--colors" => options.colors = value(arg).parse().unwrap(),
+                "--size" => options.size = value(arg).parse().unwrap(),
+                "--box" => options.box_size = value(arg).parse().unwrap(),
+                "--givens" => options.givens = value(arg).parse().unwrap(),
+                "--communities" => options.communities = value(arg).parse().unwrap(),
+                "--modularity" => options.modularity = value(arg).parse().unwrap(),
+                "--shuffle" => options.shuffle = true,
+                _ => {
+                    assert!(options.input.is_none(), "unexpected argument: {}", arg);
+                    options.input = Some(arg.clone());
+                }
+            }
+        }
+        options
+    }
+    fn generate(&self) -> SatProblem {
+        match self.family {
+            Family::Uniform => uniform(self.variables, self.ratio, self.k, self.seed),
+            Family::Pigeonhole => pigeonhole(self.holes),
+            Family::Urquhart => urquhart(self.vertices, self.degree, self.xor, self.seed),
+            Family::Coloring => {
+                let path = self.input.as_ref().expect("no graph file");
+                let (n_vertices, edges) = parse_edges(&std::fs::read_to_string(path).unwrap());
+                coloring(n_vertices, &edges, self.colors)
+            }
+            Family::Queens => queens(self.size),
+            Family::Sudoku => {
+                let givens = random_sudoku_givens(self.box_size, self.givens, self.seed);
+                sudoku(self.box_size, &givens)
+            }
+            Family::Community => community(
+                self.variables,
+                self.ratio,
+                self.k,
+                self.communities,
+                self.modularity,
+                self.seed,
+            ),
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let options = Options::parse(&args);
+    let mut problem = options.generate();
+    if options.shuffle {
+        problem = scramble(&problem, options.seed);
+    }
+    // the command line, which generates it again
+    let mut res = format!("c {}\n", args[1..].join(" "));
+    res.push_str(&problem.to_dimacs());
+    match &options.output {
+        Some(path) => std::fs::write(path, res).unwrap(),
+        None => print!("{}", res),
+    }
+}
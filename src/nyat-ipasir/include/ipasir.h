@@ -0,0 +1,46 @@
+/* IPASIR interface of nyat: https://github.com/biotomas/ipasir */
+#ifndef NYAT_IPASIR_H
+#define NYAT_IPASIR_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns the name and the version of the solver. */
+const char *ipasir_signature(void);
+
+/* Returns a new solver, which is released by ipasir_release. */
+void *ipasir_init(void);
+
+void ipasir_release(void *solver);
+
+/* Adds a literal to the current clause, or finishes it with 0. */
+void ipasir_add(void *solver, int32_t lit_or_zero);
+
+/* Assumes a literal during the next call of ipasir_solve. */
+void ipasir_assume(void *solver, int32_t lit);
+
+/* Returns 10 if satisfiable, 20 if unsatisfiable, or 0 if interrupted by the terminate callback. */
+int ipasir_solve(void *solver);
+
+/* Returns lit if it is true in the model, -lit if it is false, or 0 if either will do. */
+int32_t ipasir_val(void *solver, int32_t lit);
+
+/* Returns 1 if the assumption lit was used to refute the last call, and 0 otherwise. */
+int ipasir_failed(void *solver, int32_t lit);
+
+/* Sets a callback that is called during the search, which stops when it returns nonzero. */
+void ipasir_set_terminate(void *solver, void *data, int (*terminate)(void *data));
+
+/* Sets a callback that is called with every learnt clause of at most max_length literals,
+   which ends with 0. */
+void ipasir_set_learn(void *solver, void *data, int max_length,
+                      void (*learn)(void *data, int32_t *clause));
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,250 @@
+//! IPASIR interface over the incremental `SatSolver`, whose C header is `include/ipasir.h`.
+
+use nyat_sat::sat::{SatAssignments, SatProblem, SatSolver};
+use std::collections::HashSet;
+use std::os::raw::{c_char, c_int, c_void};
+
+/// Solver behind the opaque pointer of the interface.
+struct Ipasir {
+    solver: SatSolver<'static>,
+    /// literals of the clause being added
+    clause: Vec<i64>,
+    /// assumptions of the next call
+    assumptions: Vec<i64>,
+    /// model of the last call, if it was satisfiable
+    model: Option<SatAssignments>,
+    /// failed assumptions of the last call, if it was unsatisfiable
+    failed: HashSet<i64>,
+}
+
+/// Callback and its data given by the caller, who makes sure that it can be called from the solver.
+struct Callback<F> {
+    data: *mut c_void,
+    f: F,
+}
+
+unsafe impl<F> Send for Callback<F> {}
+
+unsafe fn ipasir<'a>(solver: *mut c_void) -> &'a mut Ipasir {
+    assert!(!solver.is_null());
+    &mut *(solver as *mut Ipasir)
+}
+
+#[no_mangle]
+pub extern "C" fn ipasir_signature() -> *const c_char {
+    concat!("nyat-", env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+#[no_mangle]
+pub extern "C" fn ipasir_init() -> *mut c_void {
+    let ipasir = Ipasir {
+        solver: SatSolver::new(&SatProblem::new(0)),
+        clause: vec![],
+        assumptions: vec![],
+        model: None,
+        failed: HashSet::new(),
+    };
+    Box::into_raw(Box::new(ipasir)) as *mut c_void
+}
+
+/// # Safety
+/// `solver` is a pointer from `ipasir_init`, which is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_release(solver: *mut c_void) {
+    assert!(!solver.is_null());
+    drop(Box::from_raw(solver as *mut Ipasir));
+}
+
+/// # Safety
+/// `solver` is a pointer from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_add(solver: *mut c_void, lit_or_zero: i32) {
+    let ipasir = ipasir(solver);
+    if lit_or_zero == 0 {
+        ipasir.solver.add_clause(&ipasir.clause);
+        ipasir.clause.clear();
+    } else {
+        ipasir.clause.push(lit_or_zero as i64);
+    }
+}
+
+/// # Safety
+/// `solver` is a pointer from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_assume(solver: *mut c_void, lit: i32) {
+    assert!(lit != 0);
+    ipasir(solver).assumptions.push(lit as i64);
+}
+
+/// Returns 10 if satisfiable, 20 if unsatisfiable, or 0 if interrupted. The assumptions are cleared.
+///
+/// # Safety
+/// `solver` is a pointer from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_solve(solver: *mut c_void) -> c_int {
+    let ipasir = ipasir(solver);
+    let assumptions = std::mem::take(&mut ipasir.assumptions);
+    ipasir.model = ipasir.solver.solve_with_assumptions(&assumptions);
+    ipasir.failed = ipasir.solver.failed_assumptions().into_iter().collect();
+    if ipasir.model.is_some() {
+        10
+    } else if ipasir.solver.interrupted() {
+        0
+    } else {
+        20
+    }
+}
+
+/// Returns `lit` if it is true in the model of the last call, `-lit` if it is false,
+/// or 0 for a variable that is unknown to the solver.
+///
+/// # Safety
+/// `solver` is a pointer from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_val(solver: *mut c_void, lit: i32) -> i32 {
+    let model = ipasir(solver)
+        .model
+        .as_ref()
+        .expect("ipasir_val needs a satisfiable call");
+    let id = lit.unsigned_abs() as usize - 1;
+    if id >= model.len() {
+        0
+    } else if model[id] == (lit > 0) {
+        lit
+    } else {
+        -lit
+    }
+}
+
+/// # Safety
+/// `solver` is a pointer from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_failed(solver: *mut c_void, lit: i32) -> c_int {
+    ipasir(solver).failed.contains(&(lit as i64)) as c_int
+}
+
+/// # Safety
+/// `solver` is a pointer from `ipasir_init`, and `terminate` can be called with `data`
+/// until the solver is released or the callback is set again.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_terminate(
+    solver: *mut c_void,
+    data: *mut c_void,
+    terminate: Option<extern "C" fn(*mut c_void) -> c_int>,
+) {
+    let callback = Callback { data, f: terminate };
+    ipasir(solver).solver.set_terminate(move || {
+        let callback = &callback;
+        match callback.f {
+            Some(f) => f(callback.data) != 0,
+            None => false,
+        }
+    });
+}
+
+/// # Safety
+/// `solver` is a pointer from `ipasir_init`, and `learn` can be called with `data`
+/// until the solver is released or the callback is set again.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_learn(
+    solver: *mut c_void,
+    data: *mut c_void,
+    max_length: c_int,
+    learn: Option<extern "C" fn(*mut c_void, *mut i32)>,
+) {
+    let callback = Callback { data, f: learn };
+    let mut buffer: Vec<i32> = vec![];
+    ipasir(solver).solver.set_clause_sharing(
+        move |clause, _| {
+            let callback = &callback;
+            if let Some(f) = callback.f {
+                if clause.len() <= max_length.max(0) as usize {
+                    buffer.clear();
+                    buffer.extend(clause.iter().map(|&x| x as i32));
+                    buffer.push(0);
+                    f(callback.data, buffer.as_mut_ptr());
+                }
+            }
+        },
+        Vec::new,
+    );
+}
+
+#[test]
+fn test_ipasir() {
+    unsafe {
+        let solver = ipasir_init();
+        // x1 or x2, and not x1 or x3
+        for &x in [1, 2, 0, -1, 3, 0].iter() {
+            ipasir_add(solver, x);
+        }
+        ipasir_assume(solver, -3);
+        assert_eq!(ipasir_solve(solver), 10);
+        assert_eq!(ipasir_val(solver, 1), -1);
+        assert_eq!(ipasir_val(solver, -2), 2);
+        assert_eq!(ipasir_val(solver, 3), -3);
+        // the assumptions are only for one call
+        ipasir_add(solver, -2);
+        ipasir_add(solver, 0);
+        ipasir_assume(solver, -3);
+        assert_eq!(ipasir_solve(solver), 20);
+        assert_eq!(ipasir_failed(solver, -3), 1);
+        assert_eq!(ipasir_solve(solver), 10);
+        assert_eq!(ipasir_val(solver, 3), 3);
+        ipasir_release(solver);
+    }
+}
+
+#[test]
+fn test_ipasir_terminate() {
+    extern "C" fn terminate(data: *mut c_void) -> c_int {
+        let n_calls = unsafe { &mut *(data as *mut usize) };
+        *n_calls += 1;
+        (*n_calls > 10) as c_int
+    }
+    extern "C" fn learn(data: *mut c_void, clause: *mut i32) {
+        let n_learnt = unsafe { &mut *(data as *mut usize) };
+        assert_ne!(unsafe { *clause }, 0);
+        *n_learnt += 1;
+    }
+    unsafe {
+        let solver = ipasir_init();
+        // 7 pigeons in 6 holes
+        let var = |pigeon: i32, hole: i32| pigeon * 6 + hole + 1;
+        for pigeon in 0..7 {
+            for hole in 0..6 {
+                ipasir_add(solver, var(pigeon, hole));
+            }
+            ipasir_add(solver, 0);
+        }
+        for hole in 0..6 {
+            for i in 0..7 {
+                for k in i + 1..7 {
+                    ipasir_add(solver, -var(i, hole));
+                    ipasir_add(solver, -var(k, hole));
+                    ipasir_add(solver, 0);
+                }
+            }
+        }
+        let mut n_calls = 0usize;
+        let mut n_learnt = 0usize;
+        ipasir_set_terminate(
+            solver,
+            &mut n_calls as *mut usize as *mut c_void,
+            Some(terminate),
+        );
+        ipasir_set_learn(
+            solver,
+            &mut n_learnt as *mut usize as *mut c_void,
+            100,
+            Some(learn),
+        );
+        assert_eq!(ipasir_solve(solver), 0);
+        // it stops only after the callback asks for it
+        assert!(n_calls > 10);
+        assert!(n_learnt >= 1);
+        ipasir_set_terminate(solver, std::ptr::null_mut(), None);
+        assert_eq!(ipasir_solve(solver), 20);
+        ipasir_release(solver);
+    }
+}
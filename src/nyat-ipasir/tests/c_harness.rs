@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Compiles `tests/ipasir.c` against the static library and runs it.
+#[test]
+fn test_c_harness() {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // the static library is built next to the test binary
+    let exe = std::env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap();
+    assert!(lib_dir.join("libnyat_ipasir.a").exists());
+    let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("ipasir_test");
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg(manifest.join("tests/ipasir.c"))
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg("-o")
+        .arg(&output)
+        .arg(lib_dir.join("libnyat_ipasir.a"))
+        .args(["-lpthread", "-ldl", "-lm"])
+        .status()
+        .expect("a C compiler is needed");
+    assert!(status.success());
+    let res = Command::new(&output).output().unwrap();
+    assert!(res.status.success(), "{:?}", res);
+    assert!(String::from_utf8_lossy(&res.stdout).ends_with("ok\n"));
+}
@@ -0,0 +1,72 @@
+/* Incremental use of the IPASIR interface, which exits with 0 if every check passes. */
+#include <assert.h>
+#include <stdio.h>
+
+#include "ipasir.h"
+
+static int n_calls = 0;
+
+static int terminate(void *data) {
+    (void)data;
+    return ++n_calls > 10;
+}
+
+static void learn(void *data, int32_t *clause) {
+    int *n_learnt = data;
+    while (*clause != 0) {
+        clause++;
+    }
+    ++*n_learnt;
+}
+
+static void add_clause(void *solver, const int32_t *literals) {
+    while (*literals != 0) {
+        ipasir_add(solver, *literals++);
+    }
+    ipasir_add(solver, 0);
+}
+
+int main(void) {
+    printf("%s\n", ipasir_signature());
+    void *solver = ipasir_init();
+    add_clause(solver, (int32_t[]){1, 2, 0});
+    add_clause(solver, (int32_t[]){-1, 3, 0});
+    ipasir_assume(solver, -3);
+    assert(ipasir_solve(solver) == 10);
+    assert(ipasir_val(solver, 1) == -1);
+    assert(ipasir_val(solver, 2) == 2);
+    add_clause(solver, (int32_t[]){-2, 0});
+    ipasir_assume(solver, -3);
+    ipasir_assume(solver, 2);
+    assert(ipasir_solve(solver) == 20);
+    assert(ipasir_failed(solver, -3) || ipasir_failed(solver, 2));
+    assert(ipasir_solve(solver) == 10);
+    assert(ipasir_val(solver, 3) == 3);
+    ipasir_release(solver);
+
+    /* 8 pigeons in 7 holes, which is interrupted */
+    solver = ipasir_init();
+    for (int pigeon = 0; pigeon < 8; pigeon++) {
+        for (int hole = 0; hole < 7; hole++) {
+            ipasir_add(solver, pigeon * 7 + hole + 1);
+        }
+        ipasir_add(solver, 0);
+    }
+    for (int hole = 0; hole < 7; hole++) {
+        for (int i = 0; i < 8; i++) {
+            for (int k = i + 1; k < 8; k++) {
+                add_clause(solver, (int32_t[]){-(i * 7 + hole + 1), -(k * 7 + hole + 1), 0});
+            }
+        }
+    }
+    int n_learnt = 0;
+    ipasir_set_terminate(solver, NULL, terminate);
+    ipasir_set_learn(solver, &n_learnt, 1000, learn);
+    assert(ipasir_solve(solver) == 0);
+    /* it stops only after the callback asks for it */
+    assert(n_calls > 10);
+    assert(n_learnt >= 1);
+    ipasir_release(solver);
+    printf("ok\n");
+    return 0;
+}
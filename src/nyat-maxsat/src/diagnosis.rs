@@ -0,0 +1,63 @@
+use crate::maxsat::{Algorithm, MaxSatResult, MaxSatSolver};
+use nyat_sat::sat::SatProblem;
+use nyat_sat::wcnf::WcnfProblem;
+
+/// Returns a correction set of the groups of clauses of the least total weight: the groups to drop so that
+/// the rest is satisfiable with the hard clauses of group 0 and the XOR constraints, where `weights[g - 1]`
+/// is the weight of the group g. It is minimal, since the weights are positive, and it is None if the hard
+/// clauses are unsatisfiable.
+///
+/// A selector of every group enforces its clauses, and the soft unit clause of the selector has the weight
+/// of the group, so that an optimum drops the cheapest groups.
+pub fn diagnose(
+    problem: &SatProblem,
+    groups: &[usize],
+    weights: &[u64],
+    algorithm: Algorithm,
+) -> Option<Vec<usize>> {
+    assert_eq!(groups.len(), problem.n_clauses());
+    let n = problem.n_variables();
+    let selector = |g: usize| (n + g) as i64;
+    let mut wcnf = WcnfProblem::new(n);
+    let mut present = vec![false; weights.len() + 1];
+    for (mut clause, &g) in problem.clauses().zip(groups) {
+        assert!(g <= weights.len(), "no weight of the group {}", g);
+        if g != 0 {
+            clause.push(-selector(g));
+            present[g] = true;
+        }
+        wcnf.add_hard_clause(&clause);
+    }
+    for xor in problem.xors() {
+        wcnf.add_hard_xor(&xor);
+    }
+    let ids: Vec<usize> = (1..=weights.len()).filter(|&g| present[g]).collect();
+    for &g in &ids {
+        wcnf.add_soft_clause(weights[g - 1], &[selector(g)]);
+    }
+    match MaxSatSolver::new(&wcnf).solve(algorithm) {
+        MaxSatResult::Optimum { model, .. } => Some(
+            ids.into_iter()
+                .filter(|&g| !model[selector(g) as usize - 1])
+                .collect(),
+        ),
+        MaxSatResult::Unsatisfiable => None,
+    }
+}
+
+#[test]
+fn test_diagnose() {
+    // x1 and not x1, x2 and not x2, and x1 or x2, whose MCSes are {1, 3, 5}, {1, 4}, {2, 3} and {2, 4}
+    let problem = SatProblem::new_from_dimacs("p cnf 2 5\n1 0\n-1 0\n2 0\n-2 0\n1 2 0\n");
+    let groups = vec![1, 2, 3, 4, 5];
+    for &algorithm in [Algorithm::Linear, Algorithm::Oll].iter() {
+        let res = diagnose(&problem, &groups, &[1, 1, 1, 1, 1], algorithm).unwrap();
+        assert_eq!(res.len(), 2);
+        let res = diagnose(&problem, &groups, &[1, 5, 1, 5, 1], algorithm);
+        assert_eq!(res, Some(vec![1, 3, 5]));
+        let res = diagnose(&problem, &[0, 0, 1, 2, 3], &[2, 1, 1], algorithm);
+        assert_eq!(res, None);
+        let res = diagnose(&problem, &[0, 1, 2, 2, 0], &[1, 2], algorithm);
+        assert_eq!(res, Some(vec![1, 2]));
+    }
+}
@@ -0,0 +1,2 @@
+pub mod diagnosis;
+pub mod maxsat;
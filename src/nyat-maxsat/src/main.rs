@@ -0,0 +1,70 @@
+extern crate env_logger;
+
+use nyat_maxsat::maxsat::*;
+use nyat_sat::sat::SatAssignments;
+use nyat_sat::wcnf::WcnfProblem;
+
+struct Options {
+    input: String,
+    algorithm: Algorithm,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Options {
+        let mut input = None;
+        let mut algorithm = Algorithm::Oll;
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--algorithm" => {
+                    algorithm = match iter.next().map(|x| x.as_str()) {
+                        Some("linear") => Algorithm::Linear,
+                        Some("oll") => Algorithm::Oll,
+                        x => panic!("--algorithm needs linear or oll: {:?}", x),
+                    };
+                }
+                _ => {
+                    assert!(input.is_none(), "unexpected argument: {}", arg);
+                    input = Some(arg.clone());
+                }
+            }
+        }
+        Options {
+            input: input.expect("no input file"),
+            algorithm,
+        }
+    }
+}
+
+/// Formats a model as the `v` line of the MaxSAT Evaluation, with one digit for every variable.
+fn model_line(model: &SatAssignments) -> String {
+    let mut res = String::from("v ");
+    for i in 0..model.len() {
+        res.push(if model[i] { '1' } else { '0' });
+    }
+    res
+}
+
+fn main() {
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().collect();
+    let options = Options::parse(&args);
+    let s = std::fs::read_to_string(options.input.as_str()).unwrap();
+    let problem = WcnfProblem::new_from_wcnf(s.as_str());
+    println!(
+        "c {} variables, {} hard clauses, {} soft clauses",
+        problem.n_variables(),
+        problem.hard().n_clauses(),
+        problem.soft().len()
+    );
+    let mut solver = MaxSatSolver::new(&problem);
+    solver.set_on_model(|cost| println!("o {}", cost));
+    match solver.solve(options.algorithm) {
+        MaxSatResult::Optimum { model, .. } => {
+            println!("s OPTIMUM FOUND");
+            println!("{}", model_line(&model));
+        }
+        MaxSatResult::Unsatisfiable => println!("s UNSATISFIABLE"),
+    }
+}
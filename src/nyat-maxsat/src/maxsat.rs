@@ -0,0 +1,253 @@
+use nyat_sat::encodings::{self, GeneralizedTotalizer};
+use nyat_sat::sat::{SatAssignments, SatSolver};
+use nyat_sat::wcnf::{SoftClause, WcnfProblem};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// SAT-UNSAT search: bounds the cost below the best model with a totalizer until it is unsatisfiable.
+    Linear,
+    /// Core-guided search of OLL and RC2: relaxes every unsatisfiable core with a totalizer.
+    Oll,
+}
+
+#[derive(Debug)]
+pub enum MaxSatResult {
+    Optimum { cost: u64, model: SatAssignments },
+    Unsatisfiable,
+}
+
+/// Weighted partial MaxSAT solver. Every soft clause gets a relaxation literal,
+/// which is true if the clause may be falsified, and the hard clauses are kept as they are.
+pub struct MaxSatSolver<'a> {
+    solver: SatSolver<'a>,
+    n_variables: usize,
+    soft: Vec<SoftClause>,
+    /// relaxation literals with the weights of their soft clauses
+    relaxations: Vec<(i64, u64)>,
+    /// weight of the empty soft clauses
+    base_cost: u64,
+    on_model: Option<Box<dyn FnMut(u64) + 'a>>,
+}
+
+impl<'a> MaxSatSolver<'a> {
+    pub fn new(problem: &WcnfProblem) -> MaxSatSolver<'a> {
+        let mut solver = SatSolver::new(problem.hard());
+        // the variables only in soft clauses, or in none, are still in the model
+        while solver.n_variables() < problem.n_variables() {
+            solver.new_variable();
+        }
+        let mut relaxations = vec![];
+        let mut base_cost = 0;
+        for x in problem.soft() {
+            match x.literals.len() {
+                0 => base_cost += x.weight,
+                // the negation of the literal relaxes a unit clause
+                1 => relaxations.push((-x.literals[0], x.weight)),
+                _ => {
+                    let relaxation = solver.new_variable();
+                    let mut clause = x.literals.clone();
+                    clause.push(relaxation);
+                    solver.add_clause(&clause);
+                    relaxations.push((relaxation, x.weight));
+                }
+            }
+        }
+        MaxSatSolver {
+            solver,
+            n_variables: problem.n_variables(),
+            soft: problem.soft().to_vec(),
+            relaxations,
+            base_cost,
+            on_model: None,
+        }
+    }
+    /// Sets a function called with the cost of every model that is better than the ones before.
+    pub fn set_on_model<F: FnMut(u64) + 'a>(&mut self, f: F) {
+        self.on_model = Some(Box::new(f));
+    }
+    pub fn solve(&mut self, algorithm: Algorithm) -> MaxSatResult {
+        match algorithm {
+            Algorithm::Linear => self.solve_linear(),
+            Algorithm::Oll => self.solve_oll(),
+        }
+    }
+    /// Returns the weight of the soft clauses falsified by `model`,
+    /// which may be less than the weight of its true relaxation literals.
+    fn cost(&self, model: &SatAssignments) -> u64 {
+        let satisfied = |xs: &[i64]| {
+            xs.iter()
+                .any(|&x| model[x.unsigned_abs() as usize - 1] == (x > 0))
+        };
+        self.soft
+            .iter()
+            .filter(|x| !satisfied(&x.literals))
+            .map(|x| x.weight)
+            .sum()
+    }
+    fn found(&mut self, mut model: SatAssignments) -> (u64, SatAssignments) {
+        let cost = self.cost(&model);
+        model.truncate(self.n_variables);
+        if let Some(f) = self.on_model.as_mut() {
+            f(cost);
+        }
+        (cost, model)
+    }
+    fn solve_linear(&mut self) -> MaxSatResult {
+        let (mut cost, mut model) = match self.solver.solve() {
+            Some(model) => self.found(model),
+            None => return MaxSatResult::Unsatisfiable,
+        };
+        if cost > self.base_cost {
+            // the relaxed weight of any better model is less than the one of the first model
+            let relaxations = self.relaxations.clone();
+            let totalizer =
+                GeneralizedTotalizer::new(&mut self.solver, &relaxations, cost - self.base_cost);
+            loop {
+                for x in totalizer.at_least(cost - self.base_cost) {
+                    self.solver.add_clause(&[-x]);
+                }
+                match self.solver.solve() {
+                    Some(x) => {
+                        let (x_cost, x_model) = self.found(x);
+                        assert!(x_cost < cost);
+                        cost = x_cost;
+                        model = x_model;
+                        if cost == self.base_cost {
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            }
+        }
+        MaxSatResult::Optimum { cost, model }
+    }
+    fn solve_oll(&mut self) -> MaxSatResult {
+        let mut lower_bound = self.base_cost;
+        // weight of every assumption, which is the negation of a relaxation literal or a totalizer output
+        let mut weights: BTreeMap<i64, u64> = BTreeMap::new();
+        for &(x, weight) in &self.relaxations {
+            *weights.entry(-x).or_insert(0) += weight;
+        }
+        let mut totalizers: Vec<encodings::Totalizer> = vec![];
+        // totalizer and bound of every assumption that bounds the sum of a totalizer
+        let mut bounds: HashMap<i64, (usize, usize)> = HashMap::new();
+        loop {
+            let assumptions: Vec<i64> = weights.iter().filter(|x| *x.1 > 0).map(|x| *x.0).collect();
+            if let Some(model) = self.solver.solve_with_assumptions(&assumptions) {
+                let (cost, model) = self.found(model);
+                assert_eq!(cost, lower_bound);
+                return MaxSatResult::Optimum { cost, model };
+            }
+            let core = self.solver.failed_assumptions();
+            if core.is_empty() {
+                return MaxSatResult::Unsatisfiable;
+            }
+            let weight = core.iter().map(|x| weights[x]).min().unwrap();
+            lower_bound += weight;
+            for x in &core {
+                *weights.get_mut(x).unwrap() -= weight;
+                // the sum of the totalizer is now known to be larger than its bound
+                if let Some(&(i, k)) = bounds.get(x) {
+                    totalizers[i].increase_bound(&mut self.solver, k + 1);
+                    if let Some(y) = totalizers[i].at_most(k + 1) {
+                        *weights.entry(y).or_insert(0) += weight;
+                        bounds.insert(y, (i, k + 1));
+                    }
+                }
+            }
+            if core.len() == 1 {
+                self.solver.add_clause(&[-core[0]]);
+                continue;
+            }
+            // at most one literal of the core is relaxed for free
+            let inputs: Vec<i64> = core.iter().map(|&x| -x).collect();
+            let totalizer = encodings::Totalizer::new(&mut self.solver, &inputs, 1);
+            let y = totalizer.at_most(1).unwrap();
+            *weights.entry(y).or_insert(0) += weight;
+            bounds.insert(y, (totalizers.len(), 1));
+            totalizers.push(totalizer);
+        }
+    }
+}
+
+#[cfg(test)]
+fn brute_force(problem: &WcnfProblem) -> Option<u64> {
+    let n = problem.n_variables();
+    let mut solver = SatSolver::new(problem.hard());
+    let mut res = None;
+    for bits in 0..1u32 << n {
+        // the model is the assignment itself if it satisfies the hard clauses
+        let assignment: Vec<i64> = (0..n as i64)
+            .map(|i| if bits >> i & 1 == 1 { i + 1 } else { -(i + 1) })
+            .collect();
+        if let Some(model) = solver.solve_with_assumptions(&assignment) {
+            let cost = problem.cost(&model).unwrap();
+            res = Some(res.map_or(cost, |x: u64| x.min(cost)));
+        }
+    }
+    res
+}
+
+#[test]
+fn test_maxsat() {
+    // x1 xor x2 is hard, x1 costs 3 and x2 costs 5
+    let s = "h 1 2 0\nh -1 -2 0\n3 -1 0\n5 -2 0\n";
+    let problem = WcnfProblem::new_from_wcnf(s);
+    for &algorithm in [Algorithm::Linear, Algorithm::Oll].iter() {
+        match MaxSatSolver::new(&problem).solve(algorithm) {
+            MaxSatResult::Optimum { cost, model } => {
+                assert_eq!(cost, 3);
+                assert_eq!(model.to_dimacs(), "1 -2 0");
+            }
+            MaxSatResult::Unsatisfiable => panic!(),
+        }
+    }
+    let problem = WcnfProblem::new_from_wcnf("p wcnf 1 3 10\n10 1 0\n10 -1 0\n1 1 0\n");
+    match MaxSatSolver::new(&problem).solve(Algorithm::Oll) {
+        MaxSatResult::Unsatisfiable => {}
+        x => panic!("{:?}", x),
+    }
+}
+
+#[test]
+fn test_maxsat_against_brute_force() {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..100 {
+        let n_variables = 8;
+        let mut problem = WcnfProblem::new(n_variables);
+        let mut random_clause = |len: usize| -> Vec<i64> {
+            (0..len)
+                .map(|_| {
+                    let x = rng.gen_range(1, n_variables as i64 + 1);
+                    if rng.gen() {
+                        x
+                    } else {
+                        -x
+                    }
+                })
+                .collect()
+        };
+        let clauses: Vec<Vec<i64>> = (0..30).map(|i| random_clause(1 + i % 3)).collect();
+        for (i, clause) in clauses.iter().enumerate() {
+            if i % 3 == 2 {
+                problem.add_hard_clause(clause);
+            } else {
+                problem.add_soft_clause(1 + (i as u64 * 7) % 5, clause);
+            }
+        }
+        let expected = brute_force(&problem);
+        for &algorithm in [Algorithm::Linear, Algorithm::Oll].iter() {
+            match MaxSatSolver::new(&problem).solve(algorithm) {
+                MaxSatResult::Optimum { cost, model } => {
+                    assert_eq!(Some(cost), expected);
+                    assert_eq!(problem.cost(&model), Some(cost));
+                }
+                MaxSatResult::Unsatisfiable => assert_eq!(expected, None),
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Python bindings of `SatProblem` and `SatSolver`.
+
+// the methods of pyo3 0.22 convert their errors into the same type
+#![allow(clippy::useless_conversion)]
+
+use nyat_sat::sat::{SatAssignments, SatProblem as Problem, SatSolver as Solver};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+fn check_literals(literals: &[i64]) -> PyResult<()> {
+    if literals.contains(&0) {
+        return Err(PyValueError::new_err("0 is not a literal"));
+    }
+    Ok(())
+}
+
+/// Problem in CNF over the variables from 1, whose literals are DIMACS integers.
+#[pyclass]
+struct SatProblem {
+    problem: Problem,
+}
+
+#[pymethods]
+impl SatProblem {
+    #[new]
+    #[pyo3(signature = (n_variables = 0))]
+    fn new(n_variables: usize) -> SatProblem {
+        SatProblem {
+            problem: Problem::new(n_variables),
+        }
+    }
+    /// Parses DIMACS.
+    #[staticmethod]
+    fn from_dimacs(s: &str) -> PyResult<SatProblem> {
+        Problem::try_from_dimacs(s)
+            .map(|problem| SatProblem { problem })
+            .map_err(PyValueError::new_err)
+    }
+    #[getter]
+    fn n_variables(&self) -> usize {
+        self.problem.n_variables()
+    }
+    #[getter]
+    fn n_clauses(&self) -> usize {
+        self.problem.n_clauses()
+    }
+    fn new_variable(&mut self) -> i64 {
+        self.problem.new_variable()
+    }
+    fn add_clause(&mut self, literals: Vec<i64>) -> PyResult<()> {
+        check_literals(&literals)?;
+        self.problem.add_clause(&literals);
+        Ok(())
+    }
+    fn add_xor(&mut self, literals: Vec<i64>) -> PyResult<()> {
+        check_literals(&literals)?;
+        self.problem.add_xor(&literals);
+        Ok(())
+    }
+    fn clauses(&self) -> Vec<Vec<i64>> {
+        self.problem.clauses().collect()
+    }
+    fn to_dimacs(&self) -> String {
+        self.problem.to_dimacs()
+    }
+}
+
+/// Incremental solver, which keeps its clauses and learnt clauses from call to call.
+#[pyclass]
+struct SatSolver {
+    solver: Solver<'static>,
+    /// model of the last call, if it was satisfiable
+    model: Option<SatAssignments>,
+}
+
+#[pymethods]
+impl SatSolver {
+    #[new]
+    #[pyo3(signature = (problem = None))]
+    fn new(problem: Option<PyRef<SatProblem>>) -> SatSolver {
+        let solver = match problem {
+            Some(x) => Solver::new(&x.problem),
+            None => Solver::new(&Problem::new(0)),
+        };
+        SatSolver {
+            solver,
+            model: None,
+        }
+    }
+    #[getter]
+    fn n_variables(&self) -> usize {
+        self.solver.n_variables()
+    }
+    fn new_variable(&mut self) -> i64 {
+        self.solver.new_variable()
+    }
+    fn add_clause(&mut self, literals: Vec<i64>) -> PyResult<()> {
+        check_literals(&literals)?;
+        self.solver.add_clause(&literals);
+        Ok(())
+    }
+    fn add_xor(&mut self, literals: Vec<i64>) -> PyResult<()> {
+        check_literals(&literals)?;
+        self.solver.add_xor(&literals);
+        Ok(())
+    }
+    fn add_at_most_k(&mut self, literals: Vec<i64>, k: usize) -> PyResult<()> {
+        check_literals(&literals)?;
+        self.solver.add_native_at_most_k(&literals, k);
+        Ok(())
+    }
+    /// Solves under the assumptions, without the GIL, and returns whether it is satisfiable.
+    #[pyo3(signature = (assumptions = vec![]))]
+    fn solve(&mut self, py: Python, assumptions: Vec<i64>) -> PyResult<bool> {
+        check_literals(&assumptions)?;
+        let solver = &mut self.solver;
+        self.model = py.allow_threads(|| solver.solve_with_assumptions(&assumptions));
+        Ok(self.model.is_some())
+    }
+    /// Returns the model of the last call as DIMACS literals, or None unless it was satisfiable.
+    fn model(&self) -> Option<Vec<i64>> {
+        self.model.as_ref().map(|model| {
+            (0..model.len())
+                .map(|i| {
+                    if model[i] {
+                        i as i64 + 1
+                    } else {
+                        -(i as i64) - 1
+                    }
+                })
+                .collect()
+        })
+    }
+    /// Returns whether the literal is true in the model of the last call.
+    fn value(&self, literal: i64) -> PyResult<bool> {
+        let model = self
+            .model
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("the last call was not satisfiable"))?;
+        let id = literal.unsigned_abs() as usize;
+        if literal == 0 || id > model.len() {
+            return Err(PyValueError::new_err(format!("no variable {}", literal)));
+        }
+        Ok(model[id - 1] == (literal > 0))
+    }
+    /// Returns the assumptions that refuted the last call, or an empty list if it was satisfiable.
+    fn failed_assumptions(&self) -> Vec<i64> {
+        self.solver.failed_assumptions()
+    }
+    fn stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let stats = self.solver.stats();
+        let res = PyDict::new_bound(py);
+        res.set_item("decisions", stats.decisions)?;
+        res.set_item("propagations", stats.propagations)?;
+        res.set_item("conflicts", stats.conflicts)?;
+        res.set_item("restarts", stats.restarts)?;
+        res.set_item("learnt_clauses", stats.learnt_clauses)?;
+        res.set_item("learnt_literals", stats.learnt_literals)?;
+        res.set_item("deleted_clauses", stats.deleted_clauses)?;
+        res.set_item("average_lbd", stats.average_lbd())?;
+        Ok(res)
+    }
+}
+
+#[pymodule]
+fn nyat(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<SatProblem>()?;
+    m.add_class::<SatSolver>()?;
+    Ok(())
+}
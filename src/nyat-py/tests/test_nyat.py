@@ -0,0 +1,86 @@
+import threading
+
+import pytest
+
+import nyat
+
+
+def pigeons(n_pigeons, n_holes):
+    problem = nyat.SatProblem(n_pigeons * n_holes)
+    var = lambda pigeon, hole: pigeon * n_holes + hole + 1
+    for pigeon in range(n_pigeons):
+        problem.add_clause([var(pigeon, hole) for hole in range(n_holes)])
+    for hole in range(n_holes):
+        for i in range(n_pigeons):
+            for k in range(i + 1, n_pigeons):
+                problem.add_clause([-var(i, hole), -var(k, hole)])
+    return problem
+
+
+def test_problem():
+    problem = nyat.SatProblem.from_dimacs("p cnf 3 2\n1 -2 0\n2 3 0\n")
+    assert problem.n_variables == 3
+    assert problem.n_clauses == 2
+    assert problem.clauses() == [[1, -2], [2, 3]]
+    assert problem.new_variable() == 4
+    problem.add_clause([-4])
+    assert problem.to_dimacs() == "p cnf 4 3\n1 -2 0\n2 3 0\n-4 0\n"
+    with pytest.raises(ValueError):
+        problem.add_clause([1, 0])
+    with pytest.raises(ValueError):
+        nyat.SatProblem.from_dimacs("p dnf 1 1\n")
+
+
+def test_solve_with_assumptions():
+    problem = nyat.SatProblem.from_dimacs("p cnf 3 2\n1 2 0\n-1 3 0\n")
+    solver = nyat.SatSolver(problem)
+    assert solver.solve([-3])
+    assert solver.model() == [-1, 2, -3]
+    assert solver.value(2) and not solver.value(1)
+    solver.add_clause([-2])
+    assert not solver.solve([-3])
+    assert solver.model() is None
+    assert solver.failed_assumptions() == [-3]
+    with pytest.raises(ValueError):
+        solver.value(1)
+    assert solver.solve()
+    assert solver.value(3)
+
+
+def test_incremental_solver():
+    solver = nyat.SatSolver()
+    x = [solver.new_variable() for _ in range(4)]
+    solver.add_at_most_k(x, 1)
+    solver.add_xor([x[0], x[1]])
+    assert solver.solve([x[0]])
+    assert solver.model() == [1, -2, -3, -4]
+    assert not solver.solve([x[2]])
+
+
+def test_stats():
+    solver = nyat.SatSolver(pigeons(7, 6))
+    assert not solver.solve()
+    stats = solver.stats()
+    assert stats["conflicts"] > 0
+    assert stats["learnt_clauses"] <= stats["conflicts"]
+
+
+def test_solve_releases_gil():
+    # the searches overlap, and a thread of Python runs meanwhile
+    solvers = [nyat.SatSolver(pigeons(8, 7)) for _ in range(2)]
+    results = [None, None]
+    ticks = []
+
+    def solve(i):
+        results[i] = solvers[i].solve()
+
+    threads = [threading.Thread(target=solve, args=(i,)) for i in range(2)]
+    for thread in threads:
+        thread.start()
+    while any(thread.is_alive() for thread in threads):
+        ticks.append(1)
+        threads[0].join(0.001)
+    for thread in threads:
+        thread.join()
+    assert results == [False, False]
+    assert len(ticks) > 1
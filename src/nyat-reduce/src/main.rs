@@ -0,0 +1,68 @@
+use nyat_reduce::reduce::*;
+use nyat_sat::sat::SatProblem;
+
+struct Options {
+    input: String,
+    output: Option<String>,
+    predicate: Predicate,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Options {
+        let mut input = None;
+        let mut output = None;
+        let mut predicate = None;
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--output" => output = Some(iter.next().expect("--output needs a path").clone()),
+                "--panics" => predicate = Some(Predicate::Panics),
+                "--differs" => predicate = Some(Predicate::Differs),
+                // the rest of the arguments are the command
+                "--command" => {
+                    let command: Vec<String> = iter.by_ref().cloned().collect();
+                    assert!(!command.is_empty(), "--command needs a command");
+                    predicate = Some(Predicate::Command(command));
+                }
+                _ => {
+                    assert!(input.is_none(), "unexpected argument: {}", arg);
+                    input = Some(arg.clone());
+                }
+            }
+        }
+        Options {
+            input: input.expect("no input file"),
+            output,
+            predicate: predicate.expect("the predicate is --panics, --differs or --command"),
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let options = Options::parse(&args);
+    let problem = SatProblem::new_from_dimacs(&std::fs::read_to_string(&options.input).unwrap());
+    eprintln!(
+        "c input: {} variables, {} clauses, {} literals",
+        problem.n_variables(),
+        problem.n_clauses(),
+        size(&problem)
+    );
+    // the smallest problem so far is written, so that an interrupted reduction keeps it
+    let res = reduce(&problem, &options.predicate, |p| {
+        eprintln!("c {} clauses, {} literals", p.n_clauses(), size(p));
+        if let Some(path) = &options.output {
+            std::fs::write(path, p.to_dimacs()).unwrap();
+        }
+    });
+    eprintln!(
+        "c output: {} variables, {} clauses, {} literals",
+        res.n_variables(),
+        res.n_clauses(),
+        size(&res)
+    );
+    match &options.output {
+        Some(path) => std::fs::write(path, res.to_dimacs()).unwrap(),
+        None => print!("{}", res.to_dimacs()),
+    }
+}
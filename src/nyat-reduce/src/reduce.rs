@@ -0,0 +1,174 @@
+use nyat_sat::fuzz::{renumber, shrink};
+use nyat_sat::proof::check_drup;
+use nyat_sat::sat::{SatAssignments, SatProblem, SatSolver};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::process::{Command, Stdio};
+
+/// Predicate of a failing problem, which is kept by the reduction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// command that is given the DIMACS file after its arguments, and exits with 0 if the problem fails
+    Command(Vec<String>),
+    /// the solver panics
+    Panics,
+    /// the model or the DRUP proof of the solver is wrong, or the solver is UNSAT with XOR constraints,
+    /// but another configuration of it finds a model of them expanded into clauses
+    Differs,
+}
+
+/// Calls `f` with the panic messages silenced, and returns its result or None if it panics.
+fn quietly<T, F: FnOnce() -> T>(f: F) -> Option<T> {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let res = catch_unwind(AssertUnwindSafe(f));
+    std::panic::set_hook(hook);
+    res.ok()
+}
+
+impl Predicate {
+    pub fn holds(&self, problem: &SatProblem) -> bool {
+        match self {
+            Predicate::Command(command) => {
+                let path =
+                    std::env::temp_dir().join(format!("nyat-reduce-{}.cnf", std::process::id()));
+                std::fs::write(&path, problem.to_dimacs()).unwrap();
+                let status = Command::new(&command[0])
+                    .args(&command[1..])
+                    .arg(&path)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()
+                    .unwrap_or_else(|e| panic!("failed to run {}: {}", command[0], e));
+                let _ = std::fs::remove_file(&path);
+                status.success()
+            }
+            Predicate::Panics => quietly(|| SatSolver::new(problem).solve()).is_none(),
+            // a panic is another failure than the one to keep
+            Predicate::Differs => {
+                quietly(|| {
+                    let prove = problem.xors().count() == 0;
+                    let mut proof = String::new();
+                    let res = {
+                        let mut solver = SatSolver::new(problem);
+                        if prove {
+                            solver.set_proof(|step| {
+                                proof.push_str(&step.to_drup());
+                                proof.push('\n');
+                            });
+                        }
+                        solver.solve()
+                    };
+                    is_wrong(
+                        problem,
+                        res.as_ref(),
+                        if prove { Some(&proof) } else { None },
+                    )
+                }) == Some(true)
+            }
+        }
+    }
+}
+
+/// Returns whether an answer of the solver is wrong: its model does not satisfy the problem,
+/// its DRUP proof is wrong, or without a proof, a solver with another seed, decision order and phases
+/// finds a model of the problem with the XOR constraints expanded into clauses.
+fn is_wrong(problem: &SatProblem, res: Option<&SatAssignments>, proof: Option<&str>) -> bool {
+    match (res, proof) {
+        (Some(model), _) => !problem.check_assignment(model),
+        (None, Some(proof)) => !check_drup(problem, proof),
+        (None, None) => {
+            let expanded = problem.expand_xors();
+            let mut solver = SatSolver::new(&expanded);
+            solver.set_seed(1);
+            solver.shuffle_decision_order();
+            solver.randomize_phases();
+            solver.set_restart_interval(50);
+            match solver.solve() {
+                Some(model) => expanded.check_assignment(&model),
+                None => false,
+            }
+        }
+    }
+}
+
+/// Returns the number of the literals of a problem, which the reduction decreases.
+pub fn size(problem: &SatProblem) -> usize {
+    problem
+        .clauses()
+        .chain(problem.xors())
+        .map(|x| x.len())
+        .sum()
+}
+
+/// Shrinks a problem for which the predicate holds by removing the clauses, then the literals,
+/// and then renumbering the variables, and calls `on_smaller` with every smaller problem on the way.
+pub fn reduce<F: FnMut(&SatProblem)>(
+    problem: &SatProblem,
+    predicate: &Predicate,
+    mut on_smaller: F,
+) -> SatProblem {
+    assert!(
+        predicate.holds(problem),
+        "the predicate does not hold for the input"
+    );
+    // every problem for which it holds is smaller than the ones before
+    let res = shrink(problem, |p| {
+        let holds = predicate.holds(p);
+        if holds {
+            on_smaller(p);
+        }
+        holds
+    });
+    let renumbered = renumber(&res);
+    if renumbered.n_variables() < res.n_variables() && predicate.holds(&renumbered) {
+        on_smaller(&renumbered);
+        renumbered
+    } else {
+        res
+    }
+}
+
+#[test]
+fn test_builtin_predicates() {
+    let problem = SatProblem::new_from_dimacs("p cnf 3 3\n1 2 0\n-1 3 0\nx2 3 0\n");
+    assert!(!Predicate::Panics.holds(&problem));
+    assert!(!Predicate::Differs.holds(&problem));
+    assert_eq!(size(&problem), 6);
+    let model = SatSolver::new(&problem).solve().unwrap();
+    assert!(!is_wrong(&problem, Some(&model), None));
+    // -1 2 3, where 2 and 3 are not odd
+    let wrong = SatSolver::new(&SatProblem::new_from_dimacs("p cnf 3 3\n-1 0\n2 0\n3 0\n"))
+        .solve()
+        .unwrap();
+    assert!(is_wrong(&problem, Some(&wrong), None));
+    assert!(is_wrong(&problem, None, None));
+    let unsat = SatProblem::new_from_dimacs("p cnf 2 4\n1 2 0\n-1 2 0\n1 -2 0\n-1 -2 0\n");
+    assert!(!is_wrong(&unsat, None, Some("1 0\n0\n")));
+    assert!(is_wrong(&unsat, None, Some("0\n")));
+    assert!(!is_wrong(&unsat.expand_xors(), None, None));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_reduce_by_command() {
+    // the problems with a clause of -3 and 5, and a literal of x7
+    let script = "grep -q -e '^-3 5 0' -e '^5 -3 0' \"$1\" && grep -q -w 7 \"$1\"";
+    let predicate = Predicate::Command(vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        script.to_string(),
+        "sh".to_string(),
+    ]);
+    let problem =
+        SatProblem::new_from_dimacs("p cnf 8 5\n1 2 0\n-3 5 0\n4 -6 7 0\n8 0\n-1 -2 -3 0\n");
+    let mut n_smaller = 0;
+    let mut last = String::new();
+    let res = reduce(&problem, &predicate, |p| {
+        n_smaller += 1;
+        last = p.to_dimacs();
+    });
+    assert_eq!(res.to_dimacs(), "p cnf 8 2\n-3 5 0\n7 0\n");
+    assert_eq!(res.to_dimacs(), last);
+    assert!(n_smaller >= 2);
+}
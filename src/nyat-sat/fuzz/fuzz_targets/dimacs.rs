@@ -0,0 +1,24 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use nyat_sat::fuzz::problem_from_bytes;
+use nyat_sat::sat::SatProblem;
+
+// The DIMACS of a problem is parsed back into the same problem after comments and other whitespace
+// are put between its tokens.
+fuzz_target!(|data: &[u8]| {
+    let (problem, _) = problem_from_bytes(data);
+    let text = problem.to_dimacs();
+    let mut lines = text.lines();
+    let mut noisy = format!("c fuzz\n{}\n", lines.next().unwrap());
+    for (i, token) in lines.flat_map(|x| x.split_whitespace()).enumerate() {
+        noisy.push_str(token);
+        noisy.push_str(match data.get(i).map(|b| b % 5) {
+            Some(1) => "  ",
+            Some(2) => "\n",
+            Some(3) => "\t",
+            Some(4) => "\r\nc comment\n",
+            _ => " ",
+        });
+    }
+    assert_eq!(SatProblem::new_from_dimacs(&noisy).to_dimacs(), text);
+});
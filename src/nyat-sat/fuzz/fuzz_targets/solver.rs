@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use nyat_sat::fuzz::{differential, problem_from_bytes, shrink};
+
+// The solver agrees with the references, and a failing problem is shrunk into a DIMACS reproducer.
+fuzz_target!(|data: &[u8]| {
+    let (problem, assumptions) = problem_from_bytes(data);
+    if let Err(e) = differential(&problem, &assumptions) {
+        let small = shrink(&problem, |p| differential(p, &assumptions).is_err());
+        panic!("{}\nassumptions: {:?}\n{}", e, assumptions, small.to_dimacs());
+    }
+});
@@ -0,0 +1,456 @@
+use crate::encodings::{ClauseSink, TseitinEncoding};
+use crate::sat::{SatAssignments, SatProblem};
+
+/// And gate `lhs = rhs0 & rhs1` over AIGER literals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AndGate {
+    pub lhs: u64,
+    pub rhs0: u64,
+    pub rhs1: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Latch {
+    pub literal: u64,
+    pub next: u64,
+    /// initial value, or `None` if the latch is uninitialized
+    pub reset: Option<bool>,
+}
+
+/// And-inverter graph of the AIGER format, where the literal `2v` is the variable `v`,
+/// `2v + 1` is its negation, and 0 and 1 are the constants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aig {
+    pub max_variable: u64,
+    pub inputs: Vec<u64>,
+    pub latches: Vec<Latch>,
+    pub outputs: Vec<u64>,
+    /// bad state properties
+    pub bad: Vec<u64>,
+    /// invariant constraints, which are true in every step
+    pub constraints: Vec<u64>,
+    pub ands: Vec<AndGate>,
+}
+
+/// Reads the lines and the binary and gates of an AIGER file.
+struct Reader<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> Reader<'b> {
+    fn line(&mut self) -> Vec<u64> {
+        let start = self.pos;
+        while self.pos < self.bytes.len() && self.bytes[self.pos] != b'\n' {
+            self.pos += 1;
+        }
+        let line = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap();
+        self.pos += 1;
+        line.split_whitespace()
+            .map(|t| t.parse::<u64>().unwrap())
+            .collect()
+    }
+    /// Reads an unsigned integer of 7 bits per byte, from the lowest ones.
+    fn varint(&mut self) -> u64 {
+        let mut res = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.bytes[self.pos];
+            self.pos += 1;
+            res |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return res;
+            }
+            shift += 7;
+        }
+    }
+}
+
+impl Aig {
+    /// Parses either the ASCII format `aag` or the binary format `aig`, without the symbol table.
+    pub fn parse(bytes: &[u8]) -> Aig {
+        let end = bytes
+            .iter()
+            .position(|&x| x == b'\n')
+            .unwrap_or(bytes.len());
+        let header: Vec<&str> = std::str::from_utf8(&bytes[..end])
+            .unwrap()
+            .split_whitespace()
+            .collect();
+        let binary = match header[0] {
+            "aag" => false,
+            "aig" => true,
+            x => panic!("unexpected format: {}", x),
+        };
+        let numbers: Vec<u64> = header[1..]
+            .iter()
+            .map(|t| t.parse::<u64>().unwrap())
+            .collect();
+        let number = |i: usize| numbers.get(i).cloned().unwrap_or(0);
+        let (n_inputs, n_latches, n_outputs, n_ands) = (number(1), number(2), number(3), number(4));
+        let (n_bad, n_constraints) = (number(5), number(6));
+        assert!(
+            number(7) == 0 && number(8) == 0,
+            "justice and fairness properties are not supported"
+        );
+        let mut reader = Reader {
+            bytes,
+            pos: end + 1,
+        };
+        let mut res = Aig {
+            max_variable: number(0),
+            inputs: vec![],
+            latches: vec![],
+            outputs: vec![],
+            bad: vec![],
+            constraints: vec![],
+            ands: vec![],
+        };
+        for i in 0..n_inputs {
+            res.inputs.push(if binary {
+                2 * (i + 1)
+            } else {
+                reader.line()[0]
+            });
+        }
+        for i in 0..n_latches {
+            let mut xs = reader.line();
+            if binary {
+                xs.insert(0, 2 * (n_inputs + i + 1));
+            }
+            let reset = match xs.get(2) {
+                None | Some(0) => Some(false),
+                Some(1) => Some(true),
+                Some(&x) => {
+                    assert_eq!(x, xs[0], "a latch resets to 0, 1 or itself");
+                    None
+                }
+            };
+            res.latches.push(Latch {
+                literal: xs[0],
+                next: xs[1],
+                reset,
+            });
+        }
+        for _ in 0..n_outputs {
+            res.outputs.push(reader.line()[0]);
+        }
+        for _ in 0..n_bad {
+            res.bad.push(reader.line()[0]);
+        }
+        for _ in 0..n_constraints {
+            res.constraints.push(reader.line()[0]);
+        }
+        for i in 0..n_ands {
+            let gate = if binary {
+                // the differences to the left-hand side, which is implicit, and to the first input
+                let lhs = 2 * (n_inputs + n_latches + i + 1);
+                let rhs0 = lhs - reader.varint();
+                let rhs1 = rhs0 - reader.varint();
+                AndGate { lhs, rhs0, rhs1 }
+            } else {
+                let xs = reader.line();
+                AndGate {
+                    lhs: xs[0],
+                    rhs0: xs[1],
+                    rhs1: xs[2],
+                }
+            };
+            res.ands.push(gate);
+        }
+        res
+    }
+    /// Returns the and gate of every variable.
+    fn gates(&self) -> Vec<Option<AndGate>> {
+        let mut res = vec![None; self.max_variable as usize + 1];
+        for gate in &self.ands {
+            res[gate.lhs as usize / 2] = Some(*gate);
+        }
+        res
+    }
+    /// Returns the value of every variable under the values of the inputs and the latches.
+    pub fn evaluate(&self, inputs: &[bool], latches: &[bool]) -> Vec<bool> {
+        let gates = self.gates();
+        let mut values: Vec<Option<bool>> = vec![None; self.max_variable as usize + 1];
+        values[0] = Some(false);
+        for (x, &value) in self.inputs.iter().zip(inputs) {
+            values[*x as usize / 2] = Some(value);
+        }
+        for (latch, &value) in self.latches.iter().zip(latches) {
+            values[latch.literal as usize / 2] = Some(value);
+        }
+        let literal_value =
+            |values: &[Option<bool>], x: u64| values[x as usize / 2].map(|y| y != (x & 1 == 1));
+        for v in 0..values.len() {
+            let mut stack = vec![v];
+            // the gates are evaluated after their inputs
+            while let Some(&u) = stack.last() {
+                if values[u].is_some() {
+                    stack.pop();
+                    continue;
+                }
+                let gate = match gates[u] {
+                    Some(gate) => gate,
+                    // a variable that is not used, since the indices may have gaps
+                    None => {
+                        values[u] = Some(false);
+                        continue;
+                    }
+                };
+                match (
+                    literal_value(&values, gate.rhs0),
+                    literal_value(&values, gate.rhs1),
+                ) {
+                    (Some(x), Some(y)) => {
+                        values[u] = Some(x && y);
+                        stack.pop();
+                    }
+                    (x, y) => {
+                        if x.is_none() {
+                            stack.push(gate.rhs0 as usize / 2);
+                        }
+                        if y.is_none() {
+                            stack.push(gate.rhs1 as usize / 2);
+                        }
+                    }
+                }
+            }
+        }
+        values.into_iter().map(|x| x.unwrap()).collect()
+    }
+    /// Adds the clauses of the gates on which `roots` depend, where `variables` has the DIMACS literal
+    /// of every variable and a false one for the constants. The roots are the literals that
+    /// occur positively in the clauses of the caller, so both a literal and its negation are
+    /// roots if they are defined by an equivalence.
+    pub fn encode<S: ClauseSink>(
+        &self,
+        sink: &mut S,
+        variables: &[i64],
+        roots: &[u64],
+        encoding: TseitinEncoding,
+    ) {
+        let gates = self.gates();
+        // the polarities of every variable, 1 for positive and 2 for negative
+        let mut polarities = vec![0u8; self.max_variable as usize + 1];
+        let mut stack: Vec<(usize, u8)> = vec![];
+        let polarity_of = |x: u64, positive: bool| {
+            if (x & 1 == 0) == positive {
+                1
+            } else {
+                2
+            }
+        };
+        for &x in roots {
+            let polarity = match encoding {
+                TseitinEncoding::Full => 3,
+                TseitinEncoding::PlaistedGreenbaum => polarity_of(x, true),
+            };
+            stack.push((x as usize / 2, polarity));
+        }
+        while let Some((v, polarity)) = stack.pop() {
+            let added = polarity & !polarities[v];
+            if added == 0 {
+                continue;
+            }
+            polarities[v] |= added;
+            let gate = match gates[v] {
+                Some(gate) => gate,
+                None => continue,
+            };
+            let literal = |x: u64| {
+                let y = variables[x as usize / 2];
+                if x & 1 == 1 {
+                    -y
+                } else {
+                    y
+                }
+            };
+            let (g, a, b) = (literal(gate.lhs), literal(gate.rhs0), literal(gate.rhs1));
+            let mut children = 0;
+            if added & 1 != 0 {
+                sink.add_clause(&[-g, a]);
+                sink.add_clause(&[-g, b]);
+                children |= 1;
+            }
+            if added & 2 != 0 {
+                sink.add_clause(&[g, -a, -b]);
+                children |= 2;
+            }
+            for &x in &[gate.rhs0, gate.rhs1] {
+                let mut polarity = 0;
+                if children & 1 != 0 {
+                    polarity |= polarity_of(x, true);
+                }
+                if children & 2 != 0 {
+                    polarity |= polarity_of(x, false);
+                }
+                stack.push((x as usize / 2, polarity));
+            }
+        }
+    }
+    /// Encodes that one of the outputs is true, or one of the bad state properties if there are
+    /// no outputs, under the invariant constraints, where the latches are free like the inputs.
+    /// The DIMACS variable `v` is the variable `v`, and the next one is false for the constants.
+    pub fn to_sat_problem(&self, encoding: TseitinEncoding) -> AigEncoding {
+        let n = self.max_variable as i64;
+        let mut problem = SatProblem::new(n as usize + 1);
+        let mut variables: Vec<i64> = (0..=n).collect();
+        variables[0] = n + 1;
+        problem.add_clause(&[-(n + 1)]);
+        let targets = if self.outputs.is_empty() {
+            &self.bad
+        } else {
+            &self.outputs
+        };
+        let roots: Vec<u64> = targets.iter().chain(&self.constraints).cloned().collect();
+        self.encode(&mut problem, &variables, &roots, encoding);
+        let literal = |x: u64| {
+            let y = variables[x as usize / 2];
+            if x & 1 == 1 {
+                -y
+            } else {
+                y
+            }
+        };
+        let clause: Vec<i64> = targets.iter().map(|&x| literal(x)).collect();
+        problem.add_clause(&clause);
+        for &x in &self.constraints {
+            problem.add_clause(&[literal(x)]);
+        }
+        AigEncoding {
+            problem,
+            inputs: self.inputs.iter().map(|&x| literal(x)).collect(),
+            latches: self.latches.iter().map(|x| literal(x.literal)).collect(),
+        }
+    }
+}
+
+/// CNF of an AIG, with the DIMACS variables of its inputs and latches.
+#[derive(Debug)]
+pub struct AigEncoding {
+    problem: SatProblem,
+    inputs: Vec<i64>,
+    latches: Vec<i64>,
+}
+
+impl AigEncoding {
+    pub fn problem(&self) -> &SatProblem {
+        &self.problem
+    }
+    pub fn inputs(&self) -> &[i64] {
+        &self.inputs
+    }
+    pub fn latches(&self) -> &[i64] {
+        &self.latches
+    }
+    /// Returns the values of the inputs in a model of the problem.
+    pub fn input_vector(&self, model: &SatAssignments) -> Vec<bool> {
+        self.inputs.iter().map(|&x| model[x as usize - 1]).collect()
+    }
+    /// Returns the values of the latches in a model of the problem.
+    pub fn latch_vector(&self, model: &SatAssignments) -> Vec<bool> {
+        self.latches
+            .iter()
+            .map(|&x| model[x as usize - 1])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+const HALF_ADDER: &str = "aag 7 2 0 2 3\n2\n4\n6\n12\n6 13 15\n12 2 4\n14 3 5\n";
+
+#[test]
+fn test_parse_aiger() {
+    let aig = Aig::parse(HALF_ADDER.as_bytes());
+    assert_eq!(aig.max_variable, 7);
+    assert_eq!(aig.inputs, vec![2, 4]);
+    assert_eq!(aig.outputs, vec![6, 12]);
+    assert_eq!(
+        aig.ands[0],
+        AndGate {
+            lhs: 6,
+            rhs0: 13,
+            rhs1: 15
+        }
+    );
+    // a latch of literal 6 in the binary format, where the gates are implicit and sorted
+    let mut bytes = b"aig 6 2 1 1 3\n12 1\n10\n".to_vec();
+    // 8 = 4 & 2, 10 = 7 & 6, 12 = 11 & 8
+    bytes.extend_from_slice(&[8 - 4, 4 - 2, 10 - 7, 7 - 6, 12 - 11, 11 - 8]);
+    let aig = Aig::parse(&bytes);
+    assert_eq!(aig.inputs, vec![2, 4]);
+    assert_eq!(
+        aig.latches,
+        vec![Latch {
+            literal: 6,
+            next: 12,
+            reset: Some(true)
+        }]
+    );
+    assert_eq!(aig.outputs, vec![10]);
+    assert_eq!(
+        aig.ands[2],
+        AndGate {
+            lhs: 12,
+            rhs0: 11,
+            rhs1: 8
+        }
+    );
+}
+
+#[test]
+fn test_tseitin_against_simulation() {
+    use crate::sat::SatSolver;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..100 {
+        // random gates over 4 inputs and 1 latch, with two outputs
+        let n_gates = rng.gen_range(1, 12);
+        let mut aig = Aig {
+            max_variable: 5 + n_gates,
+            inputs: vec![2, 4, 6, 8],
+            latches: vec![Latch {
+                literal: 10,
+                next: 0,
+                reset: Some(false),
+            }],
+            outputs: vec![],
+            bad: vec![],
+            constraints: vec![],
+            ands: vec![],
+        };
+        for v in 6..=aig.max_variable {
+            let mut input = || rng.gen_range(0, 2 * v);
+            aig.ands.push(AndGate {
+                lhs: 2 * v,
+                rhs0: input(),
+                rhs1: input(),
+            });
+        }
+        let n_literals = 2 * aig.max_variable + 2;
+        aig.outputs = vec![rng.gen_range(0, n_literals), rng.gen_range(0, n_literals)];
+        if rng.gen() {
+            aig.constraints.push(rng.gen_range(0, n_literals));
+        }
+        let value = |values: &[bool], x: u64| values[x as usize / 2] != (x & 1 == 1);
+        let expected = (0..1 << 5).any(|bits: u32| {
+            let xs: Vec<bool> = (0..5).map(|i| bits >> i & 1 == 1).collect();
+            let values = aig.evaluate(&xs[..4], &xs[4..]);
+            aig.outputs.iter().any(|&x| value(&values, x))
+                && aig.constraints.iter().all(|&x| value(&values, x))
+        });
+        for &encoding in [TseitinEncoding::Full, TseitinEncoding::PlaistedGreenbaum].iter() {
+            let cnf = aig.to_sat_problem(encoding);
+            match SatSolver::new(cnf.problem()).solve() {
+                Some(model) => {
+                    assert!(expected);
+                    let values = aig.evaluate(&cnf.input_vector(&model), &cnf.latch_vector(&model));
+                    assert!(aig.outputs.iter().any(|&x| value(&values, x)));
+                    assert!(aig.constraints.iter().all(|&x| value(&values, x)));
+                }
+                None => assert!(!expected),
+            }
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use crate::sat::{SatAssignments, SatProblem, SatSolver};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// variables with the most occurrences that are looked ahead at every node
+const MAX_CANDIDATES: usize = 50;
+/// weight of a reduced clause per literal beyond the binary ones
+const LENGTH_WEIGHT: f64 = 0.2;
+/// factor of the product of the weights in the score, so that the sum only breaks the ties of the products
+const PRODUCT_FACTOR: f64 = 1024.0;
+
+fn index(literal: i64) -> usize {
+    2 * (literal.unsigned_abs() as usize - 1) + if literal > 0 { 0 } else { 1 }
+}
+
+/// Lookahead cuber, which splits the problem into cubes of literals by a binary tree of decisions.
+/// Every free candidate variable is propagated in both signs, and the one that reduces the most
+/// clauses in both is split on, as in march: a reduced clause weighs `LENGTH_WEIGHT` per literal
+/// beyond two, and the score is `PRODUCT_FACTOR` times the product of the weights of the signs
+/// plus their sum.
+/// A failed literal, whose propagation conflicts, is negated in the cube instead.
+pub struct Cuber<'a> {
+    solver: SatSolver<'a>,
+    clauses: Vec<Vec<i64>>,
+    /// clauses of every literal, at `2 * id` for the positive one and `2 * id + 1` for the negative one
+    occurrences: Vec<Vec<usize>>,
+    /// variables by decreasing number of occurrences
+    candidates: Vec<usize>,
+}
+
+impl<'a> Cuber<'a> {
+    pub fn new(problem: &SatProblem) -> Cuber<'a> {
+        let n = problem.n_variables();
+        let clauses: Vec<Vec<i64>> = problem.clauses().collect();
+        let mut occurrences = vec![vec![]; 2 * n];
+        for (i, clause) in clauses.iter().enumerate() {
+            for &x in clause {
+                occurrences[index(x)].push(i);
+            }
+        }
+        let mut candidates: Vec<usize> = (0..n).collect();
+        candidates.sort_by_key(|&id| {
+            std::cmp::Reverse(occurrences[2 * id].len() + occurrences[2 * id + 1].len())
+        });
+        Cuber {
+            solver: SatSolver::new(problem),
+            clauses,
+            occurrences,
+            candidates,
+        }
+    }
+    /// Returns the cubes of the leaves of the tree of `depth` decisions, besides the failed literals.
+    /// A model of the problem satisfies one of the cubes, and the refuted ones are dropped,
+    /// so there are none if the propagation refutes the problem.
+    pub fn cubes(&mut self, depth: usize) -> Vec<Vec<i64>> {
+        let mut res = vec![];
+        self.split(&mut vec![], depth, &mut res);
+        res
+    }
+    fn split(&mut self, cube: &mut Vec<i64>, depth: usize, res: &mut Vec<Vec<i64>>) {
+        let n_literals = cube.len();
+        let variable = match self.lookahead(cube) {
+            Some(variable) => variable,
+            // refuted
+            None => {
+                cube.truncate(n_literals);
+                return;
+            }
+        };
+        match variable {
+            Some(id) if depth > 0 => {
+                for &x in [id as i64 + 1, -(id as i64) - 1].iter() {
+                    cube.push(x);
+                    self.split(cube, depth - 1, res);
+                    cube.pop();
+                }
+            }
+            // a leaf, or every variable is assigned without conflicts
+            _ => res.push(cube.clone()),
+        }
+        cube.truncate(n_literals);
+    }
+    /// Appends the failed literals to the cube, and returns the variable to split on,
+    /// or `None` if the cube is refuted, or `Some(None)` if no variable is free.
+    fn lookahead(&mut self, cube: &mut Vec<i64>) -> Option<Option<usize>> {
+        'l1: loop {
+            let trues = self.solver.propagate(cube).ok()?;
+            let mut values: Vec<Option<bool>> = vec![None; self.candidates.len()];
+            for &x in &trues {
+                values[x.unsigned_abs() as usize - 1] = Some(x > 0);
+            }
+            let mut best: Option<(f64, usize)> = None;
+            let candidates: Vec<usize> = self
+                .candidates
+                .iter()
+                .cloned()
+                .filter(|&id| values[id].is_none())
+                .take(MAX_CANDIDATES)
+                .collect();
+            for id in candidates {
+                let mut weights = [0.0; 2];
+                for (k, &x) in [id as i64 + 1, -(id as i64) - 1].iter().enumerate() {
+                    cube.push(x);
+                    let res = self.solver.propagate(cube);
+                    cube.pop();
+                    match res {
+                        Ok(implied) => weights[k] = self.reduction(&values, &implied),
+                        Err(_) => {
+                            // the negation is implied, so both signs fail if it fails too
+                            cube.push(-x);
+                            continue 'l1;
+                        }
+                    }
+                }
+                let score = PRODUCT_FACTOR * weights[0] * weights[1] + weights[0] + weights[1];
+                if best.is_none_or(|(x, _)| score > x) {
+                    best = Some((score, id));
+                }
+            }
+            return Some(best.map(|x| x.1));
+        }
+    }
+    /// Returns the weight of the clauses reduced but not satisfied by the literals implied
+    /// besides the assigned ones.
+    fn reduction(&self, values: &[Option<bool>], implied: &[i64]) -> f64 {
+        let mut next = values.to_vec();
+        for &x in implied {
+            next[x.unsigned_abs() as usize - 1] = Some(x > 0);
+        }
+        let mut seen = HashSet::new();
+        let mut res = 0.0;
+        for &x in implied {
+            if values[x.unsigned_abs() as usize - 1].is_some() {
+                continue;
+            }
+            for &i in &self.occurrences[index(-x)] {
+                if !seen.insert(i) {
+                    continue;
+                }
+                let clause = &self.clauses[i];
+                let value = |y: i64| next[y.unsigned_abs() as usize - 1].map(|z| z == (y > 0));
+                if clause.iter().any(|&y| value(y) == Some(true)) {
+                    continue;
+                }
+                let n_free = clause.iter().filter(|&&y| value(y).is_none()).count();
+                res += LENGTH_WEIGHT.powi(n_free as i32 - 2);
+            }
+        }
+        res
+    }
+}
+
+/// Formats the problem and the cubes as iCNF, where every cube is an `a` line of assumptions.
+pub fn to_icnf(problem: &SatProblem, cubes: &[Vec<i64>]) -> String {
+    let dimacs = problem.to_dimacs();
+    let mut res = String::from("p inccnf\n");
+    res.push_str(&dimacs[dimacs.find('\n').unwrap() + 1..]);
+    for cube in cubes {
+        res.push('a');
+        for &x in cube {
+            res.push_str(&format!(" {}", x));
+        }
+        res.push_str(" 0\n");
+    }
+    res
+}
+
+/// Solves the cubes under assumptions by incremental solvers in threads, which keep their learnt
+/// clauses from cube to cube. The first model stops the others, and so does a refuted cube whose
+/// failed assumptions are empty, which refutes the problem.
+pub fn conquer(
+    problem: &SatProblem,
+    cubes: &[Vec<i64>],
+    n_threads: usize,
+) -> Option<SatAssignments> {
+    assert!(n_threads > 0);
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let results: Vec<Option<SatAssignments>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..n_threads)
+            .map(|_| {
+                let (next, stop) = (&next, &stop);
+                scope.spawn(move || {
+                    let mut solver = SatSolver::new(problem);
+                    solver.set_terminate(move || stop.load(Ordering::Relaxed));
+                    while !stop.load(Ordering::Relaxed) {
+                        let i = next.fetch_add(1, Ordering::SeqCst);
+                        if i >= cubes.len() {
+                            break;
+                        }
+                        let res = solver.solve_with_assumptions(&cubes[i]);
+                        if solver.interrupted() {
+                            break;
+                        }
+                        if res.is_some() || solver.failed_assumptions().is_empty() {
+                            info!("cube {} finished the search", i);
+                            stop.store(true, Ordering::SeqCst);
+                            return res;
+                        }
+                    }
+                    None
+                })
+            })
+            .collect();
+        handles.into_iter().map(|x| x.join().unwrap()).collect()
+    });
+    results.into_iter().flatten().next()
+}
+
+#[test]
+fn test_cube_and_conquer() {
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
+    for n_threads in 1..4 {
+        for _ in 0..20 {
+            // around the threshold, so that about half of them are unsatisfiable
+            let mut problem = SatProblem::new(30);
+            let mut ids: Vec<i64> = (1..=30).collect();
+            for _ in 0..128 {
+                ids.shuffle(&mut rng);
+                let clause: Vec<i64> = ids[..3]
+                    .iter()
+                    .map(|&x| if rng.gen() { x } else { -x })
+                    .collect();
+                problem.add_clause(&clause);
+            }
+            let expected = SatSolver::new(&problem).solve();
+            let cubes = Cuber::new(&problem).cubes(4);
+            assert!(cubes.len() <= 16);
+            // every model satisfies a cube
+            if let Some(model) = &expected {
+                assert!(cubes.iter().any(|cube| cube
+                    .iter()
+                    .all(|&x| model[x.unsigned_abs() as usize - 1] == (x > 0))));
+            }
+            let res = conquer(&problem, &cubes, n_threads);
+            assert_eq!(res.is_some(), expected.is_some());
+            if let Some(model) = res {
+                assert!(problem.check_assignment(&model));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_to_icnf() {
+    let problem = SatProblem::new_from_dimacs("p cnf 3 2\n1 2 0\n-2 3 0\n");
+    let cubes = vec![vec![2, 3], vec![-2]];
+    assert_eq!(
+        to_icnf(&problem, &cubes),
+        "p inccnf\n1 2 0\n-2 3 0\na 2 3 0\na -2 0\n"
+    );
+}
@@ -0,0 +1,660 @@
+use crate::sat::{SatProblem, SatSolver};
+
+/// Receives the clauses of an encoding, and provides the fresh variables of its auxiliary variables.
+pub trait ClauseSink {
+    fn new_variable(&mut self) -> i64;
+    fn add_clause(&mut self, literals: &[i64]);
+}
+
+impl ClauseSink for SatProblem {
+    fn new_variable(&mut self) -> i64 {
+        SatProblem::new_variable(self)
+    }
+    fn add_clause(&mut self, literals: &[i64]) {
+        SatProblem::add_clause(self, literals);
+    }
+}
+
+impl<'a> ClauseSink for SatSolver<'a> {
+    fn new_variable(&mut self) -> i64 {
+        SatSolver::new_variable(self)
+    }
+    fn add_clause(&mut self, literals: &[i64]) {
+        SatSolver::add_clause(self, literals);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmoEncoding {
+    /// A binary clause for every pair, without auxiliary variables.
+    Pairwise,
+    /// Sequential counter of Sinz with a single register.
+    Sequential,
+    /// Commander encoding of Klieber and Kwon over groups of 3 literals.
+    Commander,
+    /// Product encoding of Chen over a grid of rows and columns.
+    Product,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardinalityEncoding {
+    /// A clause for every subset of k + 1 literals, without auxiliary variables.
+    Pairwise,
+    /// Sequential counter of Sinz.
+    SequentialCounter,
+    /// Totalizer of Bailleux and Boufkhad.
+    Totalizer,
+    /// Odd-even merge sorting network, where only the comparators of the first k + 1 outputs are encoded.
+    CardinalityNetwork,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TseitinEncoding {
+    /// Both implications of the definition of every gate.
+    Full,
+    /// Only the implications needed by the polarities in which the gates occur, after Plaisted and Greenbaum.
+    PlaistedGreenbaum,
+}
+
+/// Adds the clauses that allow at most one of `literals` to be true.
+pub fn at_most_one<S: ClauseSink>(sink: &mut S, literals: &[i64], encoding: AmoEncoding) {
+    match encoding {
+        AmoEncoding::Pairwise => pairwise(sink, literals, 1),
+        AmoEncoding::Sequential => sequential_counter(sink, literals, 1),
+        AmoEncoding::Commander => commander(sink, literals),
+        AmoEncoding::Product => product(sink, literals),
+    }
+}
+
+/// Adds the clauses that allow at most `k` of `literals` to be true.
+pub fn at_most_k<S: ClauseSink>(
+    sink: &mut S,
+    literals: &[i64],
+    k: usize,
+    encoding: CardinalityEncoding,
+) {
+    if k >= literals.len() {
+        return;
+    }
+    if k == 0 {
+        for &x in literals {
+            sink.add_clause(&[-x]);
+        }
+        return;
+    }
+    match encoding {
+        CardinalityEncoding::Pairwise => pairwise(sink, literals, k),
+        CardinalityEncoding::SequentialCounter => sequential_counter(sink, literals, k),
+        CardinalityEncoding::Totalizer => {
+            let totalizer = Totalizer::new(sink, literals, k);
+            sink.add_clause(&[totalizer.at_most(k).unwrap()]);
+        }
+        CardinalityEncoding::CardinalityNetwork => {
+            let outputs = sorting_network(sink, literals, k + 1);
+            sink.add_clause(&[-outputs[k]]);
+        }
+    }
+}
+
+/// Adds the clauses that make at least `k` of `literals` true,
+/// as at most `literals.len() - k` of their negations.
+pub fn at_least_k<S: ClauseSink>(
+    sink: &mut S,
+    literals: &[i64],
+    k: usize,
+    encoding: CardinalityEncoding,
+) {
+    if k > literals.len() {
+        sink.add_clause(&[]);
+        return;
+    }
+    let negations: Vec<i64> = literals.iter().map(|&x| -x).collect();
+    at_most_k(sink, &negations, literals.len() - k, encoding);
+}
+
+/// Adds the clauses that make exactly `k` of `literals` true.
+pub fn exactly_k<S: ClauseSink>(
+    sink: &mut S,
+    literals: &[i64],
+    k: usize,
+    encoding: CardinalityEncoding,
+) {
+    at_most_k(sink, literals, k, encoding);
+    at_least_k(sink, literals, k, encoding);
+}
+
+fn pairwise<S: ClauseSink>(sink: &mut S, literals: &[i64], k: usize) {
+    // every subset of k + 1 literals, as increasing indices
+    let mut indices: Vec<usize> = (0..=k).collect();
+    if indices.len() > literals.len() {
+        return;
+    }
+    loop {
+        let clause: Vec<i64> = indices.iter().map(|&i| -literals[i]).collect();
+        sink.add_clause(&clause);
+        let mut i = k + 1;
+        loop {
+            if i == 0 {
+                return;
+            }
+            i -= 1;
+            if indices[i] < literals.len() - (k + 1 - i) {
+                break;
+            }
+        }
+        indices[i] += 1;
+        for j in i + 1..=k {
+            indices[j] = indices[j - 1] + 1;
+        }
+    }
+}
+
+/// `registers[i][j]` is true if at least j + 1 of the first i + 1 literals are true.
+fn sequential_counter<S: ClauseSink>(sink: &mut S, literals: &[i64], k: usize) {
+    let n = literals.len();
+    if k >= n {
+        return;
+    }
+    let registers: Vec<Vec<i64>> = (0..n - 1)
+        .map(|_| (0..k).map(|_| sink.new_variable()).collect())
+        .collect();
+    sink.add_clause(&[-literals[0], registers[0][0]]);
+    for &x in &registers[0][1..] {
+        sink.add_clause(&[-x]);
+    }
+    for i in 1..n {
+        if i < n - 1 {
+            sink.add_clause(&[-literals[i], registers[i][0]]);
+            sink.add_clause(&[-registers[i - 1][0], registers[i][0]]);
+            for j in 1..k {
+                sink.add_clause(&[-literals[i], -registers[i - 1][j - 1], registers[i][j]]);
+                sink.add_clause(&[-registers[i - 1][j], registers[i][j]]);
+            }
+        }
+        sink.add_clause(&[-literals[i], -registers[i - 1][k - 1]]);
+    }
+}
+
+fn commander<S: ClauseSink>(sink: &mut S, literals: &[i64]) {
+    if literals.len() <= 6 {
+        pairwise(sink, literals, 1);
+        return;
+    }
+    let mut commanders = vec![];
+    for group in literals.chunks(3) {
+        let commander = sink.new_variable();
+        pairwise(sink, group, 1);
+        for &x in group {
+            sink.add_clause(&[-x, commander]);
+        }
+        let mut clause = vec![-commander];
+        clause.extend_from_slice(group);
+        sink.add_clause(&clause);
+        commanders.push(commander);
+    }
+    commander(sink, &commanders);
+}
+
+fn product<S: ClauseSink>(sink: &mut S, literals: &[i64]) {
+    if literals.len() <= 4 {
+        pairwise(sink, literals, 1);
+        return;
+    }
+    let n = literals.len();
+    let n_rows = (1..).find(|&p| p * p >= n).unwrap();
+    let n_columns = n.div_ceil(n_rows);
+    let rows: Vec<i64> = (0..n_rows).map(|_| sink.new_variable()).collect();
+    let columns: Vec<i64> = (0..n_columns).map(|_| sink.new_variable()).collect();
+    for (i, &x) in literals.iter().enumerate() {
+        sink.add_clause(&[-x, rows[i / n_columns]]);
+        sink.add_clause(&[-x, columns[i % n_columns]]);
+    }
+    product(sink, &rows);
+    product(sink, &columns);
+}
+
+/// Adds a comparator, which sorts the two inputs in decreasing order.
+/// `None` is a false constant, which needs no clauses, or an output whose value is not needed.
+fn comparator<S: ClauseSink>(
+    sink: &mut S,
+    a: Option<i64>,
+    b: Option<i64>,
+    max_needed: bool,
+    min_needed: bool,
+) -> (Option<i64>, Option<i64>) {
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) => (a, b),
+        (x, None) | (None, x) => return (x, None),
+    };
+    let max = if max_needed {
+        let y = sink.new_variable();
+        sink.add_clause(&[-a, y]);
+        sink.add_clause(&[-b, y]);
+        sink.add_clause(&[-y, a, b]);
+        Some(y)
+    } else {
+        None
+    };
+    let min = if min_needed {
+        let y = sink.new_variable();
+        sink.add_clause(&[-a, -b, y]);
+        sink.add_clause(&[-y, a]);
+        sink.add_clause(&[-y, b]);
+        Some(y)
+    } else {
+        None
+    };
+    (max, min)
+}
+
+/// Comparators of an odd-even merge sort of `n` wires, where `n` is a power of two.
+fn merge_sort_comparators(lo: usize, n: usize, res: &mut Vec<(usize, usize)>) {
+    if n <= 1 {
+        return;
+    }
+    merge_sort_comparators(lo, n / 2, res);
+    merge_sort_comparators(lo + n / 2, n / 2, res);
+    merge_comparators(lo, n, 1, res);
+}
+
+fn merge_comparators(lo: usize, n: usize, step: usize, res: &mut Vec<(usize, usize)>) {
+    let double = step * 2;
+    if double < n {
+        merge_comparators(lo, n, double, res);
+        merge_comparators(lo + step, n, double, res);
+        let mut i = lo + step;
+        while i + step < lo + n {
+            res.push((i, i + step));
+            i += double;
+        }
+    } else {
+        res.push((lo, lo + step));
+    }
+}
+
+/// Sorts the literals in decreasing order, and returns the first `n_outputs` outputs,
+/// so that the k-th output is true exactly when at least k literals are true.
+pub(crate) fn sorting_network<S: ClauseSink>(
+    sink: &mut S,
+    literals: &[i64],
+    n_outputs: usize,
+) -> Vec<i64> {
+    let n = literals.len().next_power_of_two();
+    let mut comparators = vec![];
+    merge_sort_comparators(0, n, &mut comparators);
+    // the wires needed after every comparator, from the outputs backwards
+    let mut needed = vec![false; n];
+    for x in needed.iter_mut().take(n_outputs) {
+        *x = true;
+    }
+    let mut needs = vec![];
+    for &(i, j) in comparators.iter().rev() {
+        needs.push((needed[i], needed[j]));
+        let any = needed[i] || needed[j];
+        needed[i] = any;
+        needed[j] = any;
+    }
+    needs.reverse();
+    let mut wires: Vec<Option<i64>> = (0..n).map(|i| literals.get(i).cloned()).collect();
+    for (&(i, j), &(max_needed, min_needed)) in comparators.iter().zip(needs.iter()) {
+        let (max, min) = comparator(sink, wires[i], wires[j], max_needed, min_needed);
+        wires[i] = max;
+        wires[j] = min;
+    }
+    wires[..n_outputs].iter().map(|x| x.unwrap()).collect()
+}
+
+struct Node {
+    /// the two children, or `None` for a leaf
+    children: Option<(usize, usize)>,
+    /// number of input literals under the node
+    size: usize,
+    /// the i-th output is true exactly when at least i + 1 inputs under the node are true
+    outputs: Vec<i64>,
+}
+
+/// Totalizer whose outputs are encoded up to the bound plus one,
+/// so that the bound can be increased later by adding the outputs after it.
+pub struct Totalizer {
+    nodes: Vec<Node>,
+    root: usize,
+    bound: usize,
+}
+
+impl Totalizer {
+    /// Adds the clauses of the totalizer of `literals` with outputs up to `bound + 1`.
+    pub fn new<S: ClauseSink>(sink: &mut S, literals: &[i64], bound: usize) -> Totalizer {
+        assert!(!literals.is_empty());
+        let mut res = Totalizer {
+            nodes: vec![],
+            root: 0,
+            bound: 0,
+        };
+        res.root = res.build(literals);
+        res.extend(sink, res.root, bound + 1);
+        res.bound = bound;
+        res
+    }
+    fn build(&mut self, literals: &[i64]) -> usize {
+        let node = if literals.len() == 1 {
+            Node {
+                children: None,
+                size: 1,
+                outputs: literals.to_vec(),
+            }
+        } else {
+            let mid = literals.len() / 2;
+            let left = self.build(&literals[..mid]);
+            let right = self.build(&literals[mid..]);
+            Node {
+                children: Some((left, right)),
+                size: literals.len(),
+                outputs: vec![],
+            }
+        };
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+    /// Adds the outputs of the node up to `n_outputs`, with the clauses of both directions.
+    fn extend<S: ClauseSink>(&mut self, sink: &mut S, id: usize, n_outputs: usize) {
+        let n_outputs = n_outputs.min(self.nodes[id].size);
+        let start = self.nodes[id].outputs.len();
+        if start >= n_outputs {
+            return;
+        }
+        let (left, right) = self.nodes[id].children.unwrap();
+        self.extend(sink, left, n_outputs);
+        self.extend(sink, right, n_outputs);
+        for _ in start..n_outputs {
+            let y = sink.new_variable();
+            self.nodes[id].outputs.push(y);
+        }
+        let xs = self.nodes[left].outputs.clone();
+        let ys = self.nodes[right].outputs.clone();
+        let outputs = self.nodes[id].outputs.clone();
+        for s in start + 1..=n_outputs {
+            for a in 0..=s.min(xs.len()) {
+                let b = s - a;
+                // at least a on the left and b on the right
+                if b <= ys.len() {
+                    let mut clause = vec![outputs[s - 1]];
+                    if a > 0 {
+                        clause.push(-xs[a - 1]);
+                    }
+                    if b > 0 {
+                        clause.push(-ys[b - 1]);
+                    }
+                    sink.add_clause(&clause);
+                }
+                // at most a on the left and s - 1 - a on the right
+                if a < s {
+                    let b = s - 1 - a;
+                    let mut clause = vec![-outputs[s - 1]];
+                    if a < self.nodes[left].size {
+                        clause.push(xs[a]);
+                    }
+                    if b < self.nodes[right].size {
+                        clause.push(ys[b]);
+                    }
+                    sink.add_clause(&clause);
+                }
+            }
+        }
+    }
+    pub fn bound(&self) -> usize {
+        self.bound
+    }
+    /// Adds the outputs up to `bound + 1`, after which `at_most` and `at_least` accept up to `bound`.
+    pub fn increase_bound<S: ClauseSink>(&mut self, sink: &mut S, bound: usize) {
+        if bound <= self.bound {
+            return;
+        }
+        let root = self.root;
+        self.extend(sink, root, bound + 1);
+        self.bound = bound;
+    }
+    /// Returns the outputs, where the i-th one is true exactly when at least i + 1 inputs are true.
+    pub fn outputs(&self) -> &[i64] {
+        &self.nodes[self.root].outputs
+    }
+    /// Returns a literal that is true exactly when at most `k` inputs are true,
+    /// or `None` if it always holds. `k` must not be larger than the bound.
+    pub fn at_most(&self, k: usize) -> Option<i64> {
+        assert!(k <= self.bound);
+        self.outputs().get(k).map(|&x| -x)
+    }
+    /// Returns a literal that is true exactly when at least `k` inputs are true,
+    /// or `None` if it always holds. `k` must not be larger than the bound or the number of inputs.
+    pub fn at_least(&self, k: usize) -> Option<i64> {
+        assert!(k <= self.bound);
+        if k == 0 {
+            None
+        } else {
+            Some(self.outputs()[k - 1])
+        }
+    }
+}
+
+/// Generalized totalizer over `(literal, weight)` inputs.
+/// Every node of the tree has an output literal for every sum of the weights of its true inputs,
+/// where the sums larger than `cap` are merged into `cap`. An output is implied by its sum,
+/// so making the outputs of the sums at least k false bounds the weighted sum below k.
+/// With unit weights it is the totalizer of Bailleux and Boufkhad.
+pub struct GeneralizedTotalizer {
+    /// output literals of the root with their sums, in increasing order
+    outputs: Vec<(u64, i64)>,
+}
+
+impl GeneralizedTotalizer {
+    /// Adds the clauses of the tree over `(literal, weight)` inputs to `sink`.
+    pub fn new<S: ClauseSink>(
+        sink: &mut S,
+        inputs: &[(i64, u64)],
+        cap: u64,
+    ) -> GeneralizedTotalizer {
+        assert!(!inputs.is_empty());
+        GeneralizedTotalizer {
+            outputs: GeneralizedTotalizer::build(sink, inputs, cap),
+        }
+    }
+    fn build<S: ClauseSink>(sink: &mut S, inputs: &[(i64, u64)], cap: u64) -> Vec<(u64, i64)> {
+        if inputs.len() == 1 {
+            let (literal, weight) = inputs[0];
+            return vec![(weight.min(cap), literal)];
+        }
+        let mid = inputs.len() / 2;
+        let left = GeneralizedTotalizer::build(sink, &inputs[..mid], cap);
+        let right = GeneralizedTotalizer::build(sink, &inputs[mid..], cap);
+        let sum = |a: u64, b: u64| a.saturating_add(b).min(cap);
+        let mut sums: Vec<u64> = left.iter().chain(right.iter()).map(|x| x.0).collect();
+        for &(a, _) in &left {
+            for &(b, _) in &right {
+                sums.push(sum(a, b));
+            }
+        }
+        sums.sort_unstable();
+        sums.dedup();
+        let outputs: Vec<(u64, i64)> = sums.iter().map(|&x| (x, sink.new_variable())).collect();
+        let output_of = |x: u64| outputs[outputs.binary_search_by_key(&x, |y| y.0).unwrap()].1;
+        for &(a, x) in left.iter().chain(right.iter()) {
+            sink.add_clause(&[-x, output_of(a)]);
+        }
+        for &(a, x) in &left {
+            for &(b, y) in &right {
+                sink.add_clause(&[-x, -y, output_of(sum(a, b))]);
+            }
+        }
+        outputs
+    }
+    pub fn outputs(&self) -> &[(u64, i64)] {
+        &self.outputs
+    }
+    /// Returns the outputs that must be false for the weighted sum to be less than `k`.
+    pub fn at_least(&self, k: u64) -> Vec<i64> {
+        self.outputs
+            .iter()
+            .filter(|x| x.0 >= k)
+            .map(|x| x.1)
+            .collect()
+    }
+}
+
+impl SatProblem {
+    pub fn add_at_most_one(&mut self, literals: &[i64], encoding: AmoEncoding) {
+        at_most_one(self, literals, encoding);
+    }
+    pub fn add_at_most_k(&mut self, literals: &[i64], k: usize, encoding: CardinalityEncoding) {
+        at_most_k(self, literals, k, encoding);
+    }
+    pub fn add_at_least_k(&mut self, literals: &[i64], k: usize, encoding: CardinalityEncoding) {
+        at_least_k(self, literals, k, encoding);
+    }
+    pub fn add_exactly_k(&mut self, literals: &[i64], k: usize, encoding: CardinalityEncoding) {
+        exactly_k(self, literals, k, encoding);
+    }
+}
+
+/// Counts the models of `problem` projected on its first `n` variables.
+#[cfg(test)]
+pub(crate) fn count_inputs(problem: &SatProblem, n: usize) -> usize {
+    use crate::enumerate::Blocking;
+    let projection: Vec<usize> = (0..n).collect();
+    SatSolver::new(problem)
+        .enumerate_projected(&projection, Blocking::Full)
+        .count()
+}
+
+/// Counts the assignments of `n` variables whose number of true ones satisfies `f`.
+#[cfg(test)]
+fn brute_force_count<F: Fn(usize) -> bool>(n: usize, f: F) -> usize {
+    (0..1u32 << n)
+        .filter(|&bits| f(bits.count_ones() as usize))
+        .count()
+}
+
+#[test]
+fn test_at_most_one() {
+    let encodings = [
+        AmoEncoding::Pairwise,
+        AmoEncoding::Sequential,
+        AmoEncoding::Commander,
+        AmoEncoding::Product,
+    ];
+    for &encoding in encodings.iter() {
+        for n in 1..=10 {
+            let mut problem = SatProblem::new(n);
+            // mixed signs, which the brute force count does not depend on
+            let literals: Vec<i64> = (1..=n as i64)
+                .map(|x| if x % 3 == 0 { -x } else { x })
+                .collect();
+            problem.add_at_most_one(&literals, encoding);
+            assert_eq!(
+                count_inputs(&problem, n),
+                brute_force_count(n, |x| x <= 1),
+                "{:?} {}",
+                encoding,
+                n
+            );
+        }
+    }
+}
+
+#[test]
+fn test_cardinality_constraints() {
+    let encodings = [
+        CardinalityEncoding::Pairwise,
+        CardinalityEncoding::SequentialCounter,
+        CardinalityEncoding::Totalizer,
+        CardinalityEncoding::CardinalityNetwork,
+    ];
+    for &encoding in encodings.iter() {
+        for n in 1..=6 {
+            let literals: Vec<i64> = (1..=n as i64).collect();
+            for k in 0..=n + 1 {
+                let mut problem = SatProblem::new(n);
+                problem.add_at_most_k(&literals, k, encoding);
+                let expected = brute_force_count(n, |x| x <= k);
+                assert_eq!(
+                    count_inputs(&problem, n),
+                    expected,
+                    "{:?} <= {}",
+                    encoding,
+                    k
+                );
+                let mut problem = SatProblem::new(n);
+                problem.add_at_least_k(&literals, k, encoding);
+                let expected = brute_force_count(n, |x| x >= k);
+                assert_eq!(
+                    count_inputs(&problem, n),
+                    expected,
+                    "{:?} >= {}",
+                    encoding,
+                    k
+                );
+                let mut problem = SatProblem::new(n);
+                problem.add_exactly_k(&literals, k, encoding);
+                let expected = brute_force_count(n, |x| x == k);
+                assert_eq!(
+                    count_inputs(&problem, n),
+                    expected,
+                    "{:?} == {}",
+                    encoding,
+                    k
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_totalizer_increase_bound() {
+    let n = 7;
+    let literals: Vec<i64> = (1..=n as i64).collect();
+    let mut problem = SatProblem::new(n);
+    let mut totalizer = Totalizer::new(&mut problem, &literals, 1);
+    assert_eq!(totalizer.outputs().len(), 2);
+    let mut solver = SatSolver::new(&problem);
+    for k in 1..=n {
+        totalizer.increase_bound(&mut solver, k);
+        assert_eq!(totalizer.bound(), k);
+        // every assignment with exactly k true inputs is allowed by at most k and at least k,
+        // and the ones with k + 1 are not allowed by at most k
+        let mut assumptions: Vec<i64> = (1..=n as i64)
+            .map(|x| if x <= k as i64 { x } else { -x })
+            .collect();
+        let mut with_bounds = assumptions.clone();
+        with_bounds.extend(totalizer.at_most(k));
+        with_bounds.extend(totalizer.at_least(k));
+        assert!(solver.solve_with_assumptions(&with_bounds).is_some());
+        if k < n {
+            assumptions[k] = -assumptions[k];
+            assumptions.extend(totalizer.at_most(k));
+            assert!(solver.solve_with_assumptions(&assumptions).is_none());
+        }
+    }
+}
+
+#[test]
+fn test_generalized_totalizer() {
+    let weights = [3, 1, 2, 2];
+    for k in 1..=8 {
+        let mut solver = SatSolver::new(&SatProblem::new(4));
+        let inputs: Vec<(i64, u64)> = (0..4).map(|i| (i as i64 + 1, weights[i])).collect();
+        let totalizer = GeneralizedTotalizer::new(&mut solver, &inputs, 8);
+        for x in totalizer.at_least(k) {
+            solver.add_clause(&[-x]);
+        }
+        // every assignment of the inputs is allowed exactly when its sum is less than k
+        for bits in 0..16 {
+            let assumptions: Vec<i64> = (0..4)
+                .map(|i| if bits >> i & 1 == 1 { i + 1 } else { -(i + 1) })
+                .collect();
+            let sum: u64 = (0..4)
+                .filter(|i| bits >> i & 1 == 1)
+                .map(|i| weights[i])
+                .sum();
+            let res = solver.solve_with_assumptions(&assumptions);
+            assert_eq!(res.is_some(), sum < k);
+        }
+    }
+}
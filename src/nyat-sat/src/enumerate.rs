@@ -0,0 +1,138 @@
+use crate::sat::{SatPartialAssignments, SatSolver};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Blocking {
+    /// Blocks exactly the projected model that was returned.
+    Full,
+    /// Blocks a lifted cube, so that one item may stand for several projected models.
+    Minimal,
+}
+
+/// Enumerates models by adding a blocking clause to the solver after every model.
+/// The blocking clauses stay in the solver after the enumeration.
+pub struct ModelEnumerator<'s, 'a> {
+    solver: &'s mut SatSolver<'a>,
+    projection: Vec<usize>,
+    blocking: Blocking,
+    finished: bool,
+}
+
+impl<'s, 'a> ModelEnumerator<'s, 'a> {
+    fn new(
+        solver: &'s mut SatSolver<'a>,
+        projection: Vec<usize>,
+        blocking: Blocking,
+    ) -> ModelEnumerator<'s, 'a> {
+        ModelEnumerator {
+            solver,
+            projection,
+            blocking,
+            finished: false,
+        }
+    }
+}
+
+impl<'s, 'a> Iterator for ModelEnumerator<'s, 'a> {
+    type Item = SatPartialAssignments;
+    fn next(&mut self) -> Option<SatPartialAssignments> {
+        if self.finished {
+            return None;
+        }
+        let assignment = match self.solver.solve() {
+            Some(assignment) => assignment,
+            None => {
+                self.finished = true;
+                return None;
+            }
+        };
+        let res = match self.blocking {
+            Blocking::Full => assignment.project(self.projection.as_slice()),
+            Blocking::Minimal => self
+                .solver
+                .lift_model(&assignment, self.projection.as_slice()),
+        };
+        let mut blocking_clause = vec![];
+        for &id in &self.projection {
+            match res[id] {
+                Some(true) => blocking_clause.push(-(id as i64 + 1)),
+                Some(false) => blocking_clause.push(id as i64 + 1),
+                None => {}
+            }
+        }
+        if blocking_clause.is_empty() {
+            // the cube covers every projected model
+            self.finished = true;
+        } else {
+            self.solver.add_clause(&blocking_clause);
+        }
+        Some(res)
+    }
+}
+
+impl<'a> SatSolver<'a> {
+    /// Enumerates every model over all variables.
+    pub fn enumerate<'s>(&'s mut self) -> ModelEnumerator<'s, 'a> {
+        let projection = (0..self.n_variables()).collect();
+        ModelEnumerator::new(self, projection, Blocking::Full)
+    }
+    /// Enumerates the distinct restrictions of the models to `projection`.
+    pub fn enumerate_projected<'s>(
+        &'s mut self,
+        projection: &[usize],
+        blocking: Blocking,
+    ) -> ModelEnumerator<'s, 'a> {
+        ModelEnumerator::new(self, projection.to_vec(), blocking)
+    }
+}
+
+#[cfg(test)]
+use crate::sat::SatProblem;
+
+#[test]
+fn test_enumerate_all() {
+    // x1 or x2
+    let problem = SatProblem::new_from_dimacs("p cnf 2 1\n1 2 0\n");
+    let mut solver = SatSolver::new(&problem);
+    let mut models: Vec<String> = solver.enumerate().map(|x| x.to_dimacs()).collect();
+    models.sort();
+    assert_eq!(models, vec!["-1 2 0", "1 -2 0", "1 2 0"]);
+    assert!(solver.solve().is_none());
+}
+
+#[test]
+fn test_enumerate_unsat() {
+    let problem = SatProblem::new_from_dimacs("p cnf 1 2\n1 0\n-1 0\n");
+    let mut solver = SatSolver::new(&problem);
+    assert_eq!(solver.enumerate().count(), 0);
+}
+
+#[test]
+fn test_enumerate_projected() {
+    // x3 <-> (x1 or x2)
+    let problem = SatProblem::new_from_dimacs("p cnf 3 3\n-3 1 2 0\n3 -1 0\n3 -2 0\n");
+    let mut solver = SatSolver::new(&problem);
+    let mut models: Vec<String> = solver
+        .enumerate_projected(&[2], Blocking::Full)
+        .map(|x| x.to_dimacs())
+        .collect();
+    models.sort();
+    assert_eq!(models, vec!["-3 0", "3 0"]);
+}
+
+#[test]
+fn test_enumerate_minimal_blocking() {
+    // x1 or x2 or x3 has 7 models, covered by at most 3 cubes
+    let problem = SatProblem::new_from_dimacs("p cnf 3 1\n1 2 3 0\n");
+    let mut solver = SatSolver::new(&problem);
+    let cubes: Vec<SatPartialAssignments> = solver
+        .enumerate_projected(&[0, 1, 2], Blocking::Minimal)
+        .collect();
+    assert!(!cubes.is_empty() && cubes.len() <= 3);
+    for x in 0..8 {
+        let model: Vec<bool> = (0..3).map(|i| x >> i & 1 == 1).collect();
+        let covered = cubes
+            .iter()
+            .any(|c| (0..3).all(|i| c[i].is_none_or(|y| y == model[i])));
+        assert_eq!(covered, model.iter().any(|&y| y));
+    }
+}
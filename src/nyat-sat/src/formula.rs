@@ -0,0 +1,571 @@
+use crate::encodings::{ClauseSink, TseitinEncoding};
+use crate::sat::{SatAssignments, SatProblem};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Boolean formula over the DIMACS variables from 1.
+/// The operators `!`, `&`, `|` and `^` build formulas, and so do `implies`, `iff` and `ite`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Formula {
+    Constant(bool),
+    Variable(usize),
+    Not(Box<Formula>),
+    And(Vec<Formula>),
+    Or(Vec<Formula>),
+    Implies(Box<Formula>, Box<Formula>),
+    Iff(Box<Formula>, Box<Formula>),
+    Xor(Box<Formula>, Box<Formula>),
+    /// if-then-else
+    Ite(Box<Formula>, Box<Formula>, Box<Formula>),
+}
+
+impl Formula {
+    pub fn var(id: usize) -> Formula {
+        assert!(id >= 1);
+        Formula::Variable(id)
+    }
+    pub fn implies(self, other: Formula) -> Formula {
+        Formula::Implies(Box::new(self), Box::new(other))
+    }
+    pub fn iff(self, other: Formula) -> Formula {
+        Formula::Iff(Box::new(self), Box::new(other))
+    }
+    pub fn ite(condition: Formula, then: Formula, otherwise: Formula) -> Formula {
+        Formula::Ite(Box::new(condition), Box::new(then), Box::new(otherwise))
+    }
+    /// Returns the largest variable, or 0 if there is none.
+    pub fn max_variable(&self) -> usize {
+        match self {
+            Formula::Constant(_) => 0,
+            Formula::Variable(id) => *id,
+            Formula::Not(x) => x.max_variable(),
+            Formula::And(xs) | Formula::Or(xs) => {
+                xs.iter().map(|x| x.max_variable()).max().unwrap_or(0)
+            }
+            Formula::Implies(x, y) | Formula::Iff(x, y) | Formula::Xor(x, y) => {
+                x.max_variable().max(y.max_variable())
+            }
+            Formula::Ite(x, y, z) => x.max_variable().max(y.max_variable()).max(z.max_variable()),
+        }
+    }
+    /// Returns the value of the formula, where `assignment` has every variable.
+    pub fn evaluate(&self, assignment: &SatAssignments) -> bool {
+        match self {
+            Formula::Constant(x) => *x,
+            Formula::Variable(id) => assignment[id - 1],
+            Formula::Not(x) => !x.evaluate(assignment),
+            Formula::And(xs) => xs.iter().all(|x| x.evaluate(assignment)),
+            Formula::Or(xs) => xs.iter().any(|x| x.evaluate(assignment)),
+            Formula::Implies(x, y) => !x.evaluate(assignment) || y.evaluate(assignment),
+            Formula::Iff(x, y) => x.evaluate(assignment) == y.evaluate(assignment),
+            Formula::Xor(x, y) => x.evaluate(assignment) != y.evaluate(assignment),
+            Formula::Ite(x, y, z) => {
+                if x.evaluate(assignment) {
+                    y.evaluate(assignment)
+                } else {
+                    z.evaluate(assignment)
+                }
+            }
+        }
+    }
+    /// Encodes the formula into clauses over its variables and auxiliary ones after them.
+    pub fn to_sat_problem(&self, encoding: TseitinEncoding) -> SatProblem {
+        let mut res = SatProblem::new(self.max_variable());
+        TseitinEncoder::new(encoding).assert(&mut res, self);
+        res
+    }
+    /// Parses a formula such as `x1 & !(x2 | x3) -> ite(x4, x5, true)`.
+    /// The operators from the tightest are `!` (or `~`), `&`, `^`, `|`, `->` and `<->`,
+    /// where `->` is right associative.
+    pub fn parse(s: &str) -> Formula {
+        let tokens = tokenize(s);
+        let mut parser = Parser { tokens, pos: 0 };
+        let res = parser.iff();
+        assert!(
+            parser.pos == parser.tokens.len(),
+            "unexpected token: {}",
+            parser.tokens[parser.pos]
+        );
+        res
+    }
+}
+
+impl std::ops::Not for Formula {
+    type Output = Formula;
+    fn not(self) -> Formula {
+        Formula::Not(Box::new(self))
+    }
+}
+
+impl std::ops::BitAnd for Formula {
+    type Output = Formula;
+    fn bitand(self, other: Formula) -> Formula {
+        match self {
+            Formula::And(mut xs) => {
+                xs.push(other);
+                Formula::And(xs)
+            }
+            x => Formula::And(vec![x, other]),
+        }
+    }
+}
+
+impl std::ops::BitOr for Formula {
+    type Output = Formula;
+    fn bitor(self, other: Formula) -> Formula {
+        match self {
+            Formula::Or(mut xs) => {
+                xs.push(other);
+                Formula::Or(xs)
+            }
+            x => Formula::Or(vec![x, other]),
+        }
+    }
+}
+
+impl std::ops::BitXor for Formula {
+    type Output = Formula;
+    fn bitxor(self, other: Formula) -> Formula {
+        Formula::Xor(Box::new(self), Box::new(other))
+    }
+}
+
+/// Prints the syntax of `Formula::parse`, with parentheses around every compound subformula.
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter, xs: &[Formula], op: &str| {
+            write!(f, "(")?;
+            for (i, x) in xs.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                write!(f, "{}", x)?;
+            }
+            write!(f, ")")
+        };
+        match self {
+            Formula::Constant(x) => write!(f, "{}", x),
+            Formula::Variable(id) => write!(f, "x{}", id),
+            Formula::Not(x) => write!(f, "!{}", x),
+            Formula::And(xs) if xs.is_empty() => write!(f, "true"),
+            Formula::Or(xs) if xs.is_empty() => write!(f, "false"),
+            Formula::And(xs) => join(f, xs, "&"),
+            Formula::Or(xs) => join(f, xs, "|"),
+            Formula::Implies(x, y) => write!(f, "({} -> {})", x, y),
+            Formula::Iff(x, y) => write!(f, "({} <-> {})", x, y),
+            Formula::Xor(x, y) => write!(f, "({} ^ {})", x, y),
+            Formula::Ite(x, y, z) => write!(f, "ite({}, {}, {})", x, y, z),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Vec<String> {
+    let mut res = vec![];
+    let chars: Vec<char> = s.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            res.push(chars[start..i].iter().collect());
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 3)].iter().collect();
+            let token = ["<->", "->"]
+                .iter()
+                .find(|x| rest.starts_with(*x))
+                .map_or(c.to_string(), |x| x.to_string());
+            i += token.chars().count();
+            res.push(token);
+        }
+    }
+    res
+}
+
+/// Recursive descent parser with a function for every precedence.
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|x| x.as_str())
+    }
+    fn expect(&mut self, token: &str) {
+        assert_eq!(self.peek(), Some(token), "expected {}", token);
+        self.pos += 1;
+    }
+    fn iff(&mut self) -> Formula {
+        let mut res = self.implies();
+        while self.peek() == Some("<->") {
+            self.pos += 1;
+            res = res.iff(self.implies());
+        }
+        res
+    }
+    fn implies(&mut self) -> Formula {
+        let res = self.or();
+        if self.peek() == Some("->") {
+            self.pos += 1;
+            return res.implies(self.implies());
+        }
+        res
+    }
+    fn or(&mut self) -> Formula {
+        let mut xs = vec![self.xor()];
+        while self.peek() == Some("|") {
+            self.pos += 1;
+            xs.push(self.xor());
+        }
+        if xs.len() == 1 {
+            xs.pop().unwrap()
+        } else {
+            Formula::Or(xs)
+        }
+    }
+    fn xor(&mut self) -> Formula {
+        let mut res = self.and();
+        while self.peek() == Some("^") {
+            self.pos += 1;
+            res = res ^ self.and();
+        }
+        res
+    }
+    fn and(&mut self) -> Formula {
+        let mut xs = vec![self.unary()];
+        while self.peek() == Some("&") {
+            self.pos += 1;
+            xs.push(self.unary());
+        }
+        if xs.len() == 1 {
+            xs.pop().unwrap()
+        } else {
+            Formula::And(xs)
+        }
+    }
+    fn unary(&mut self) -> Formula {
+        match self.peek() {
+            Some("!") | Some("~") => {
+                self.pos += 1;
+                !self.unary()
+            }
+            _ => self.atom(),
+        }
+    }
+    fn atom(&mut self) -> Formula {
+        let token = self.peek().expect("unexpected end of formula").to_string();
+        self.pos += 1;
+        match token.as_str() {
+            "(" => {
+                let res = self.iff();
+                self.expect(")");
+                res
+            }
+            "true" => Formula::Constant(true),
+            "false" => Formula::Constant(false),
+            "ite" => {
+                self.expect("(");
+                let condition = self.iff();
+                self.expect(",");
+                let then = self.iff();
+                self.expect(",");
+                let otherwise = self.iff();
+                self.expect(")");
+                Formula::ite(condition, then, otherwise)
+            }
+            _ => {
+                let id = token
+                    .strip_prefix('x')
+                    .and_then(|x| x.parse::<usize>().ok())
+                    .filter(|&x| x >= 1);
+                Formula::var(id.unwrap_or_else(|| panic!("unexpected token: {}", token)))
+            }
+        }
+    }
+}
+
+/// Gate over DIMACS literals, in a normal form so that the same gates are hashed together.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Gate {
+    /// sorted literals
+    And(Vec<i64>),
+    /// two positive literals in order
+    Xor(i64, i64),
+    /// positive condition
+    Ite(i64, i64, i64),
+}
+
+/// Tseitin encoding of formulas, which shares the variable of a gate among every subformula with
+/// the same gate over the same literals, also across calls. The polarities are 1 for positive and
+/// 2 for negative, and a shared gate gets the clauses of the polarities it had not needed before.
+pub struct TseitinEncoder {
+    encoding: TseitinEncoding,
+    /// variable and encoded polarities of every gate
+    gates: HashMap<Gate, (i64, u8)>,
+    /// variable that is true, for the constants
+    constant: Option<i64>,
+}
+
+fn flip(polarity: u8) -> u8 {
+    (polarity & 1) << 1 | (polarity & 2) >> 1
+}
+
+impl TseitinEncoder {
+    pub fn new(encoding: TseitinEncoding) -> TseitinEncoder {
+        TseitinEncoder {
+            encoding,
+            gates: HashMap::new(),
+            constant: None,
+        }
+    }
+    /// Adds the clauses that make the formula true.
+    pub fn assert<S: ClauseSink>(&mut self, sink: &mut S, formula: &Formula) {
+        match formula {
+            Formula::And(xs) => {
+                for x in xs {
+                    self.assert(sink, x);
+                }
+            }
+            Formula::Or(xs) => {
+                let clause: Vec<i64> = xs.iter().map(|x| self.encode(sink, x, 1)).collect();
+                sink.add_clause(&clause);
+            }
+            x => {
+                let literal = self.encode(sink, x, 1);
+                sink.add_clause(&[literal]);
+            }
+        }
+    }
+    /// Returns a literal that is equivalent to the formula with `TseitinEncoding::Full`.
+    /// Otherwise it only implies the formula, so it must occur positively in the clauses of the caller.
+    pub fn literal<S: ClauseSink>(&mut self, sink: &mut S, formula: &Formula) -> i64 {
+        self.encode(sink, formula, 1)
+    }
+    fn encode<S: ClauseSink>(&mut self, sink: &mut S, formula: &Formula, polarity: u8) -> i64 {
+        match formula {
+            Formula::Constant(x) => {
+                let constant = self.constant(sink);
+                if *x {
+                    constant
+                } else {
+                    -constant
+                }
+            }
+            Formula::Variable(id) => *id as i64,
+            Formula::Not(x) => -self.encode(sink, x, flip(polarity)),
+            Formula::And(xs) => {
+                let literals: Vec<i64> =
+                    xs.iter().map(|x| self.encode(sink, x, polarity)).collect();
+                self.and(sink, literals, polarity)
+            }
+            // the negation of the conjunction of the negations
+            Formula::Or(xs) => {
+                let literals: Vec<i64> =
+                    xs.iter().map(|x| -self.encode(sink, x, polarity)).collect();
+                -self.and(sink, literals, flip(polarity))
+            }
+            Formula::Implies(x, y) => {
+                let x = self.encode(sink, x, flip(polarity));
+                let y = self.encode(sink, y, polarity);
+                -self.and(sink, vec![x, -y], flip(polarity))
+            }
+            Formula::Iff(x, y) => {
+                let x = self.encode(sink, x, 3);
+                let y = self.encode(sink, y, 3);
+                -self.xor(sink, x, y, flip(polarity))
+            }
+            Formula::Xor(x, y) => {
+                let x = self.encode(sink, x, 3);
+                let y = self.encode(sink, y, 3);
+                self.xor(sink, x, y, polarity)
+            }
+            Formula::Ite(x, y, z) => {
+                let x = self.encode(sink, x, 3);
+                let y = self.encode(sink, y, polarity);
+                let z = self.encode(sink, z, polarity);
+                self.ite(sink, x, y, z, polarity)
+            }
+        }
+    }
+    fn constant<S: ClauseSink>(&mut self, sink: &mut S) -> i64 {
+        match self.constant {
+            Some(x) => x,
+            None => {
+                let x = sink.new_variable();
+                sink.add_clause(&[x]);
+                self.constant = Some(x);
+                x
+            }
+        }
+    }
+    fn and<S: ClauseSink>(&mut self, sink: &mut S, mut literals: Vec<i64>, polarity: u8) -> i64 {
+        if let Some(constant) = self.constant {
+            literals.retain(|&x| x != constant);
+        }
+        literals.sort_unstable();
+        literals.dedup();
+        if literals.iter().any(|x| literals.binary_search(&-x).is_ok()) {
+            return -self.constant(sink);
+        }
+        match literals.len() {
+            0 => self.constant(sink),
+            1 => literals[0],
+            _ => self.gate(sink, Gate::And(literals), polarity),
+        }
+    }
+    fn xor<S: ClauseSink>(&mut self, sink: &mut S, x: i64, y: i64, polarity: u8) -> i64 {
+        // the negations are taken out of the gate, which flips its polarity
+        let negated = (x < 0) != (y < 0);
+        let (x, y) = (x.abs().min(y.abs()), x.abs().max(y.abs()));
+        if x == y {
+            let constant = self.constant(sink);
+            return if negated { constant } else { -constant };
+        }
+        if negated {
+            -self.gate(sink, Gate::Xor(x, y), flip(polarity))
+        } else {
+            self.gate(sink, Gate::Xor(x, y), polarity)
+        }
+    }
+    fn ite<S: ClauseSink>(&mut self, sink: &mut S, x: i64, y: i64, z: i64, polarity: u8) -> i64 {
+        if y == z {
+            return y;
+        }
+        if x < 0 {
+            return self.ite(sink, -x, z, y, polarity);
+        }
+        self.gate(sink, Gate::Ite(x, y, z), polarity)
+    }
+    /// Returns the variable of the gate, and adds the clauses of the polarities it needs.
+    fn gate<S: ClauseSink>(&mut self, sink: &mut S, gate: Gate, polarity: u8) -> i64 {
+        let polarity = match self.encoding {
+            TseitinEncoding::Full => 3,
+            TseitinEncoding::PlaistedGreenbaum => polarity,
+        };
+        let (g, encoded) = match self.gates.get(&gate) {
+            Some(&x) => x,
+            None => (sink.new_variable(), 0),
+        };
+        let added = polarity & !encoded;
+        self.gates.insert(gate.clone(), (g, encoded | polarity));
+        let mut clauses: Vec<Vec<i64>> = vec![];
+        match gate {
+            Gate::And(xs) => {
+                if added & 1 != 0 {
+                    clauses.extend(xs.iter().map(|&x| vec![-g, x]));
+                }
+                if added & 2 != 0 {
+                    let mut clause: Vec<i64> = xs.iter().map(|&x| -x).collect();
+                    clause.push(g);
+                    clauses.push(clause);
+                }
+            }
+            Gate::Xor(x, y) => {
+                if added & 1 != 0 {
+                    clauses.push(vec![-g, x, y]);
+                    clauses.push(vec![-g, -x, -y]);
+                }
+                if added & 2 != 0 {
+                    clauses.push(vec![g, -x, y]);
+                    clauses.push(vec![g, x, -y]);
+                }
+            }
+            Gate::Ite(x, y, z) => {
+                if added & 1 != 0 {
+                    clauses.push(vec![-g, -x, y]);
+                    clauses.push(vec![-g, x, z]);
+                }
+                if added & 2 != 0 {
+                    clauses.push(vec![g, -x, -y]);
+                    clauses.push(vec![g, x, -z]);
+                }
+            }
+        }
+        for clause in &clauses {
+            sink.add_clause(clause);
+        }
+        g
+    }
+}
+
+#[cfg(test)]
+fn random_formula<R: rand::Rng>(rng: &mut R, n_variables: usize, depth: usize) -> Formula {
+    if depth == 0 || rng.gen_range(0, 5) == 0 {
+        return match rng.gen_range(0, 10) {
+            0 => Formula::Constant(rng.gen()),
+            _ => Formula::var(rng.gen_range(1, n_variables + 1)),
+        };
+    }
+    let kind = rng.gen_range(0, 7);
+    let mut sub = || random_formula(rng, n_variables, depth - 1);
+    match kind {
+        0 => !sub(),
+        1 => sub() & sub() & sub(),
+        2 => sub() | sub(),
+        3 => sub().implies(sub()),
+        4 => sub().iff(sub()),
+        5 => sub() ^ sub(),
+        _ => Formula::ite(sub(), sub(), sub()),
+    }
+}
+
+#[test]
+fn test_parse_formula() {
+    let x = |id| Formula::var(id);
+    let formula = Formula::parse("x1 & !(x2 | x3) -> ite(x4, x5, true) <-> ~x1 ^ x2");
+    let expected = (x(1) & !(x(2) | x(3)))
+        .implies(Formula::ite(x(4), x(5), Formula::Constant(true)))
+        .iff(!x(1) ^ x(2));
+    assert_eq!(formula, expected);
+    // `->` is right associative
+    assert_eq!(
+        Formula::parse("x1 -> x2 -> x3"),
+        x(1).implies(x(2).implies(x(3)))
+    );
+    assert_eq!(Formula::parse(&expected.to_string()), expected);
+}
+
+#[test]
+fn test_structural_hashing() {
+    // the conjunction and the exclusive or are encoded once, since a negated input only negates the gate
+    let formula = Formula::parse("((x1 & x2) ^ x3) | !((x2 & x1) ^ !x3)");
+    let problem = formula.to_sat_problem(TseitinEncoding::Full);
+    assert_eq!(problem.n_variables(), 3 + 2);
+}
+
+#[test]
+fn test_tseitin_against_brute_force() {
+    use crate::enumerate::Blocking;
+    use crate::sat::SatSolver;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..200 {
+        let n = 5;
+        let formula = random_formula(&mut rng, n, 4);
+        let formula = formula & Formula::var(n);
+        let expected = (0..1u32 << n)
+            .filter(|&bits| {
+                let xs = (0..n).map(|i| bits >> i & 1 == 1).collect();
+                formula.evaluate(&SatAssignments::new_from_vec(xs))
+            })
+            .count();
+        let projection: Vec<usize> = (0..n).collect();
+        // every assignment of the variables has a single extension with the full encoding
+        let problem = formula.to_sat_problem(TseitinEncoding::Full);
+        let count = SatSolver::new(&problem)
+            .enumerate_projected(&projection, Blocking::Full)
+            .count();
+        assert_eq!(count, expected, "{}", formula);
+        let problem = formula.to_sat_problem(TseitinEncoding::PlaistedGreenbaum);
+        match SatSolver::new(&problem).solve() {
+            Some(model) => assert!(formula.evaluate(&model), "{}", formula),
+            None => assert_eq!(expected, 0, "{}", formula),
+        }
+    }
+}
@@ -0,0 +1,440 @@
+use crate::proof::check_drup;
+use crate::sat::{SatAssignments, SatProblem, SatSolver};
+
+/// problems up to this many variables are checked against every assignment, and larger ones against DPLL
+pub const BRUTE_FORCE_VARIABLES: usize = 12;
+
+/// Returns whether the assignment satisfies the assumptions and the problem.
+fn satisfies(problem: &SatProblem, assumptions: &[i64], assignment: &SatAssignments) -> bool {
+    assumptions
+        .iter()
+        .all(|&x| assignment[x.unsigned_abs() as usize - 1] == (x > 0))
+        && problem.check_assignment(assignment)
+}
+
+/// Returns a model under the assumptions by trying every assignment, for problems of a few variables.
+pub fn brute_force(problem: &SatProblem, assumptions: &[i64]) -> Option<SatAssignments> {
+    let n = problem.n_variables();
+    assert!(n <= 24, "too many variables to enumerate: {}", n);
+    (0..1u32 << n)
+        .map(|bits| SatAssignments::new_from_vec((0..n).map(|i| bits >> i & 1 == 1).collect()))
+        .find(|assignment| satisfies(problem, assumptions, assignment))
+}
+
+/// Constraint of DIMACS literals, which is an XOR constraint if the flag is set.
+type Constraint = (bool, Vec<i64>);
+
+fn constraints(problem: &SatProblem) -> Vec<Constraint> {
+    problem
+        .clauses()
+        .map(|x| (false, x))
+        .chain(problem.xors().map(|x| (true, x)))
+        .collect()
+}
+
+fn to_problem(n_variables: usize, constraints: &[Constraint]) -> SatProblem {
+    let mut res = SatProblem::new(n_variables);
+    for (xor, literals) in constraints {
+        if *xor {
+            res.add_xor(literals);
+        } else {
+            res.add_clause(literals);
+        }
+    }
+    res
+}
+
+/// Reference DPLL, which assigns the unit constraints and branches on the first unassigned variable
+/// without learning, so that it is slow but simple.
+struct Dpll {
+    constraints: Vec<Constraint>,
+    values: Vec<Option<bool>>,
+}
+
+impl Dpll {
+    fn value(&self, literal: i64) -> Option<bool> {
+        self.values[literal.unsigned_abs() as usize - 1].map(|x| x == (literal > 0))
+    }
+    /// Assigns the unit constraints up to a fixpoint, and returns false on a conflict.
+    fn propagate(&mut self) -> bool {
+        loop {
+            let mut unit = None;
+            for (xor, literals) in &self.constraints {
+                let unassigned: Vec<i64> = literals
+                    .iter()
+                    .cloned()
+                    .filter(|&x| self.value(x).is_none())
+                    .collect();
+                let n_true = literals
+                    .iter()
+                    .filter(|&&x| self.value(x) == Some(true))
+                    .count();
+                match (*xor, unassigned.len()) {
+                    (false, _) if n_true > 0 => {}
+                    (false, 0) => return false,
+                    (false, 1) => unit = Some(unassigned[0]),
+                    (true, 0) if n_true % 2 == 0 => return false,
+                    // the last literal sets the parity
+                    (true, 1) => {
+                        let x = unassigned[0];
+                        unit = Some(if n_true % 2 == 0 { x } else { -x });
+                    }
+                    _ => {}
+                }
+                if unit.is_some() {
+                    break;
+                }
+            }
+            match unit {
+                Some(x) => self.values[x.unsigned_abs() as usize - 1] = Some(x > 0),
+                None => return true,
+            }
+        }
+    }
+    fn search(&mut self) -> bool {
+        if !self.propagate() {
+            return false;
+        }
+        let id = match self.values.iter().position(|x| x.is_none()) {
+            Some(id) => id,
+            None => return true,
+        };
+        for &value in &[false, true] {
+            let values = self.values.clone();
+            self.values[id] = Some(value);
+            if self.search() {
+                return true;
+            }
+            self.values = values;
+        }
+        false
+    }
+}
+
+/// Returns a model under the assumptions found by the reference DPLL.
+pub fn reference_dpll(problem: &SatProblem, assumptions: &[i64]) -> Option<SatAssignments> {
+    let mut constraints = constraints(problem);
+    constraints.extend(assumptions.iter().map(|&x| (false, vec![x])));
+    let mut dpll = Dpll {
+        constraints,
+        values: vec![None; problem.n_variables()],
+    };
+    if dpll.search() {
+        Some(SatAssignments::new_from_vec(
+            dpll.values.iter().map(|x| x.unwrap()).collect(),
+        ))
+    } else {
+        None
+    }
+}
+
+/// Solves the problem under the assumptions by `SatSolver`, and returns an error if it disagrees with
+/// `brute_force` up to `BRUTE_FORCE_VARIABLES` variables or else `reference_dpll`, its model is wrong,
+/// its failed assumptions are satisfiable, or its DRUP proof is wrong.
+pub fn differential(problem: &SatProblem, assumptions: &[i64]) -> Result<(), String> {
+    let expected = if problem.n_variables() <= BRUTE_FORCE_VARIABLES {
+        brute_force(problem, assumptions)
+    } else {
+        reference_dpll(problem, assumptions)
+    };
+    let prove = assumptions.is_empty() && problem.xors().count() == 0;
+    let mut proof = String::new();
+    let (res, core) = {
+        let mut solver = SatSolver::new(problem);
+        if prove {
+            solver.set_proof(|step| {
+                proof.push_str(&step.to_drup());
+                proof.push('\n');
+            });
+        }
+        let res = solver.solve_with_assumptions(assumptions);
+        (res, solver.failed_assumptions())
+    };
+    match (res, expected) {
+        (Some(model), _) if !satisfies(problem, assumptions, &model) => {
+            Err(format!("wrong model: {}", model.to_dimacs()))
+        }
+        (Some(_), None) => Err("SAT, but the reference is UNSAT".to_string()),
+        (Some(_), Some(_)) => Ok(()),
+        (None, Some(model)) => Err(format!(
+            "UNSAT, but the reference has a model: {}",
+            model.to_dimacs()
+        )),
+        (None, None) => {
+            if !core.iter().all(|x| assumptions.contains(x)) {
+                Err(format!(
+                    "failed assumptions out of the assumptions: {:?}",
+                    core
+                ))
+            } else if problem.n_variables() <= BRUTE_FORCE_VARIABLES
+                && brute_force(problem, &core).is_some()
+            {
+                Err(format!("satisfiable failed assumptions: {:?}", core))
+            } else if prove && !check_drup(problem, &proof) {
+                Err(format!("wrong proof:\n{}", proof))
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+/// variables of the problems of `problem_from_bytes`
+pub const FUZZ_VARIABLES: usize = 40;
+
+/// Decodes the input of a fuzzer into a problem and assumptions. The first byte is the number of variables,
+/// and the bytes up to the first 0xff are constraints, which end with 0, and the ones after it are assumptions.
+/// Every other byte is a literal of the variable of its low 6 bits, which is negative if 0x40 is set,
+/// and the constraint is an XOR constraint if 0x80 is set in its first literal.
+pub fn problem_from_bytes(data: &[u8]) -> (SatProblem, Vec<i64>) {
+    let n = 1 + data.first().map_or(0, |&b| b as usize % FUZZ_VARIABLES);
+    let literal = |b: u8| {
+        let x = (b & 0x3f) as i64 % n as i64 + 1;
+        if b & 0x40 == 0 {
+            x
+        } else {
+            -x
+        }
+    };
+    let rest = data.get(1..).unwrap_or(&[]);
+    let (body, assumptions) = match rest.iter().position(|&b| b == 0xff) {
+        Some(i) => (&rest[..i], &rest[i + 1..]),
+        None => (rest, &[][..]),
+    };
+    let mut pieces: Vec<&[u8]> = body.split(|&b| b == 0).collect();
+    // the last constraint ends with 0 or at the end
+    if pieces.last().is_some_and(|x| x.is_empty()) {
+        pieces.pop();
+    }
+    let mut constraints = vec![];
+    for bytes in pieces {
+        let xor = bytes.first().is_some_and(|&b| b & 0x80 != 0);
+        constraints.push((xor, bytes.iter().map(|&b| literal(b)).collect()));
+    }
+    let assumptions = assumptions.iter().map(|&b| literal(b)).collect();
+    (to_problem(n, &constraints), assumptions)
+}
+
+/// Removes the chunks of the items while `fails` holds for the rest, halving the chunks down to single items.
+fn ddmin<T: Clone, F: FnMut(&[T]) -> bool>(mut items: Vec<T>, fails: &mut F) -> Vec<T> {
+    let mut n_chunks = 2;
+    while !items.is_empty() {
+        let chunk = items.len().div_ceil(n_chunks);
+        let mut reduced = false;
+        for start in (0..items.len()).step_by(chunk) {
+            let end = (start + chunk).min(items.len());
+            let rest: Vec<T> = items[..start]
+                .iter()
+                .chain(items[end..].iter())
+                .cloned()
+                .collect();
+            if fails(&rest) {
+                items = rest;
+                n_chunks = (n_chunks - 1).max(2);
+                reduced = true;
+                break;
+            }
+        }
+        if !reduced {
+            if chunk == 1 {
+                break;
+            }
+            n_chunks = (2 * n_chunks).min(items.len());
+        }
+    }
+    items
+}
+
+/// Delta-debugs a problem for which `fails` holds into a smaller one for which it still holds,
+/// by removing the clauses and XOR constraints, and then the literals one by one, until neither removes any.
+pub fn shrink<F: FnMut(&SatProblem) -> bool>(problem: &SatProblem, mut fails: F) -> SatProblem {
+    let n = problem.n_variables();
+    let mut constraints = constraints(problem);
+    loop {
+        constraints = ddmin(constraints, &mut |xs: &[Constraint]| {
+            fails(&to_problem(n, xs))
+        });
+        let mut reduced = false;
+        for i in 0..constraints.len() {
+            let mut j = 0;
+            while j < constraints[i].1.len() {
+                let mut smaller = constraints.clone();
+                smaller[i].1.remove(j);
+                if fails(&to_problem(n, &smaller)) {
+                    constraints = smaller;
+                    reduced = true;
+                } else {
+                    j += 1;
+                }
+            }
+        }
+        if !reduced {
+            return to_problem(n, &constraints);
+        }
+    }
+}
+
+/// Renumbers the variables that occur in the constraints to 1, 2, ... in their order, dropping the others.
+pub fn renumber(problem: &SatProblem) -> SatProblem {
+    let constraints = constraints(problem);
+    let mut ids = vec![0; problem.n_variables() + 1];
+    for (_, literals) in &constraints {
+        for &x in literals {
+            ids[x.unsigned_abs() as usize] = 1;
+        }
+    }
+    let mut n = 0;
+    for id in ids.iter_mut() {
+        if *id != 0 {
+            n += 1;
+            *id = n;
+        }
+    }
+    let renumbered: Vec<Constraint> = constraints
+        .into_iter()
+        .map(|(xor, literals)| {
+            let literals = literals
+                .iter()
+                .map(|&x| x.signum() * ids[x.unsigned_abs() as usize])
+                .collect();
+            (xor, literals)
+        })
+        .collect();
+    to_problem(n as usize, &renumbered)
+}
+
+#[test]
+fn test_references() {
+    // x1 xor x2, x1 or x2, and not x1 or not x2 are satisfied by x1 != x2
+    let mut problem = SatProblem::new(3);
+    problem.add_xor(&[1, 2]);
+    problem.add_clause(&[1, 2]);
+    problem.add_clause(&[-1, -2]);
+    for reference in &[brute_force, reference_dpll] {
+        let model = reference(&problem, &[]).unwrap();
+        assert!(problem.check_assignment(&model));
+        assert_eq!(reference(&problem, &[-1, 2]).map(|x| x[0]), Some(false));
+        assert_eq!(reference(&problem, &[1, 2]), None);
+    }
+    problem.add_xor(&[1, 2, 3]);
+    problem.add_clause(&[3]);
+    assert_eq!(reference_dpll(&problem, &[]), None);
+    assert_eq!(differential(&problem, &[]), Ok(()));
+}
+
+#[test]
+fn test_problem_from_bytes() {
+    let (problem, assumptions) = problem_from_bytes(&[2, 1, 0x42, 0, 0x81, 2, 0, 0xff, 0x41]);
+    assert_eq!(problem.to_dimacs(), "p cnf 3 2\n2 -3 0\nx2 3 0\n");
+    assert_eq!(assumptions, vec![-2]);
+    let (problem, assumptions) = problem_from_bytes(&[]);
+    assert_eq!(problem.to_dimacs(), "p cnf 1 0\n");
+    assert!(assumptions.is_empty());
+}
+
+#[test]
+fn test_shrink() {
+    let mut problem = SatProblem::new(5);
+    problem.add_clause(&[1, 2, 3]);
+    problem.add_clause(&[-1, 4]);
+    problem.add_clause(&[-4, 5]);
+    problem.add_clause(&[-2, -5]);
+    problem.add_xor(&[3, 4]);
+    // the clauses that contain both x2 and x3
+    let small = shrink(&problem, |p| {
+        p.clauses()
+            .any(|clause| clause.contains(&2) && clause.contains(&3))
+    });
+    assert_eq!(small.to_dimacs(), "p cnf 5 1\n2 3 0\n");
+    // satisfiable, but not under x1 and not x5
+    let small = shrink(&problem, |p| {
+        brute_force(p, &[]).is_some() && brute_force(p, &[1, -5]).is_none()
+    });
+    assert_eq!(small.to_dimacs(), "p cnf 5 1\n5 0\n");
+    assert_eq!(renumber(&small).to_dimacs(), "p cnf 1 1\n1 0\n");
+    assert_eq!(renumber(&problem).to_dimacs(), problem.to_dimacs());
+    let mut problem = SatProblem::new(6);
+    problem.add_clause(&[-2, 6]);
+    problem.add_xor(&[6, 4]);
+    assert_eq!(
+        renumber(&problem).to_dimacs(),
+        "p cnf 3 2\n-1 3 0\nx3 2 0\n"
+    );
+}
+
+/// problem of variables in the range, with up to `ratio` clauses per variable of lengths in the range
+/// and a few XOR constraints, and a few assumptions
+#[cfg(test)]
+fn problems(
+    variables: std::ops::RangeInclusive<usize>,
+    ratio: usize,
+    lengths: std::ops::RangeInclusive<usize>,
+    n_xors: usize,
+) -> impl proptest::strategy::Strategy<Value = (SatProblem, Vec<i64>)> {
+    use proptest::prelude::*;
+    variables.prop_flat_map(move |n| {
+        let literal = (1..=n as i64, any::<bool>()).prop_map(|(x, sign)| if sign { x } else { -x });
+        let constraint = prop::collection::vec(literal.clone(), lengths.clone());
+        (
+            prop::collection::vec(constraint.clone(), 0..=ratio * n),
+            prop::collection::vec(constraint, 0..=n_xors),
+            prop::collection::vec(literal, 0..=3),
+        )
+            .prop_map(move |(clauses, xors, assumptions)| {
+                let mut problem = SatProblem::new(n);
+                for clause in &clauses {
+                    problem.add_clause(clause);
+                }
+                for xor in &xors {
+                    problem.add_xor(xor);
+                }
+                (problem, assumptions)
+            })
+    })
+}
+
+/// Fails with the error and the DIMACS of the shrunk problem if the solver disagrees with the reference.
+#[cfg(test)]
+fn check_differential(
+    problem: &SatProblem,
+    assumptions: &[i64],
+) -> Result<(), proptest::test_runner::TestCaseError> {
+    if let Err(e) = differential(problem, assumptions) {
+        let small = shrink(problem, |p| differential(p, assumptions).is_err());
+        return Err(proptest::test_runner::TestCaseError::fail(format!(
+            "{}\nassumptions: {:?}\n{}",
+            e,
+            assumptions,
+            small.to_dimacs()
+        )));
+    }
+    Ok(())
+}
+
+/// Configuration of the property tests, whose generator is seeded like the other random tests.
+#[cfg(test)]
+fn proptest_config(cases: u32) -> proptest::test_runner::Config {
+    proptest::test_runner::Config {
+        cases,
+        rng_seed: proptest::test_runner::RngSeed::Fixed(0),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #![proptest_config(proptest_config(256))]
+    #[test]
+    fn test_against_brute_force((problem, assumptions) in problems(1..=BRUTE_FORCE_VARIABLES, 5, 1..=4, 3)) {
+        check_differential(&problem, &assumptions)?;
+    }
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #![proptest_config(proptest_config(64))]
+    #[test]
+    fn test_against_dpll((problem, assumptions) in problems(BRUTE_FORCE_VARIABLES + 1..=40, 5, 3..=3, 2)) {
+        check_differential(&problem, &assumptions)?;
+    }
+}
@@ -1,5 +1,20 @@
 #[macro_use]
 extern crate log;
+#[cfg(not(target_arch = "wasm32"))]
 extern crate env_logger;
 
+pub mod aiger;
+pub mod cube;
+pub mod encodings;
+pub mod enumerate;
+pub mod formula;
+pub mod fuzz;
+pub mod mcs;
+pub mod mus;
+pub mod pb;
+pub mod portfolio;
+pub mod proof;
 pub mod sat;
+pub mod sls;
+pub mod stats;
+pub mod wcnf;
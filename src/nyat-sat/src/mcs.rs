@@ -0,0 +1,399 @@
+use crate::mus::{subproblem, with_selectors, MusExtractor};
+use crate::sat::{SatAssignments, SatProblem, SatSolver};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McsAlgorithm {
+    /// Tests the falsified groups one by one, keeping the ones that the next model also satisfies.
+    LinearSearch,
+    /// Clause D: asks for a model that satisfies any of the falsified groups, until there is none.
+    Cld,
+}
+
+/// Groups of clauses with an incremental solver that enforces a group by assuming its selector.
+struct Selectors<'p> {
+    problem: &'p SatProblem,
+    clauses: Vec<Vec<i64>>,
+    /// group of every clause, where group 0 is hard
+    groups: Vec<usize>,
+    /// groups other than 0 in increasing order
+    ids: Vec<usize>,
+    solver: SatSolver<'p>,
+}
+
+impl<'p> Selectors<'p> {
+    fn new(problem: &'p SatProblem, groups: &[usize]) -> Selectors<'p> {
+        assert_eq!(groups.len(), problem.n_clauses());
+        let mut ids: Vec<usize> = groups.iter().cloned().filter(|&g| g != 0).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        Selectors {
+            problem,
+            clauses: problem.clauses().collect(),
+            groups: groups.to_vec(),
+            ids,
+            solver: SatSolver::new(&with_selectors(problem, groups)),
+        }
+    }
+    fn selectors(&self, groups: &[usize]) -> Vec<i64> {
+        let n = self.problem.n_variables();
+        groups.iter().map(|&g| (n + g) as i64).collect()
+    }
+    /// Returns whether the model satisfies every clause of a group, indexed by the group.
+    fn satisfied(&self, model: &SatAssignments) -> Vec<bool> {
+        let mut res = vec![true; self.ids.last().map_or(1, |&g| g + 1)];
+        for (clause, &g) in self.clauses.iter().zip(&self.groups) {
+            if !clause
+                .iter()
+                .any(|&x| model[x.unsigned_abs() as usize - 1] == (x > 0))
+            {
+                res[g] = false;
+            }
+        }
+        res
+    }
+    /// Returns the indices of the clauses of the groups, and of the hard clauses if `hard` is set.
+    fn clauses_of(&self, groups: &[usize], hard: bool) -> Vec<usize> {
+        (0..self.clauses.len())
+            .filter(|&i| (hard && self.groups[i] == 0) || groups.contains(&self.groups[i]))
+            .collect()
+    }
+    /// Grows the groups that a model satisfies into a maximal satisfiable subset,
+    /// and returns the groups out of it in increasing order, which are a minimal correction set.
+    fn correction_set(&mut self, algorithm: McsAlgorithm, model: &SatAssignments) -> Vec<usize> {
+        let satisfied = self.satisfied(model);
+        let (mut kept, mut falsified): (Vec<usize>, Vec<usize>) =
+            self.ids.iter().partition(|&&g| satisfied[g]);
+        let mut res = vec![];
+        loop {
+            let model = match algorithm {
+                McsAlgorithm::LinearSearch => {
+                    let g = match falsified.pop() {
+                        Some(g) => g,
+                        None => break,
+                    };
+                    let mut assumptions = self.selectors(&kept);
+                    assumptions.extend(self.selectors(&[g]));
+                    match self.solver.solve_with_assumptions(&assumptions) {
+                        Some(model) => {
+                            kept.push(g);
+                            model
+                        }
+                        None => {
+                            res.push(g);
+                            continue;
+                        }
+                    }
+                }
+                McsAlgorithm::Cld => {
+                    if falsified.is_empty() {
+                        break;
+                    }
+                    // the clause D of the selectors of the falsified groups, activated by a fresh literal
+                    let activation = self.solver.new_variable();
+                    let mut clause = vec![-activation];
+                    clause.extend(self.selectors(&falsified));
+                    self.solver.add_clause(&clause);
+                    let mut assumptions = self.selectors(&kept);
+                    assumptions.push(activation);
+                    let model = self.solver.solve_with_assumptions(&assumptions);
+                    self.solver.add_clause(&[-activation]);
+                    match model {
+                        Some(model) => model,
+                        None => {
+                            res = std::mem::take(&mut falsified);
+                            break;
+                        }
+                    }
+                }
+            };
+            let satisfied = self.satisfied(&model);
+            kept.extend(falsified.iter().filter(|&&g| satisfied[g]));
+            falsified.retain(|&g| !satisfied[g]);
+        }
+        res.sort_unstable();
+        res
+    }
+}
+
+/// Enumerator of the minimal correction sets (MCS) of the groups of clauses: the minimal sets of groups
+/// whose removal makes the rest satisfiable together with the hard clauses of group 0 and the XOR constraints.
+/// Every clause is its own group unless the groups are given, as in GCNF.
+///
+/// Every MCS is the complement of a maximal satisfiable subset grown by the incremental solver,
+/// and a clause of its selectors blocks it, so that the next one is another MCS.
+pub struct McsEnumerator<'p> {
+    selectors: Selectors<'p>,
+    algorithm: McsAlgorithm,
+    /// whether every MCS is found
+    done: bool,
+}
+
+impl<'p> McsEnumerator<'p> {
+    pub fn new(problem: &'p SatProblem) -> McsEnumerator<'p> {
+        let groups: Vec<usize> = (1..=problem.n_clauses()).collect();
+        McsEnumerator::new_with_groups(problem, &groups)
+    }
+    /// Creates an enumerator over the groups of the clauses, where the clauses of group 0 are hard.
+    pub fn new_with_groups(problem: &'p SatProblem, groups: &[usize]) -> McsEnumerator<'p> {
+        McsEnumerator {
+            selectors: Selectors::new(problem, groups),
+            algorithm: McsAlgorithm::Cld,
+            done: false,
+        }
+    }
+    /// Sets the algorithm of every MCS, which is CLD by default.
+    pub fn set_algorithm(&mut self, algorithm: McsAlgorithm) {
+        self.algorithm = algorithm;
+    }
+    /// Returns the groups of the next MCS in increasing order, or None if there are no more.
+    pub fn next_groups(&mut self) -> Option<Vec<usize>> {
+        if self.done {
+            return None;
+        }
+        let model = match self.selectors.solver.solve() {
+            Some(model) => model,
+            None => {
+                self.done = true;
+                return None;
+            }
+        };
+        let res = self.selectors.correction_set(self.algorithm, &model);
+        if res.is_empty() {
+            self.done = true;
+        } else {
+            // a later maximal satisfiable subset keeps one of these groups
+            let clause = self.selectors.selectors(&res);
+            self.selectors.solver.add_clause(&clause);
+        }
+        Some(res)
+    }
+}
+
+impl<'p> Iterator for McsEnumerator<'p> {
+    type Item = Vec<usize>;
+    /// Returns the indices of the clauses of the next MCS in increasing order, or None if there are no more.
+    /// A satisfiable problem has only the empty MCS, and one whose hard clauses are unsatisfiable has none.
+    fn next(&mut self) -> Option<Vec<usize>> {
+        let groups = self.next_groups()?;
+        Some(self.selectors.clauses_of(&groups, false))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarcoResult {
+    Mus(Vec<usize>),
+    Mcs(Vec<usize>),
+}
+
+/// MARCO, which enumerates the MUSes and the MCSes of the groups of clauses together.
+/// A map solver over the groups gives a seed that contains no MUS and meets every MCS found so far.
+/// A satisfiable seed grows into a maximal satisfiable subset, whose complement is a new MCS,
+/// and an unsatisfiable one shrinks into a new MUS, and either is blocked in the map.
+pub struct Marco<'p> {
+    selectors: Selectors<'p>,
+    /// solver over the groups, whose variable g is true if the group g is in the seed
+    map: SatSolver<'p>,
+    algorithm: McsAlgorithm,
+    done: bool,
+}
+
+impl<'p> Marco<'p> {
+    pub fn new(problem: &'p SatProblem) -> Marco<'p> {
+        let groups: Vec<usize> = (1..=problem.n_clauses()).collect();
+        Marco::new_with_groups(problem, &groups)
+    }
+    /// Creates MARCO over the groups of the clauses, where the clauses of group 0 are hard.
+    pub fn new_with_groups(problem: &'p SatProblem, groups: &[usize]) -> Marco<'p> {
+        let selectors = Selectors::new(problem, groups);
+        let n_groups = selectors.ids.last().cloned().unwrap_or(0);
+        let mut map = SatSolver::new(&SatProblem::new(n_groups));
+        // large seeds, which are more likely to grow into an MCS quickly
+        map.set_phases(&vec![true; n_groups]);
+        Marco {
+            selectors,
+            map,
+            algorithm: McsAlgorithm::Cld,
+            done: false,
+        }
+    }
+    /// Sets the algorithm of every MCS, which is CLD by default.
+    pub fn set_algorithm(&mut self, algorithm: McsAlgorithm) {
+        self.algorithm = algorithm;
+    }
+    /// Returns the groups of the next MUS or MCS in increasing order, or None if there are no more.
+    pub fn next_groups(&mut self) -> Option<MarcoResult> {
+        if self.done {
+            return None;
+        }
+        let seed = match self.map.solve() {
+            Some(model) => model,
+            None => {
+                self.done = true;
+                return None;
+            }
+        };
+        let seed: Vec<usize> = self
+            .selectors
+            .ids
+            .iter()
+            .cloned()
+            .filter(|&g| seed[g - 1])
+            .collect();
+        let assumptions = self.selectors.selectors(&seed);
+        match self.selectors.solver.solve_with_assumptions(&assumptions) {
+            Some(model) => {
+                let res = self.selectors.correction_set(self.algorithm, &model);
+                if res.is_empty() {
+                    self.done = true;
+                } else {
+                    let clause: Vec<i64> = res.iter().map(|&g| g as i64).collect();
+                    self.map.add_clause(&clause);
+                }
+                Some(MarcoResult::Mcs(res))
+            }
+            None => {
+                let clauses = self.selectors.clauses_of(&seed, true);
+                let problem = subproblem(self.selectors.problem, &clauses);
+                let groups: Vec<usize> =
+                    clauses.iter().map(|&i| self.selectors.groups[i]).collect();
+                let res = MusExtractor::new_with_groups(&problem, &groups)
+                    .extract_groups()
+                    .unwrap();
+                if res.is_empty() {
+                    self.done = true;
+                } else {
+                    let clause: Vec<i64> = res.iter().map(|&g| -(g as i64)).collect();
+                    self.map.add_clause(&clause);
+                }
+                Some(MarcoResult::Mus(res))
+            }
+        }
+    }
+}
+
+impl<'p> Iterator for Marco<'p> {
+    type Item = MarcoResult;
+    /// Returns the indices of the clauses of the next MUS with the hard clauses or the next MCS
+    /// in increasing order, or None if there are no more.
+    fn next(&mut self) -> Option<MarcoResult> {
+        Some(match self.next_groups()? {
+            MarcoResult::Mus(groups) => MarcoResult::Mus(self.selectors.clauses_of(&groups, true)),
+            MarcoResult::Mcs(groups) => MarcoResult::Mcs(self.selectors.clauses_of(&groups, false)),
+        })
+    }
+}
+
+#[test]
+fn test_mcs() {
+    // x1 and not x1, x2 and not x2, and x1 or x2, whose MUSes are {0, 1}, {2, 3} and {1, 3, 4}
+    let problem = SatProblem::new_from_dimacs("p cnf 2 5\n1 0\n-1 0\n2 0\n-2 0\n1 2 0\n");
+    let expected = vec![vec![0, 2, 4], vec![0, 3], vec![1, 2], vec![1, 3]];
+    for &algorithm in &[McsAlgorithm::LinearSearch, McsAlgorithm::Cld] {
+        let mut mcses = McsEnumerator::new(&problem);
+        mcses.set_algorithm(algorithm);
+        let mut res: Vec<Vec<usize>> = mcses.collect();
+        res.sort();
+        assert_eq!(res, expected);
+        let mut marco = Marco::new(&problem);
+        marco.set_algorithm(algorithm);
+        let mut mcses = vec![];
+        let mut muses = vec![];
+        for x in marco {
+            match x {
+                MarcoResult::Mus(mus) => muses.push(mus),
+                MarcoResult::Mcs(mcs) => mcses.push(mcs),
+            }
+        }
+        mcses.sort();
+        muses.sort();
+        assert_eq!(mcses, expected);
+        assert_eq!(muses, vec![vec![0, 1], vec![1, 3, 4], vec![2, 3]]);
+    }
+    let sat = SatProblem::new_from_dimacs("p cnf 2 2\n1 2 0\n-1 0\n");
+    let mut mcses = McsEnumerator::new(&sat);
+    assert_eq!(mcses.next(), Some(vec![]));
+    assert_eq!(mcses.next(), None);
+}
+
+#[test]
+fn test_mcs_against_brute_force() {
+    use crate::fuzz::brute_force;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
+    for seed in 0..30 {
+        let mut problem = SatProblem::gen_random_sat(6, 14, 2, 0.5, seed);
+        for _ in 0..rng.gen_range(0, 4) {
+            let x = rng.gen_range(1, 7);
+            problem.add_clause(&[if rng.gen() { x } else { -x }]);
+        }
+        if seed % 3 == 0 {
+            problem.add_xor(&[1, 2, 3]);
+        }
+        let groups: Vec<usize> = (0..problem.n_clauses())
+            .map(|_| rng.gen_range(0, 7))
+            .collect();
+        let mut ids: Vec<usize> = groups.iter().cloned().filter(|&g| g != 0).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        // every subset of the groups, whether it is satisfiable with the hard clauses
+        let subsets: Vec<(Vec<usize>, bool)> = (0..1u32 << ids.len())
+            .map(|bits| {
+                let subset: Vec<usize> = (0..ids.len())
+                    .filter(|&i| bits >> i & 1 == 1)
+                    .map(|i| ids[i])
+                    .collect();
+                let clauses: Vec<usize> = (0..groups.len())
+                    .filter(|&i| groups[i] == 0 || subset.contains(&groups[i]))
+                    .collect();
+                let satisfiable = brute_force(&subproblem(&problem, &clauses), &[]).is_some();
+                (subset, satisfiable)
+            })
+            .collect();
+        let is_subset = |xs: &[usize], ys: &[usize]| xs.iter().all(|x| ys.contains(x));
+        let mut expected_mcses: Vec<Vec<usize>> = subsets
+            .iter()
+            .filter(|(xs, sat)| {
+                *sat && !subsets
+                    .iter()
+                    .any(|(ys, t)| *t && ys.len() > xs.len() && is_subset(xs, ys))
+            })
+            .map(|(xs, _)| ids.iter().cloned().filter(|g| !xs.contains(g)).collect())
+            .collect();
+        let mut expected_muses: Vec<Vec<usize>> = subsets
+            .iter()
+            .filter(|(xs, sat)| {
+                !*sat
+                    && !subsets
+                        .iter()
+                        .any(|(ys, t)| !*t && ys.len() < xs.len() && is_subset(ys, xs))
+            })
+            .map(|(xs, _)| xs.clone())
+            .collect();
+        expected_mcses.sort();
+        expected_muses.sort();
+        for &algorithm in &[McsAlgorithm::LinearSearch, McsAlgorithm::Cld] {
+            let mut mcses = McsEnumerator::new_with_groups(&problem, &groups);
+            mcses.set_algorithm(algorithm);
+            let mut res = vec![];
+            while let Some(mcs) = mcses.next_groups() {
+                res.push(mcs);
+            }
+            res.sort();
+            assert_eq!(res, expected_mcses);
+            let mut marco = Marco::new_with_groups(&problem, &groups);
+            marco.set_algorithm(algorithm);
+            let (mut mcses, mut muses) = (vec![], vec![]);
+            while let Some(x) = marco.next_groups() {
+                match x {
+                    MarcoResult::Mus(mus) => muses.push(mus),
+                    MarcoResult::Mcs(mcs) => mcses.push(mcs),
+                }
+            }
+            mcses.sort();
+            muses.sort();
+            assert_eq!(mcses, expected_mcses);
+            assert_eq!(muses, expected_muses);
+        }
+    }
+}
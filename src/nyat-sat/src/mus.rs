@@ -0,0 +1,312 @@
+use crate::sat::{content_lines, SatAssignments, SatProblem, SatSolver};
+
+/// Parses GCNF, where a `p gcnf <variables> <clauses> <groups>` header is followed by clauses prefixed
+/// by `{g}`, and returns the clauses and the group of every clause, where the clauses of group 0 are hard.
+pub fn parse_gcnf(s: &str) -> (SatProblem, Vec<usize>) {
+    let mut iter = content_lines(s);
+    let mut header = iter.next().unwrap().split_whitespace();
+    assert_eq!(header.next(), Some("p"));
+    assert_eq!(header.next(), Some("gcnf"));
+    let n_variables = header.next().unwrap().parse::<usize>().unwrap();
+    let n_clauses = header.next().unwrap().parse::<usize>().unwrap();
+    let n_groups = header.next().unwrap().parse::<usize>().unwrap();
+    let mut problem = SatProblem::new(n_variables);
+    let mut groups = vec![];
+    let mut literals = vec![];
+    for t in iter.flat_map(|x| x.split_whitespace()) {
+        if let Some(group) = t.strip_prefix('{') {
+            assert!(literals.is_empty(), "a group in a clause");
+            let group = group.strip_suffix('}').unwrap().parse::<usize>().unwrap();
+            assert!(group <= n_groups);
+            groups.push(group);
+            continue;
+        }
+        let x = t.parse::<i64>().unwrap();
+        if x != 0 {
+            literals.push(x);
+            continue;
+        }
+        assert_eq!(
+            groups.len(),
+            problem.n_clauses() + 1,
+            "a clause without a group"
+        );
+        problem.add_clause(&std::mem::take(&mut literals));
+    }
+    assert_eq!(problem.n_clauses(), n_clauses);
+    (problem, groups)
+}
+
+/// Returns the problem of the clauses at `indices` and the XOR constraints, such as the clauses of a MUS.
+pub fn subproblem(problem: &SatProblem, indices: &[usize]) -> SatProblem {
+    let clauses: Vec<Vec<i64>> = problem.clauses().collect();
+    let mut res = SatProblem::new(problem.n_variables());
+    for &i in indices {
+        res.add_clause(&clauses[i]);
+    }
+    for xor in problem.xors() {
+        res.add_xor(&xor);
+    }
+    res
+}
+
+/// Returns the problem where the clauses of every group g other than 0 are relaxed by the negation
+/// of the selector `n + g` of the n variables, so that assuming the selector enforces the group.
+pub(crate) fn with_selectors(problem: &SatProblem, groups: &[usize]) -> SatProblem {
+    let n = problem.n_variables();
+    let mut res = SatProblem::new(n + groups.iter().cloned().max().unwrap_or(0));
+    for (mut clause, &g) in problem.clauses().zip(groups) {
+        if g != 0 {
+            clause.push(-((n + g) as i64));
+        }
+        res.add_clause(&clause);
+    }
+    for xor in problem.xors() {
+        res.add_xor(&xor);
+    }
+    res
+}
+
+/// Extractor of a minimal unsatisfiable subset (MUS) of the groups of clauses, whose every proper subset
+/// is satisfiable together with the hard clauses of group 0 and the XOR constraints.
+/// Every clause is its own group unless the groups are given, as in GCNF.
+///
+/// An incremental solver assumes a selector of every group. The failed assumptions of an UNSAT call refine
+/// the core, and deletion removes its groups one by one. A group whose removal is satisfiable is necessary,
+/// and model rotation flips the variables of its clauses in the model to find other necessary groups
+/// without solving.
+pub struct MusExtractor<'p> {
+    problem: &'p SatProblem,
+    clauses: Vec<Vec<i64>>,
+    /// group of every clause
+    groups: Vec<usize>,
+    n_groups: usize,
+    model_rotation: bool,
+    n_solves: usize,
+    /// necessary groups found by model rotation
+    n_rotated: usize,
+}
+
+impl<'p> MusExtractor<'p> {
+    pub fn new(problem: &'p SatProblem) -> MusExtractor<'p> {
+        let groups: Vec<usize> = (1..=problem.n_clauses()).collect();
+        MusExtractor::new_with_groups(problem, &groups)
+    }
+    /// Creates an extractor over the groups of the clauses, where the clauses of group 0 are hard.
+    pub fn new_with_groups(problem: &'p SatProblem, groups: &[usize]) -> MusExtractor<'p> {
+        assert_eq!(groups.len(), problem.n_clauses());
+        MusExtractor {
+            problem,
+            clauses: problem.clauses().collect(),
+            groups: groups.to_vec(),
+            n_groups: groups.iter().cloned().max().unwrap_or(0),
+            model_rotation: true,
+            n_solves: 0,
+            n_rotated: 0,
+        }
+    }
+    /// Sets whether model rotation is used, which is the default.
+    pub fn set_model_rotation(&mut self, model_rotation: bool) {
+        self.model_rotation = model_rotation;
+    }
+    pub fn n_solves(&self) -> usize {
+        self.n_solves
+    }
+    pub fn n_rotated(&self) -> usize {
+        self.n_rotated
+    }
+    /// Returns the indices of the clauses of a MUS with the hard clauses in increasing order,
+    /// or None if the problem is satisfiable.
+    pub fn extract(&mut self) -> Option<Vec<usize>> {
+        let mut in_mus = vec![false; self.n_groups + 1];
+        in_mus[0] = true;
+        for g in self.extract_groups()? {
+            in_mus[g] = true;
+        }
+        Some(
+            (0..self.clauses.len())
+                .filter(|&i| in_mus[self.groups[i]])
+                .collect(),
+        )
+    }
+    /// Returns the groups of a MUS in increasing order, or None if the problem is satisfiable.
+    pub fn extract_groups(&mut self) -> Option<Vec<usize>> {
+        let n = self.problem.n_variables();
+        let selector = |g: usize| (n + g) as i64;
+        let mut solver = SatSolver::new(&with_selectors(self.problem, &self.groups));
+        let mut core: Vec<usize> = self.groups.iter().cloned().filter(|&g| g != 0).collect();
+        core.sort_unstable();
+        core.dedup();
+        let mut necessary = vec![false; self.n_groups + 1];
+        let assumptions =
+            |core: &[usize]| -> Vec<i64> { core.iter().map(|&g| selector(g)).collect() };
+        // the groups of the failed assumptions
+        let refine = |solver: &SatSolver| -> Vec<usize> {
+            let mut res: Vec<usize> = solver
+                .failed_assumptions()
+                .iter()
+                .map(|&x| x as usize - n)
+                .collect();
+            res.sort_unstable();
+            res
+        };
+        self.n_solves += 1;
+        if solver.solve_with_assumptions(&assumptions(&core)).is_some() {
+            return None;
+        }
+        core = refine(&solver);
+        while let Some(&g) = core.iter().find(|&&g| !necessary[g]) {
+            let rest: Vec<usize> = core.iter().cloned().filter(|&h| h != g).collect();
+            self.n_solves += 1;
+            match solver.solve_with_assumptions(&assumptions(&rest)) {
+                Some(model) => {
+                    necessary[g] = true;
+                    if self.model_rotation {
+                        self.rotate(&model, g, &core, &mut necessary);
+                    }
+                }
+                None => {
+                    core = refine(&solver);
+                    // the group is left out for good
+                    solver.add_clause(&[-selector(g)]);
+                }
+            }
+        }
+        Some(core)
+    }
+    /// Returns the groups of the core whose clauses the assignment falsifies,
+    /// or None if it falsifies a hard clause or an XOR constraint.
+    fn falsified_groups(&self, assignment: &[bool], in_core: &[bool]) -> Option<Vec<usize>> {
+        let value = |x: i64| assignment[x.unsigned_abs() as usize - 1] == (x > 0);
+        if !self
+            .problem
+            .xors()
+            .all(|xor| xor.iter().filter(|&&x| value(x)).count() % 2 == 1)
+        {
+            return None;
+        }
+        let mut res = vec![];
+        for (clause, &g) in self.clauses.iter().zip(&self.groups) {
+            if (g == 0 || in_core[g]) && !clause.iter().any(|&x| value(x)) {
+                if g == 0 {
+                    return None;
+                }
+                res.push(g);
+            }
+        }
+        res.sort_unstable();
+        res.dedup();
+        Some(res)
+    }
+    /// Recursive model rotation: the model falsifies only the group `g` of the core, and if flipping
+    /// a variable of its clauses falsifies only another group, that group is necessary as well.
+    fn rotate(&mut self, model: &SatAssignments, g: usize, core: &[usize], necessary: &mut [bool]) {
+        let mut in_core = vec![false; self.n_groups + 1];
+        for &h in core {
+            in_core[h] = true;
+        }
+        let model: Vec<bool> = (0..self.problem.n_variables()).map(|i| model[i]).collect();
+        let mut stack = vec![(model, g)];
+        while let Some((model, g)) = stack.pop() {
+            let clauses: Vec<usize> = (0..self.clauses.len())
+                .filter(|&i| self.groups[i] == g)
+                .collect();
+            for i in clauses {
+                for &x in &self.clauses[i] {
+                    let mut flipped = model.clone();
+                    let id = x.unsigned_abs() as usize - 1;
+                    flipped[id] = !flipped[id];
+                    if let Some(falsified) = self.falsified_groups(&flipped, &in_core) {
+                        if falsified.len() == 1 && !necessary[falsified[0]] {
+                            necessary[falsified[0]] = true;
+                            self.n_rotated += 1;
+                            stack.push((flipped, falsified[0]));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_mus() {
+    // x1, x1 -> x2, not x2 conflict, and so do x3, x3 -> x1 with the last two
+    let problem = SatProblem::new_from_dimacs("p cnf 5 6\n1 0\n-1 2 0\n-2 0\n3 0\n-3 1 0\n4 5 0\n");
+    for &model_rotation in &[false, true] {
+        let mut mus = MusExtractor::new(&problem);
+        mus.set_model_rotation(model_rotation);
+        let res = mus.extract().unwrap();
+        assert!(res == vec![0, 1, 2] || res == vec![1, 2, 3, 4]);
+    }
+    let sat = SatProblem::new_from_dimacs("p cnf 2 2\n1 2 0\n-1 0\n");
+    assert_eq!(MusExtractor::new(&sat).extract(), None);
+    let mut mus = MusExtractor::new(&problem);
+    mus.extract_groups().unwrap();
+    assert!(mus.n_rotated() > 0);
+}
+
+#[test]
+fn test_group_mus() {
+    let (problem, groups) = parse_gcnf(
+        "c two groups conflict under the hard clause\np gcnf 3 5 3\n{0} -1 -2 0\n{1} 1 0\n{1} 3 0\n{2} 2 0\n{3} -3 0\n",
+    );
+    assert_eq!(groups, vec![0, 1, 1, 2, 3]);
+    let mut mus = MusExtractor::new_with_groups(&problem, &groups);
+    assert_eq!(mus.extract_groups(), Some(vec![1, 2]));
+    let mut mus = MusExtractor::new_with_groups(&problem, &groups);
+    let res = mus.extract().unwrap();
+    assert_eq!(res, vec![0, 1, 2, 3]);
+    assert_eq!(
+        subproblem(&problem, &res).to_dimacs(),
+        "p cnf 3 4\n-1 -2 0\n1 0\n3 0\n2 0\n"
+    );
+}
+
+#[test]
+fn test_mus_against_brute_force() {
+    use crate::fuzz::brute_force;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
+    for seed in 0..100 {
+        let mut problem = SatProblem::gen_random_sat(8, 40, 3, 0.5, seed);
+        problem.add_clause(&[-1, -2]);
+        problem.add_clause(&[1]);
+        problem.add_clause(&[2, 3]);
+        problem.add_clause(&[-3]);
+        if seed % 2 == 0 {
+            problem.add_xor(&[4, 5, 6]);
+        }
+        let groups: Vec<usize> = (0..problem.n_clauses())
+            .map(|_| rng.gen_range(0, 8))
+            .collect();
+        let satisfiable =
+            |clauses: &[usize]| brute_force(&subproblem(&problem, clauses), &[]).is_some();
+        for &model_rotation in &[false, true] {
+            let mut mus = MusExtractor::new(&problem);
+            mus.set_model_rotation(model_rotation);
+            let res = mus.extract().unwrap();
+            assert!(!satisfiable(&res));
+            for i in 0..res.len() {
+                let mut rest = res.clone();
+                rest.remove(i);
+                assert!(satisfiable(&rest));
+            }
+            let mut mus = MusExtractor::new_with_groups(&problem, &groups);
+            mus.set_model_rotation(model_rotation);
+            let res = mus.extract_groups().unwrap();
+            let clauses = |gs: &[usize]| -> Vec<usize> {
+                (0..groups.len())
+                    .filter(|&i| groups[i] == 0 || gs.contains(&groups[i]))
+                    .collect()
+            };
+            assert!(!satisfiable(&clauses(&res)));
+            for i in 0..res.len() {
+                let mut rest = res.clone();
+                rest.remove(i);
+                assert!(satisfiable(&clauses(&rest)));
+            }
+        }
+    }
+}
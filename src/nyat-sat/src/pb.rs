@@ -0,0 +1,691 @@
+use crate::encodings::{sorting_network, ClauseSink, GeneralizedTotalizer};
+use crate::sat::{SatAssignments, SatProblem, SatSolver};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    GreaterEqual,
+    Equal,
+    LessEqual,
+}
+
+/// Linear constraint over `(coefficient, literal)` terms with DIMACS literals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PbConstraint {
+    pub terms: Vec<(i64, i64)>,
+    pub relation: Relation,
+    pub rhs: i64,
+}
+
+impl PbConstraint {
+    pub fn is_satisfied(&self, assignment: &SatAssignments) -> bool {
+        let lhs = evaluate(&self.terms, assignment);
+        match self.relation {
+            Relation::GreaterEqual => lhs >= self.rhs,
+            Relation::Equal => lhs == self.rhs,
+            Relation::LessEqual => lhs <= self.rhs,
+        }
+    }
+}
+
+fn evaluate(terms: &[(i64, i64)], assignment: &SatAssignments) -> i64 {
+    terms
+        .iter()
+        .filter(|x| assignment[x.1.unsigned_abs() as usize - 1] == (x.1 > 0))
+        .map(|x| x.0)
+        .sum()
+}
+
+/// Encoding of a constraint into clauses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClausalEncoding {
+    /// Reduced BDD of the constraint, whose nodes are shared by the intervals of their bounds.
+    Bdd,
+    /// Binary adders of the weighted sum, compared with the bound bit by bit.
+    Adder,
+    /// Generalized totalizer, with an output for every partial sum up to the bound.
+    GeneralizedTotalizer,
+    /// Sorting networks of the literals of every bit of the coefficients and the carries of the bit
+    /// below it, whose parities are compared with the bound bit by bit.
+    SortingNetwork,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PbEncoding {
+    /// clauses of an encoding
+    Clauses(ClausalEncoding),
+    /// No clauses: the constraint is kept in `SatSolver`, which propagates it natively.
+    Native,
+}
+
+/// Pseudo-Boolean problem: linear constraints and an optional objective to minimize.
+#[derive(Debug)]
+pub struct PbProblem {
+    n_variables: usize,
+    objective: Option<Vec<(i64, i64)>>,
+    constraints: Vec<PbConstraint>,
+}
+
+#[derive(Debug)]
+pub enum PbResult {
+    Satisfiable(SatAssignments),
+    Optimum { value: i64, model: SatAssignments },
+    Unsatisfiable,
+}
+
+impl PbProblem {
+    pub fn new(n_variables: usize) -> PbProblem {
+        PbProblem {
+            n_variables,
+            objective: None,
+            constraints: vec![],
+        }
+    }
+    pub fn n_variables(&self) -> usize {
+        self.n_variables
+    }
+    pub fn objective(&self) -> Option<&[(i64, i64)]> {
+        self.objective.as_deref()
+    }
+    pub fn constraints(&self) -> &[PbConstraint] {
+        &self.constraints
+    }
+    fn extend_variables(&mut self, terms: &[(i64, i64)]) {
+        for &(_, x) in terms {
+            self.n_variables = self.n_variables.max(x.unsigned_abs() as usize);
+        }
+    }
+    pub fn set_objective(&mut self, terms: &[(i64, i64)]) {
+        self.extend_variables(terms);
+        self.objective = Some(terms.to_vec());
+    }
+    pub fn add_constraint(&mut self, terms: &[(i64, i64)], relation: Relation, rhs: i64) {
+        self.extend_variables(terms);
+        self.constraints.push(PbConstraint {
+            terms: terms.to_vec(),
+            relation,
+            rhs,
+        });
+    }
+    pub fn check_assignment(&self, assignment: &SatAssignments) -> bool {
+        self.constraints.iter().all(|x| x.is_satisfied(assignment))
+    }
+    /// Returns the value of the objective, or 0 without an objective.
+    pub fn value(&self, assignment: &SatAssignments) -> i64 {
+        self.objective
+            .as_ref()
+            .map_or(0, |x| evaluate(x, assignment))
+    }
+    /// Parses the OPB format of the PB competition, with `x1` and `~x1` literals.
+    /// Only linear constraints are supported.
+    pub fn new_from_opb(s: &str) -> PbProblem {
+        let mut res = PbProblem::new(0);
+        let mut statement = vec![];
+        for line in s.lines() {
+            if line.starts_with('*') {
+                let mut iter = line.split_whitespace();
+                while let Some(t) = iter.next() {
+                    if t == "#variable=" {
+                        let n = iter.next().unwrap().parse::<usize>().unwrap();
+                        res.n_variables = res.n_variables.max(n);
+                    }
+                }
+                continue;
+            }
+            for t in line.replace(';', " ; ").split_whitespace() {
+                if t != ";" {
+                    statement.push(t.to_string());
+                    continue;
+                }
+                res.add_statement(&statement);
+                statement.clear();
+            }
+        }
+        assert!(statement.is_empty(), "missing ';'");
+        res
+    }
+    fn add_statement(&mut self, statement: &[String]) {
+        let (is_objective, rest) = match statement.first().map(|x| x.as_str()) {
+            Some("min:") => (true, &statement[1..]),
+            _ => (false, statement),
+        };
+        let mut terms = vec![];
+        let mut i = 0;
+        while i < rest.len() && !["<=", "=", ">="].contains(&rest[i].as_str()) {
+            // a product of literals has no coefficient after its first literal
+            let coefficient = rest[i]
+                .parse::<i64>()
+                .expect("non-linear terms are not supported");
+            let literal = parse_literal(&rest[i + 1]);
+            terms.push((coefficient, literal));
+            i += 2;
+        }
+        if is_objective {
+            assert_eq!(i, rest.len());
+            self.set_objective(&terms);
+            return;
+        }
+        let relation = match rest[i].as_str() {
+            ">=" => Relation::GreaterEqual,
+            "=" => Relation::Equal,
+            _ => Relation::LessEqual,
+        };
+        assert_eq!(i + 2, rest.len());
+        let rhs = rest[i + 1].parse::<i64>().unwrap();
+        self.add_constraint(&terms, relation, rhs);
+    }
+    /// Encodes the constraints into clauses over the same variables and auxiliary ones.
+    pub fn to_sat_problem(&self, encoding: ClausalEncoding) -> SatProblem {
+        let mut res = SatProblem::new(self.n_variables);
+        for constraint in &self.constraints {
+            add_constraint(&mut res, constraint, encoding);
+        }
+        res
+    }
+    /// Solves the problem, and minimizes the objective by adding a bound below every model
+    /// until it is unsatisfiable. `on_model` is called with the value of every model.
+    pub fn solve<F: FnMut(i64)>(&self, encoding: PbEncoding, mut on_model: F) -> PbResult {
+        let mut solver = SatSolver::new(&SatProblem::new(self.n_variables));
+        for constraint in &self.constraints {
+            solver.add_pb_constraint(constraint, encoding);
+        }
+        let mut model = match solver.solve() {
+            Some(model) => model,
+            None => return PbResult::Unsatisfiable,
+        };
+        model.truncate(self.n_variables);
+        let objective = match &self.objective {
+            Some(objective) => objective,
+            None => return PbResult::Satisfiable(model),
+        };
+        let mut value = evaluate(objective, &model);
+        on_model(value);
+        loop {
+            let bound = PbConstraint {
+                terms: objective.clone(),
+                relation: Relation::LessEqual,
+                rhs: value - 1,
+            };
+            solver.add_pb_constraint(&bound, encoding);
+            match solver.solve() {
+                Some(mut x) => {
+                    x.truncate(self.n_variables);
+                    value = evaluate(objective, &x);
+                    model = x;
+                    on_model(value);
+                }
+                None => return PbResult::Optimum { value, model },
+            }
+        }
+    }
+}
+
+impl<'a> SatSolver<'a> {
+    /// Adds a constraint, either natively or as the clauses of its encoding.
+    pub fn add_pb_constraint(&mut self, constraint: &PbConstraint, encoding: PbEncoding) {
+        if let PbEncoding::Clauses(encoding) = encoding {
+            add_constraint(self, constraint, encoding);
+            return;
+        }
+        let negated: Vec<(i64, i64)> = constraint.terms.iter().map(|&(a, x)| (-a, x)).collect();
+        if constraint.relation != Relation::GreaterEqual {
+            let (terms, bound) = normalize(&constraint.terms, constraint.rhs);
+            self.add_native_at_most(&terms, bound);
+        }
+        if constraint.relation != Relation::LessEqual {
+            let (terms, bound) = normalize(&negated, -constraint.rhs);
+            self.add_native_at_most(&terms, bound);
+        }
+    }
+}
+
+fn parse_literal(t: &str) -> i64 {
+    let (sign, name) = match t.strip_prefix('~') {
+        Some(name) => (-1, name),
+        None => (1, t),
+    };
+    assert!(name.starts_with('x'), "unexpected literal: {}", t);
+    sign * name[1..].parse::<i64>().unwrap()
+}
+
+/// Adds the clauses of a constraint, as one or two constraints of the form `sum <= bound`.
+pub fn add_constraint<S: ClauseSink>(
+    sink: &mut S,
+    constraint: &PbConstraint,
+    encoding: ClausalEncoding,
+) {
+    let negated: Vec<(i64, i64)> = constraint.terms.iter().map(|&(a, x)| (-a, x)).collect();
+    if constraint.relation != Relation::GreaterEqual {
+        let (terms, bound) = normalize(&constraint.terms, constraint.rhs);
+        at_most(sink, &terms, bound, encoding);
+    }
+    if constraint.relation != Relation::LessEqual {
+        let (terms, bound) = normalize(&negated, -constraint.rhs);
+        at_most(sink, &terms, bound, encoding);
+    }
+}
+
+/// Rewrites `sum <= rhs` into `(literal, coefficient)` terms with positive coefficients,
+/// since `a x = a - a ~x` for a negative coefficient `a`.
+fn normalize(terms: &[(i64, i64)], rhs: i64) -> (Vec<(i64, u64)>, i64) {
+    let mut res = vec![];
+    let mut bound = rhs;
+    for &(a, x) in terms {
+        if a > 0 {
+            res.push((x, a as u64));
+        } else if a < 0 {
+            res.push((-x, a.unsigned_abs()));
+            bound -= a;
+        }
+    }
+    (res, bound)
+}
+
+/// Adds the clauses that bound the weighted sum of the true literals by `bound`.
+pub fn at_most<S: ClauseSink>(
+    sink: &mut S,
+    terms: &[(i64, u64)],
+    bound: i64,
+    encoding: ClausalEncoding,
+) {
+    if bound < 0 {
+        sink.add_clause(&[]);
+        return;
+    }
+    let bound = bound as u64;
+    // a literal whose coefficient is larger than the bound is false
+    let mut rest = vec![];
+    for &(x, a) in terms {
+        if a > bound {
+            sink.add_clause(&[-x]);
+        } else {
+            rest.push((x, a));
+        }
+    }
+    if rest.iter().map(|x| x.1).sum::<u64>() <= bound {
+        return;
+    }
+    match encoding {
+        ClausalEncoding::Bdd => Bdd::new(&rest).encode(sink, bound),
+        ClausalEncoding::Adder => adder(sink, &rest, bound),
+        ClausalEncoding::GeneralizedTotalizer => {
+            let totalizer = GeneralizedTotalizer::new(sink, &rest, bound + 1);
+            for x in totalizer.at_least(bound + 1) {
+                sink.add_clause(&[-x]);
+            }
+        }
+        ClausalEncoding::SortingNetwork => sorters(sink, &rest, bound),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BddNode {
+    True,
+    False,
+    Variable(i64),
+}
+
+/// Builds the BDD of `sum <= k` from the first term, where the node of the terms from `i`
+/// is the same for every `k` in an interval, which `intervals[i]` keeps.
+struct Bdd<'t> {
+    terms: &'t [(i64, u64)],
+    /// sum of the coefficients from every term
+    suffix_sums: Vec<u64>,
+    intervals: Vec<Vec<(i64, i64, BddNode)>>,
+}
+
+impl<'t> Bdd<'t> {
+    fn new(terms: &'t [(i64, u64)]) -> Bdd<'t> {
+        let mut suffix_sums = vec![0; terms.len() + 1];
+        for i in (0..terms.len()).rev() {
+            suffix_sums[i] = suffix_sums[i + 1] + terms[i].1;
+        }
+        Bdd {
+            terms,
+            suffix_sums,
+            intervals: vec![vec![]; terms.len()],
+        }
+    }
+    fn encode<S: ClauseSink>(&mut self, sink: &mut S, bound: u64) {
+        match self.build(sink, 0, bound as i64).0 {
+            BddNode::True => {}
+            BddNode::False => sink.add_clause(&[]),
+            BddNode::Variable(x) => sink.add_clause(&[x]),
+        }
+    }
+    /// Returns the node of the terms from `i` with the bound `k`, and the interval of its bounds.
+    fn build<S: ClauseSink>(&mut self, sink: &mut S, i: usize, k: i64) -> (BddNode, i64, i64) {
+        if k < 0 {
+            return (BddNode::False, i64::MIN, -1);
+        }
+        if k as u64 >= self.suffix_sums[i] {
+            return (BddNode::True, self.suffix_sums[i] as i64, i64::MAX);
+        }
+        if let Some(&(lo, hi, node)) = self.intervals[i].iter().find(|x| x.0 <= k && k <= x.1) {
+            return (node, lo, hi);
+        }
+        let (x, a) = self.terms[i];
+        let a = a as i64;
+        let (high, high_lo, high_hi) = self.build(sink, i + 1, k - a);
+        let (low, low_lo, low_hi) = self.build(sink, i + 1, k);
+        let lo = high_lo.saturating_add(a).max(low_lo);
+        let hi = high_hi.saturating_add(a).min(low_hi);
+        let node = if high == low {
+            high
+        } else {
+            // the node implies its children, which is enough for an upper bound
+            let y = sink.new_variable();
+            match high {
+                BddNode::True => {}
+                BddNode::False => sink.add_clause(&[-y, -x]),
+                BddNode::Variable(z) => sink.add_clause(&[-y, -x, z]),
+            }
+            if let BddNode::Variable(z) = low {
+                sink.add_clause(&[-y, z]);
+            }
+            BddNode::Variable(y)
+        };
+        self.intervals[i].push((lo, hi, node));
+        (node, lo, hi)
+    }
+}
+
+/// Returns `x xor y` and `x and y`.
+fn half_adder<S: ClauseSink>(sink: &mut S, x: i64, y: i64) -> (i64, i64) {
+    let s = sink.new_variable();
+    let c = sink.new_variable();
+    sink.add_clause(&[-x, -y, -s]);
+    sink.add_clause(&[x, y, -s]);
+    sink.add_clause(&[-x, y, s]);
+    sink.add_clause(&[x, -y, s]);
+    sink.add_clause(&[-x, -y, c]);
+    sink.add_clause(&[x, -c]);
+    sink.add_clause(&[y, -c]);
+    (s, c)
+}
+
+/// Returns the sum bit and the carry of the three literals.
+fn full_adder<S: ClauseSink>(sink: &mut S, x: i64, y: i64, z: i64) -> (i64, i64) {
+    let s = sink.new_variable();
+    let c = sink.new_variable();
+    sink.add_clause(&[x, y, z, -s]);
+    sink.add_clause(&[x, -y, -z, -s]);
+    sink.add_clause(&[-x, y, -z, -s]);
+    sink.add_clause(&[-x, -y, z, -s]);
+    sink.add_clause(&[-x, -y, -z, s]);
+    sink.add_clause(&[-x, y, z, s]);
+    sink.add_clause(&[x, -y, z, s]);
+    sink.add_clause(&[x, y, -z, s]);
+    sink.add_clause(&[-x, -y, c]);
+    sink.add_clause(&[-x, -z, c]);
+    sink.add_clause(&[-y, -z, c]);
+    sink.add_clause(&[x, y, -c]);
+    sink.add_clause(&[x, z, -c]);
+    sink.add_clause(&[y, z, -c]);
+    (s, c)
+}
+
+fn adder<S: ClauseSink>(sink: &mut S, terms: &[(i64, u64)], bound: u64) {
+    // literals of every bit of the sum, which the adders reduce to one
+    let mut buckets: Vec<Vec<i64>> = vec![];
+    for &(x, a) in terms {
+        for bit in 0..64 {
+            if a >> bit & 1 == 1 {
+                if buckets.len() <= bit {
+                    buckets.resize(bit + 1, vec![]);
+                }
+                buckets[bit].push(x);
+            }
+        }
+    }
+    let mut bits: Vec<Option<i64>> = vec![];
+    let mut i = 0;
+    while i < buckets.len() {
+        while buckets[i].len() >= 2 {
+            let (s, c) = if buckets[i].len() >= 3 {
+                let z = buckets[i].pop().unwrap();
+                let y = buckets[i].pop().unwrap();
+                let x = buckets[i].pop().unwrap();
+                full_adder(sink, x, y, z)
+            } else {
+                let y = buckets[i].pop().unwrap();
+                let x = buckets[i].pop().unwrap();
+                half_adder(sink, x, y)
+            };
+            buckets[i].push(s);
+            if buckets.len() <= i + 1 {
+                buckets.push(vec![]);
+            }
+            buckets[i + 1].push(c);
+        }
+        bits.push(buckets[i].pop());
+        i += 1;
+    }
+    forbid_above(sink, &bits, bound);
+}
+
+/// Adds the clauses of `sum <= bound` by sorting networks, one for every bit, whose inputs are
+/// the literals of the terms with the bit in their coefficients and the carries of the bit below:
+/// every second output of its network, which counts the pairs of its true inputs.
+/// The bit of the sum is the parity of the true inputs, which is true if an output is true
+/// and the next one is not.
+fn sorters<S: ClauseSink>(sink: &mut S, terms: &[(i64, u64)], bound: u64) {
+    let mut buckets: Vec<Vec<i64>> = vec![];
+    for &(x, a) in terms {
+        for bit in 0..64 {
+            if a >> bit & 1 == 1 {
+                if buckets.len() <= bit {
+                    buckets.resize(bit + 1, vec![]);
+                }
+                buckets[bit].push(x);
+            }
+        }
+    }
+    let mut bits: Vec<Option<i64>> = vec![];
+    let mut carries = vec![];
+    let mut i = 0;
+    while i < buckets.len() || !carries.is_empty() {
+        let mut literals = std::mem::take(&mut carries);
+        literals.extend(buckets.get(i).into_iter().flatten());
+        if literals.len() <= 1 {
+            bits.push(literals.pop());
+            i += 1;
+            continue;
+        }
+        let outputs = sorting_network(sink, &literals, literals.len());
+        let parity = sink.new_variable();
+        for k in (0..outputs.len()).step_by(2) {
+            match outputs.get(k + 1) {
+                Some(&y) => sink.add_clause(&[-outputs[k], y, parity]),
+                None => sink.add_clause(&[-outputs[k], parity]),
+            }
+        }
+        bits.push(Some(parity));
+        carries = outputs.iter().skip(1).step_by(2).cloned().collect();
+        i += 1;
+    }
+    forbid_above(sink, &bits, bound);
+}
+
+/// Adds the clauses that the binary number of `bits` from the lowest one, where `None` is false,
+/// is at most `bound`. A bit needs to be true whenever the number has it.
+fn forbid_above<S: ClauseSink>(sink: &mut S, bits: &[Option<i64>], bound: u64) {
+    // the sum is larger than the bound if a bit of it is set where the bound is not,
+    // and it has every bit above that one that the bound has
+    for i in 0..bits.len() {
+        let x = match bits[i] {
+            Some(x) if bound >> i & 1 == 0 => x,
+            _ => continue,
+        };
+        let mut clause = vec![-x];
+        let mut satisfied = false;
+        for (j, y) in bits.iter().enumerate().skip(i + 1) {
+            if bound >> j & 1 == 1 {
+                match y {
+                    Some(y) => clause.push(-y),
+                    None => satisfied = true,
+                }
+            }
+        }
+        if !satisfied {
+            sink.add_clause(&clause);
+        }
+    }
+}
+
+#[test]
+fn test_parse_opb() {
+    let s = "* #variable= 4 #constraint= 3\nmin: +1 x1 -2 x2 ;\n+1 x1 +1 ~x2 >= 1 ;\n3 x3 -1 x4 = 2;\n+2 x1 +1 x3 <= 2 ;\n";
+    let problem = PbProblem::new_from_opb(s);
+    assert_eq!(problem.n_variables(), 4);
+    assert_eq!(problem.objective(), Some(&[(1, 1), (-2, 2)][..]));
+    assert_eq!(
+        problem.constraints()[0],
+        PbConstraint {
+            terms: vec![(1, 1), (1, -2)],
+            relation: Relation::GreaterEqual,
+            rhs: 1
+        }
+    );
+    assert_eq!(problem.constraints()[1].relation, Relation::Equal);
+    assert_eq!(problem.constraints()[2].relation, Relation::LessEqual);
+}
+
+#[test]
+fn test_pb_encodings() {
+    use crate::encodings::count_inputs;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    let encodings = [
+        ClausalEncoding::Bdd,
+        ClausalEncoding::Adder,
+        ClausalEncoding::GeneralizedTotalizer,
+        ClausalEncoding::SortingNetwork,
+    ];
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..40 {
+        let n = rng.gen_range(1, 7);
+        let terms: Vec<(i64, i64)> = (1..=n as i64)
+            .map(|x| (rng.gen_range(-5, 8), if rng.gen() { x } else { -x }))
+            .collect();
+        let relation =
+            [Relation::GreaterEqual, Relation::Equal, Relation::LessEqual][rng.gen_range(0, 3)];
+        let constraint = PbConstraint {
+            terms,
+            relation,
+            rhs: rng.gen_range(-6, 12),
+        };
+        let expected = (0..1u32 << n)
+            .filter(|&bits| {
+                let lhs: i64 = constraint
+                    .terms
+                    .iter()
+                    .filter(|x| (bits >> (x.1.abs() - 1) & 1 == 1) == (x.1 > 0))
+                    .map(|x| x.0)
+                    .sum();
+                match relation {
+                    Relation::GreaterEqual => lhs >= constraint.rhs,
+                    Relation::Equal => lhs == constraint.rhs,
+                    Relation::LessEqual => lhs <= constraint.rhs,
+                }
+            })
+            .count();
+        for &encoding in encodings.iter() {
+            let mut problem = SatProblem::new(n);
+            add_constraint(&mut problem, &constraint, encoding);
+            assert_eq!(
+                count_inputs(&problem, n),
+                expected,
+                "{:?} {:?}",
+                encoding,
+                constraint
+            );
+        }
+    }
+}
+
+#[test]
+fn test_sorting_networks_of_large_coefficients() {
+    use crate::encodings::count_inputs;
+    // the networks are of the bits of the coefficients, not of 2999 copies of the literals
+    let terms = [(1, 1000), (2, 999), (3, 1000)];
+    let mut problem = SatProblem::new(3);
+    at_most(&mut problem, &terms, 1999, ClausalEncoding::SortingNetwork);
+    assert!(problem.n_variables() < 300);
+    // not x1 and x3
+    assert_eq!(count_inputs(&problem, 3), 6);
+    let mut problem = SatProblem::new(3);
+    at_most(&mut problem, &terms, 1998, ClausalEncoding::SortingNetwork);
+    assert_eq!(count_inputs(&problem, 3), 4);
+}
+
+#[test]
+fn test_pb_optimize() {
+    // choose 2 of 4 items with the least cost
+    let s = "min: +3 x1 +1 x2 +4 x3 +2 x4 ;\n+1 x1 +1 x2 +1 x3 +1 x4 >= 2 ;\n";
+    let problem = PbProblem::new_from_opb(s);
+    for &encoding in [ClausalEncoding::Bdd, ClausalEncoding::Adder].iter() {
+        let encoding = PbEncoding::Clauses(encoding);
+        let mut values = vec![];
+        match problem.solve(encoding, |x| values.push(x)) {
+            PbResult::Optimum { value, model } => {
+                assert_eq!(value, 3);
+                assert!(problem.check_assignment(&model));
+                assert_eq!(problem.value(&model), 3);
+            }
+            x => panic!("{:?}", x),
+        }
+        assert_eq!(values.last(), Some(&3));
+    }
+    let problem = PbProblem::new_from_opb("+1 x1 +1 x2 >= 3 ;\n");
+    match problem.solve(
+        PbEncoding::Clauses(ClausalEncoding::GeneralizedTotalizer),
+        |_| {},
+    ) {
+        PbResult::Unsatisfiable => {}
+        x => panic!("{:?}", x),
+    }
+}
+
+#[test]
+fn test_native_pb_constraints() {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..100 {
+        let n = rng.gen_range(1, 9);
+        let mut problem = PbProblem::new(n);
+        for _ in 0..rng.gen_range(1, 4) {
+            let terms: Vec<(i64, i64)> = (1..=n as i64)
+                .filter_map(|x| match rng.gen_range(0, 3) {
+                    0 => None,
+                    k => Some((rng.gen_range(-5, 8), if k == 1 { x } else { -x })),
+                })
+                .collect();
+            let relation =
+                [Relation::GreaterEqual, Relation::Equal, Relation::LessEqual][rng.gen_range(0, 3)];
+            problem.add_constraint(&terms, relation, rng.gen_range(-4, 10));
+        }
+        let expected = (0..1u32 << n)
+            .filter(|&bits| {
+                problem.constraints().iter().all(|constraint| {
+                    let lhs: i64 = constraint
+                        .terms
+                        .iter()
+                        .filter(|x| (bits >> (x.1.abs() - 1) & 1 == 1) == (x.1 > 0))
+                        .map(|x| x.0)
+                        .sum();
+                    match constraint.relation {
+                        Relation::GreaterEqual => lhs >= constraint.rhs,
+                        Relation::Equal => lhs == constraint.rhs,
+                        Relation::LessEqual => lhs <= constraint.rhs,
+                    }
+                })
+            })
+            .count();
+        let mut solver = SatSolver::new(&SatProblem::new(n));
+        for constraint in problem.constraints() {
+            solver.add_pb_constraint(constraint, PbEncoding::Native);
+        }
+        assert_eq!(solver.enumerate().count(), expected, "{:?}", problem);
+    }
+}
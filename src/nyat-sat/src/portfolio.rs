@@ -0,0 +1,171 @@
+use crate::sat::{SatAssignments, SatProblem, SatSolver};
+use crate::stats::Stats;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+/// learnt clauses of at most this length are shared
+const MAX_SHARED_LENGTH: usize = 8;
+/// learnt clauses of at most this LBD are shared
+const MAX_SHARED_LBD: usize = 2;
+
+/// Portfolio of solvers of the same problem in threads, which differ in the order of the decisions,
+/// their phases and the restart interval, and share their short learnt clauses and the ones of low LBD.
+/// Every solver sends its clauses to every other one through a channel, which never blocks the sender,
+/// and receives them at its restarts. The first answer wins, and the other solvers are stopped.
+pub struct Portfolio {
+    n_threads: usize,
+    seed: u64,
+    /// number of learnt clauses sent to the others
+    n_exported: AtomicUsize,
+    /// number of clauses received from the others
+    n_imported: AtomicUsize,
+}
+
+impl Portfolio {
+    pub fn new(n_threads: usize) -> Portfolio {
+        assert!(n_threads > 0);
+        Portfolio {
+            n_threads,
+            seed: 0,
+            n_exported: AtomicUsize::new(0),
+            n_imported: AtomicUsize::new(0),
+        }
+    }
+    /// Sets the seed of the configurations of the solvers besides the first one.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+    pub fn n_exported(&self) -> usize {
+        self.n_exported.load(Ordering::Relaxed)
+    }
+    pub fn n_imported(&self) -> usize {
+        self.n_imported.load(Ordering::Relaxed)
+    }
+    /// Returns the answer of the first solver that finishes, with its statistics.
+    pub fn solve(&self, problem: &SatProblem) -> (Option<SatAssignments>, Stats) {
+        let stop = AtomicBool::new(false);
+        let mut senders = vec![];
+        let mut receivers = vec![];
+        for _ in 0..self.n_threads {
+            let (sender, receiver) = mpsc::channel::<Vec<i64>>();
+            senders.push(sender);
+            receivers.push(receiver);
+        }
+        let results: Vec<Option<(Option<SatAssignments>, Stats)>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = receivers
+                .into_iter()
+                .enumerate()
+                .map(|(i, receiver)| {
+                    let others: Vec<mpsc::Sender<Vec<i64>>> = senders
+                        .iter()
+                        .enumerate()
+                        .filter(|&(k, _)| k != i)
+                        .map(|(_, x)| x.clone())
+                        .collect();
+                    let stop = &stop;
+                    scope.spawn(move || {
+                        let mut solver = SatSolver::new(problem);
+                        self.configure(&mut solver, i);
+                        solver.set_clause_sharing(
+                            move |clause, lbd| {
+                                if clause.len() <= MAX_SHARED_LENGTH || lbd <= MAX_SHARED_LBD {
+                                    self.n_exported.fetch_add(1, Ordering::Relaxed);
+                                    for other in &others {
+                                        // the receiver is gone after its solver finished
+                                        let _ = other.send(clause.to_vec());
+                                    }
+                                }
+                            },
+                            move || {
+                                let clauses: Vec<Vec<i64>> = receiver.try_iter().collect();
+                                self.n_imported.fetch_add(clauses.len(), Ordering::Relaxed);
+                                clauses
+                            },
+                        );
+                        solver.set_terminate(move || stop.load(Ordering::Relaxed));
+                        let res = solver.solve();
+                        if solver.interrupted() {
+                            return None;
+                        }
+                        // only the first answer is taken, and it stops the others
+                        match stop.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                        {
+                            Ok(_) => {
+                                info!("solver {} won", i);
+                                Some((res, solver.stats().clone()))
+                            }
+                            Err(_) => None,
+                        }
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|x| x.join().unwrap()).collect()
+        });
+        results.into_iter().flatten().next().unwrap()
+    }
+    /// Configures the solver of the thread `i`, where the first one keeps the defaults.
+    fn configure(&self, solver: &mut SatSolver, i: usize) {
+        if i == 0 {
+            return;
+        }
+        solver.set_seed(self.seed.wrapping_add(i as u64));
+        solver.shuffle_decision_order();
+        if i % 2 == 1 {
+            solver.randomize_phases();
+        }
+        solver.set_restart_interval([100, 50, 200, 400][i % 4]);
+    }
+}
+
+#[test]
+fn test_portfolio_against_single_solver() {
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
+    for n_threads in 1..5 {
+        for _ in 0..20 {
+            // around the threshold, so that about half of them are unsatisfiable
+            let mut problem = SatProblem::new(40);
+            let mut ids: Vec<i64> = (1..=40).collect();
+            for _ in 0..170 {
+                ids.shuffle(&mut rng);
+                let clause: Vec<i64> = ids[..3]
+                    .iter()
+                    .map(|&x| if rng.gen() { x } else { -x })
+                    .collect();
+                problem.add_clause(&clause);
+            }
+            let expected = SatSolver::new(&problem).solve().is_some();
+            let (res, _) = Portfolio::new(n_threads).solve(&problem);
+            assert_eq!(res.is_some(), expected);
+            if let Some(model) = res {
+                assert!(problem.check_assignment(&model));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_portfolio_shares_clauses() {
+    // 8 pigeons in 7 holes, which takes several restarts
+    let var = |pigeon: usize, hole: usize| (pigeon * 7 + hole + 1) as i64;
+    let mut problem = SatProblem::new(56);
+    for pigeon in 0..8 {
+        let clause: Vec<i64> = (0..7).map(|hole| var(pigeon, hole)).collect();
+        problem.add_clause(&clause);
+    }
+    for hole in 0..7 {
+        for i in 0..8 {
+            for k in i + 1..8 {
+                problem.add_clause(&[-var(i, hole), -var(k, hole)]);
+            }
+        }
+    }
+    let portfolio = Portfolio::new(4);
+    let (res, stats) = portfolio.solve(&problem);
+    assert!(res.is_none());
+    assert!(stats.restarts > 0);
+    assert!(portfolio.n_exported() > 0);
+    assert!(portfolio.n_imported() > 0);
+}
@@ -0,0 +1,316 @@
+use crate::sat::SatProblem;
+use std::collections::HashMap;
+
+/// Step of a DRUP proof of unsatisfiability in DIMACS literals: a lemma implied by unit propagation
+/// from the clauses and the lemmas before it, or the deletion of one of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofStep<'p> {
+    Add(&'p [i64]),
+    Delete(&'p [i64]),
+}
+
+impl<'p> ProofStep<'p> {
+    /// Formats the step as a line of the DRUP text format.
+    pub fn to_drup(self) -> String {
+        let (mut res, literals) = match self {
+            ProofStep::Add(literals) => (String::new(), literals),
+            ProofStep::Delete(literals) => (String::from("d "), literals),
+        };
+        for &x in literals {
+            res.push_str(&format!("{} ", x));
+        }
+        res.push('0');
+        res
+    }
+}
+
+fn index(literal: i64) -> usize {
+    2 * (literal.unsigned_abs() as usize - 1) + if literal > 0 { 0 } else { 1 }
+}
+
+/// Forward checker of DRUP proofs, which checks every lemma by unit propagation over two watched
+/// literals from the clauses that are not deleted. The assignment implied by the unit clauses is kept,
+/// and a deleted unit clause stays assigned as in drat-trim.
+pub struct DrupChecker {
+    /// clauses and lemmas, whose first two literals are watched
+    clauses: Vec<Vec<i64>>,
+    deleted: Vec<bool>,
+    /// clauses of every literal, at `2 * id` for the positive one and `2 * id + 1` for the negative one
+    watches: Vec<Vec<usize>>,
+    /// clauses of every sorted list of literals, for the deletions
+    ids: HashMap<Vec<i64>, Vec<usize>>,
+    values: Vec<Option<bool>>,
+    trail: Vec<i64>,
+    /// literals of `trail` before it are propagated
+    head: usize,
+    /// whether unit propagation refutes the clauses
+    refuted: bool,
+}
+
+impl DrupChecker {
+    pub fn new(problem: &SatProblem) -> DrupChecker {
+        assert_eq!(
+            problem.xors().count(),
+            0,
+            "XOR constraints are not supported"
+        );
+        let n = problem.n_variables();
+        let mut res = DrupChecker {
+            clauses: vec![],
+            deleted: vec![],
+            watches: vec![vec![]; 2 * n],
+            ids: HashMap::new(),
+            values: vec![None; n],
+            trail: vec![],
+            head: 0,
+            refuted: false,
+        };
+        for clause in problem.clauses() {
+            res.push_clause(&clause);
+        }
+        res
+    }
+    /// Returns whether unit propagation refutes the clauses, as it does after the empty clause.
+    pub fn is_refuted(&self) -> bool {
+        self.refuted
+    }
+    fn reserve_variables(&mut self, n_variables: usize) {
+        while self.values.len() < n_variables {
+            self.values.push(None);
+            self.watches.push(vec![]);
+            self.watches.push(vec![]);
+        }
+    }
+    fn value(&self, literal: i64) -> Option<bool> {
+        self.values[literal.unsigned_abs() as usize - 1].map(|x| x == (literal > 0))
+    }
+    fn assign(&mut self, literal: i64) {
+        self.values[literal.unsigned_abs() as usize - 1] = Some(literal > 0);
+        self.trail.push(literal);
+    }
+    /// Propagates the trail from `head`, and returns false on a conflict.
+    fn propagate(&mut self) -> bool {
+        while self.head < self.trail.len() {
+            let falsified = -self.trail[self.head];
+            self.head += 1;
+            let watching = std::mem::take(&mut self.watches[index(falsified)]);
+            let mut kept = vec![];
+            let mut conflict = false;
+            for (k, &i) in watching.iter().enumerate() {
+                if conflict {
+                    kept.extend_from_slice(&watching[k..]);
+                    break;
+                }
+                if self.deleted[i] {
+                    continue;
+                }
+                if self.clauses[i][0] == falsified {
+                    self.clauses[i].swap(0, 1);
+                }
+                let first = self.clauses[i][0];
+                if self.value(first) == Some(true) {
+                    kept.push(i);
+                    continue;
+                }
+                let clause = &self.clauses[i];
+                match (2..clause.len()).find(|&j| self.value(clause[j]) != Some(false)) {
+                    Some(j) => {
+                        self.clauses[i].swap(1, j);
+                        let watched = self.clauses[i][1];
+                        self.watches[index(watched)].push(i);
+                    }
+                    None => {
+                        kept.push(i);
+                        match self.value(first) {
+                            Some(false) => conflict = true,
+                            _ => self.assign(first),
+                        }
+                    }
+                }
+            }
+            self.watches[index(falsified)] = kept;
+            if conflict {
+                return false;
+            }
+        }
+        true
+    }
+    /// Adds a clause without checking it, and propagates it if it is unit under the kept assignment.
+    fn push_clause(&mut self, literals: &[i64]) {
+        let mut clause = literals.to_vec();
+        clause.sort_unstable();
+        clause.dedup();
+        if let Some(&x) = clause.iter().max_by_key(|x| x.unsigned_abs()) {
+            self.reserve_variables(x.unsigned_abs() as usize);
+        }
+        let i = self.clauses.len();
+        self.ids.entry(clause.clone()).or_default().push(i);
+        // the literals that are not false come first
+        clause.sort_by_key(|&x| self.value(x) == Some(false));
+        let n_not_false = clause
+            .iter()
+            .filter(|&&x| self.value(x) != Some(false))
+            .count();
+        if n_not_false == 0 {
+            self.refuted = true;
+        } else if n_not_false == 1 && self.value(clause[0]).is_none() {
+            self.assign(clause[0]);
+            if !self.propagate() {
+                self.refuted = true;
+            }
+        }
+        if clause.len() >= 2 {
+            self.watches[index(clause[0])].push(i);
+            self.watches[index(clause[1])].push(i);
+        }
+        self.clauses.push(clause);
+        self.deleted.push(false);
+    }
+    /// Returns whether the lemma is implied by unit propagation, and adds it if it is.
+    pub fn add(&mut self, lemma: &[i64]) -> bool {
+        if self.refuted {
+            return true;
+        }
+        if let Some(&x) = lemma.iter().max_by_key(|x| x.unsigned_abs()) {
+            self.reserve_variables(x.unsigned_abs() as usize);
+        }
+        let n_assigned = self.trail.len();
+        let mut implied = false;
+        for &x in lemma {
+            match self.value(x) {
+                Some(true) => {
+                    implied = true;
+                    break;
+                }
+                Some(false) => {}
+                None => self.assign(-x),
+            }
+        }
+        if !implied {
+            implied = !self.propagate();
+        }
+        for x in self.trail.drain(n_assigned..) {
+            self.values[x.unsigned_abs() as usize - 1] = None;
+        }
+        self.head = n_assigned;
+        if implied {
+            self.push_clause(lemma);
+        }
+        implied
+    }
+    /// Deletes a clause or a lemma with the same literals, unless it is a unit clause.
+    pub fn delete(&mut self, literals: &[i64]) {
+        let mut clause = literals.to_vec();
+        clause.sort_unstable();
+        clause.dedup();
+        if clause.len() <= 1 {
+            return;
+        }
+        if let Some(i) = self.ids.get_mut(&clause).and_then(|ids| ids.pop()) {
+            self.deleted[i] = true;
+        }
+    }
+}
+
+/// Checks a DRUP proof in the text format with `d` lines of deletions, and returns whether every
+/// lemma is implied by unit propagation and the clauses are refuted at the end.
+pub fn check_drup(problem: &SatProblem, proof: &str) -> bool {
+    let mut checker = DrupChecker::new(problem);
+    for line in proof.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+        let (delete, line) = match line.strip_prefix('d') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let mut literals: Vec<i64> = line
+            .split_whitespace()
+            .map(|x| x.parse::<i64>().unwrap())
+            .collect();
+        assert_eq!(literals.pop(), Some(0), "a step ends with 0");
+        if delete {
+            checker.delete(&literals);
+        } else if !checker.add(&literals) {
+            return false;
+        }
+    }
+    checker.is_refuted()
+}
+
+#[test]
+fn test_check_drup() {
+    // every assignment of x1 and x2 falsifies a clause
+    let problem = SatProblem::new_from_dimacs("p cnf 2 4\n1 2 0\n1 -2 0\n-1 2 0\n-1 -2 0\n");
+    assert!(check_drup(&problem, "1 0\n0\n"));
+    // the empty clause is implied after the unit
+    assert!(check_drup(&problem, "1 0\n"));
+    assert!(!check_drup(&problem, ""));
+    // x1 is implied, but x3 is not
+    assert!(!check_drup(&problem, "3 0\n0\n"));
+    // a lemma is not implied after the deletion of the clause it needs
+    assert!(!check_drup(&problem, "d 1 -2 0\n1 0\n"));
+    assert_eq!(ProofStep::Delete(&[1, -2]).to_drup(), "d 1 -2 0");
+}
+
+#[test]
+fn test_solver_proofs() {
+    use crate::sat::SatSolver;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut problems = vec![];
+    for _ in 0..50 {
+        // above the threshold, so that most of them are unsatisfiable
+        let mut problem = SatProblem::new(40);
+        let mut ids: Vec<i64> = (1..=40).collect();
+        for _ in 0..200 {
+            ids.shuffle(&mut rng);
+            let clause: Vec<i64> = ids[..3]
+                .iter()
+                .map(|&x| if rng.gen() { x } else { -x })
+                .collect();
+            problem.add_clause(&clause);
+        }
+        problems.push(problem);
+    }
+    // 8 pigeons in 7 holes, whose learnt clauses are deleted
+    let mut problem = SatProblem::new(56);
+    for pigeon in 0..8 {
+        let clause: Vec<i64> = (0..7).map(|hole| pigeon * 7 + hole + 1).collect();
+        problem.add_clause(&clause);
+    }
+    for hole in 0..7 {
+        for i in 0..8 {
+            for k in i + 1..8 {
+                problem.add_clause(&[-(i * 7 + hole + 1), -(k * 7 + hole + 1)]);
+            }
+        }
+    }
+    problems.push(problem);
+    let mut n_unsat = 0;
+    let mut n_deleted = 0;
+    for problem in &problems {
+        let mut proof = String::new();
+        let res = {
+            let mut solver = SatSolver::new(problem);
+            solver.set_proof(|step| {
+                if let ProofStep::Delete(_) = step {
+                    n_deleted += 1;
+                }
+                proof.push_str(&step.to_drup());
+                proof.push('\n');
+            });
+            solver.solve()
+        };
+        if res.is_none() {
+            n_unsat += 1;
+            assert!(proof.ends_with("\n0\n"));
+            assert!(check_drup(problem, &proof));
+        }
+    }
+    assert!(n_unsat > 25);
+    assert!(n_deleted > 0);
+}
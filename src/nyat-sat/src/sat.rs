@@ -1,3 +1,12 @@
+use crate::proof::ProofStep;
+use crate::stats::{Phase, Stats, Timer};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// default conflicts between restarts, multiplied by the Luby sequence
+const RESTART_INTERVAL: usize = 100;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 struct Literal {
     id: usize,
@@ -8,13 +17,28 @@ impl Literal {
     fn new(id: usize, sign: bool) -> Literal {
         Literal { id, sign }
     }
+    fn new_from_dimacs(x: i64) -> Literal {
+        assert!(x != 0);
+        if x > 0 {
+            Literal::new(x as usize - 1, true)
+        } else {
+            Literal::new(-x as usize - 1, false)
+        }
+    }
     fn id(&self) -> usize {
         self.id
     }
     fn sign(&self) -> bool {
         self.sign
     }
-    fn to_dimacs(&self) -> String {
+    fn to_i64(self) -> i64 {
+        if self.sign {
+            self.id as i64 + 1
+        } else {
+            -(self.id as i64 + 1)
+        }
+    }
+    fn to_dimacs(self) -> String {
         format!(
             "{}",
             if self.sign {
@@ -60,37 +84,26 @@ impl Clause {
         }
         res
     }
-    fn resolution(left: &Clause, right: &Clause) -> Option<Clause> {
-        let mut left_valids = vec![true; left.0.len()];
-        let mut right_valids = vec![true; right.0.len()];
-        let mut succeeded = false;
-        for i in 0..left.0.len() {
-            for k in 0..right.len() {
-                if left[i].id() == right[k].id() && left[i].sign() != right[k].sign() {
-                    left_valids[i] = false;
-                    right_valids[k] = false;
-                    succeeded = true;
-                } else if left[i].id() == right[k].id() {
-                    right_valids[k] = false;
-                }
-            }
+    /// Resolves two clauses on the variable `pivot`.
+    /// Resolving on every clashing variable at once would not be sound, so the other variables are only merged.
+    fn resolution(left: &Clause, right: &Clause, pivot: usize) -> Option<Clause> {
+        let left_i = left.get_index(pivot)?;
+        let right_i = right.get_index(pivot)?;
+        if left[left_i].sign() == right[right_i].sign() {
+            return None;
         }
-        if succeeded {
-            let mut res = Clause::new();
-            for i in 0..left.0.len() {
-                if left_valids[i] {
-                    res.push(left[i]);
-                }
+        let mut res = Clause::new();
+        for (i, &literal) in left.iter().enumerate() {
+            if i != left_i {
+                res.push(literal);
             }
-            for k in 0..right.0.len() {
-                if right_valids[k] {
-                    res.push(right[k]);
-                }
+        }
+        for (k, &literal) in right.iter().enumerate() {
+            if k != right_i && !res.contains(&literal) {
+                res.push(literal);
             }
-            Some(res)
-        } else {
-            None
         }
+        Some(res)
     }
 }
 
@@ -134,6 +147,7 @@ impl Clauses {
     fn new() -> Clauses {
         Clauses(vec![])
     }
+    #[cfg(test)]
     fn new_from_vec(xs: Vec<Clause>) -> Clauses {
         Clauses(xs)
     }
@@ -143,7 +157,7 @@ impl Clauses {
     fn num(&self) -> usize {
         self.0.len()
     }
-    fn iter(&self) -> std::slice::Iter<Clause> {
+    fn iter(&self) -> std::slice::Iter<'_, Clause> {
         self.0.iter()
     }
 }
@@ -187,54 +201,151 @@ impl<I: SliceIndex<[Clause]>> IndexMut<I> for Clauses {
 pub struct SatProblem {
     n_variables: usize,
     clauses: Clauses,
+    /// XOR constraints, each of which has an odd number of true literals
+    xors: Vec<Vec<Literal>>,
 }
 
 impl SatProblem {
-    pub fn new_from_dimacs(s: &str) -> SatProblem {
-        let s2 = {
-            let mut res = String::new();
-            for line in s.lines() {
-                let t = line.to_string();
-                if t.chars().take(1).next() == Some('c') {
-                    continue;
-                }
-                res.push_str(line);
-                res.push('\n');
+    pub fn new(n_variables: usize) -> SatProblem {
+        SatProblem {
+            n_variables,
+            clauses: Clauses::new(),
+            xors: vec![],
+        }
+    }
+    pub fn n_variables(&self) -> usize {
+        self.n_variables
+    }
+    pub fn n_clauses(&self) -> usize {
+        self.clauses.num()
+    }
+    /// Returns the clauses as DIMACS literals.
+    pub fn clauses(&self) -> impl Iterator<Item = Vec<i64>> + '_ {
+        self.clauses.iter().map(|clause| {
+            clause
+                .iter()
+                .map(|x| if x.sign() { 1 } else { -1 } * (x.id() as i64 + 1))
+                .collect()
+        })
+    }
+    /// Returns the XOR constraints as DIMACS literals.
+    pub fn xors(&self) -> impl Iterator<Item = Vec<i64>> + '_ {
+        self.xors
+            .iter()
+            .map(|xor| xor.iter().map(|x| x.to_i64()).collect())
+    }
+    /// Returns a fresh DIMACS variable, for the auxiliary variables of an encoding.
+    pub fn new_variable(&mut self) -> i64 {
+        self.n_variables += 1;
+        self.n_variables as i64
+    }
+    /// Adds a clause of DIMACS literals, extending the variables if needed.
+    pub fn add_clause(&mut self, literals: &[i64]) {
+        let clause: Vec<Literal> = literals
+            .iter()
+            .map(|&x| Literal::new_from_dimacs(x))
+            .collect();
+        for literal in &clause {
+            self.n_variables = self.n_variables.max(literal.id() + 1);
+        }
+        self.clauses.push(Clause::new_from_vec(clause));
+    }
+    /// Adds an XOR constraint: an odd number of the DIMACS literals are true.
+    pub fn add_xor(&mut self, literals: &[i64]) {
+        let xor: Vec<Literal> = literals
+            .iter()
+            .map(|&x| Literal::new_from_dimacs(x))
+            .collect();
+        for literal in &xor {
+            self.n_variables = self.n_variables.max(literal.id() + 1);
+        }
+        self.xors.push(xor);
+    }
+    /// Returns the problem with the XOR constraints expanded into clauses, for the algorithms
+    /// on clauses only. A long constraint is split by fresh variables, each of which is the sum of
+    /// the literals it replaces, so the models of the problem extend uniquely to them.
+    pub fn expand_xors(&self) -> SatProblem {
+        let mut res = SatProblem::new(self.n_variables);
+        for clause in self.clauses() {
+            res.add_clause(&clause);
+        }
+        for mut xor in self.xors() {
+            while xor.len() > 4 {
+                let y = res.new_variable();
+                let mut head: Vec<i64> = xor.drain(..3).collect();
+                head.push(-y);
+                res.add_xor_clauses(&head);
+                xor.push(y);
+            }
+            res.add_xor_clauses(&xor);
+        }
+        res
+    }
+    /// Adds a clause against every assignment of the DIMACS literals with an even number of true ones.
+    fn add_xor_clauses(&mut self, literals: &[i64]) {
+        for bits in 0..1u32 << literals.len() {
+            if bits.count_ones() % 2 == 0 {
+                let clause: Vec<i64> = (0..literals.len())
+                    .map(|i| {
+                        if bits >> i & 1 == 1 {
+                            -literals[i]
+                        } else {
+                            literals[i]
+                        }
+                    })
+                    .collect();
+                self.add_clause(&clause);
             }
-            res
+        }
+    }
+    /// Parses DIMACS CNF, where a line of the form `x1 -2 3 0` is an XOR constraint
+    /// as in CryptoMiniSat, and counts as a clause in the header.
+    /// Parses DIMACS, which panics if it is malformed.
+    pub fn new_from_dimacs(s: &str) -> SatProblem {
+        SatProblem::try_from_dimacs(s).unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Parses DIMACS, which returns an error if it is malformed.
+    pub fn try_from_dimacs(s: &str) -> Result<SatProblem, String> {
+        let mut iter = content_lines(s);
+        let header: Vec<&str> = iter.next().unwrap_or("").split_whitespace().collect();
+        let (n_variables, n_clauses) = match header[..] {
+            ["p", "cnf", n, m] => match (n.parse::<usize>(), m.parse::<usize>()) {
+                (Ok(n), Ok(m)) => (n, m),
+                _ => return Err(format!("malformed header: {}", header.join(" "))),
+            },
+            _ => return Err(format!("malformed header: {}", header.join(" "))),
         };
-        let mut iter = s2.split_whitespace();
-        assert_eq!(iter.next(), Some("p"));
-        assert_eq!(iter.next(), Some("cnf"));
-        let n_variables = iter.next().unwrap().parse::<usize>().unwrap();
-        let n_clauses = iter.next().unwrap().parse::<usize>().unwrap();
         let mut clauses = Clauses::new();
-        let mut xs = vec![];
-        for t in iter {
-            let u = t.parse::<i64>().unwrap();
-            if u == 0 {
-                clauses.push(Clause(xs.clone()));
-                xs.clear();
-            } else if u > 0 {
-                xs.push(Literal::new(u as usize - 1, true));
-            } else if u < 0 {
-                xs.push(Literal::new(-u as usize - 1, false));
-            } else {
-                unreachable!();
+        let mut xors = vec![];
+        let literals = |xs: Vec<i64>| -> Vec<Literal> {
+            xs.into_iter().map(Literal::new_from_dimacs).collect()
+        };
+        for line in parse_lines(iter)? {
+            match line {
+                IcnfLine::Clause(xs) => clauses.push(Clause(literals(xs))),
+                IcnfLine::Xor(xs) => xors.push(literals(xs)),
+                IcnfLine::Assumptions(_) => return Err("assumptions in DIMACS".to_string()),
             }
         }
-        assert_eq!(clauses.num(), n_clauses);
-        SatProblem {
+        if clauses.num() + xors.len() != n_clauses {
+            return Err(format!(
+                "{} clauses, but the header has {}",
+                clauses.num() + xors.len(),
+                n_clauses
+            ));
+        }
+        Ok(SatProblem {
             n_variables,
             clauses,
-        }
+            xors,
+        })
     }
     pub fn to_dimacs(&self) -> String {
         let mut res = String::new();
         res.push_str(&format!(
             "p cnf {} {}\n",
             self.n_variables,
-            self.clauses.num()
+            self.clauses.num() + self.xors.len()
         ));
         for clause in &self.clauses {
             for &literal in clause.iter() {
@@ -244,18 +355,27 @@ impl SatProblem {
             }
             res.push_str("0\n");
         }
+        for xor in &self.xors {
+            res.push('x');
+            for &literal in xor {
+                res.push_str(&format!("{} ", literal.to_i64()));
+            }
+            res.push_str("0\n");
+        }
         res
     }
+    /// Generates clauses of `k_sat` literals satisfied by a planted model, where every literal
+    /// but the first one agrees with it with the probability `prob_true`, by a generator with `seed`.
     pub fn gen_random_sat(
         n_variables: usize,
         n_clauses: usize,
         k_sat: usize,
         prob_true: f64,
+        seed: u64,
     ) -> SatProblem {
-        use rand::distributions::Uniform;
-        use rand::prelude::*;
+        use rand::distributions::{Distribution, Uniform};
         let mut assignments = vec![false; n_variables];
-        let mut rng = rand::thread_rng();
+        let mut rng = StdRng::seed_from_u64(seed);
         for assignment in assignments.iter_mut() {
             *assignment = rng.gen::<bool>();
         }
@@ -270,7 +390,7 @@ impl SatProblem {
                         continue 'l1;
                     }
                 }
-                let sign = if clause.len() == 0 || rng.gen::<f64>() < prob_true {
+                let sign = if clause.is_empty() || rng.gen::<f64>() < prob_true {
                     assignments[id]
                 } else {
                     !assignments[id]
@@ -282,9 +402,10 @@ impl SatProblem {
         SatProblem {
             n_variables,
             clauses,
+            xors: vec![],
         }
     }
-    fn check_assingemnt(&self, assignment: &SatAssignments) -> bool {
+    pub fn check_assignment(&self, assignment: &SatAssignments) -> bool {
         for clause in &self.clauses {
             let mut tf = false;
             for &x in &clause.0 {
@@ -297,21 +418,93 @@ impl SatProblem {
                 return false;
             }
         }
-        true
+        self.xors.iter().all(|xor| {
+            xor.iter()
+                .filter(|x| assignment[x.id()] == x.sign())
+                .count()
+                % 2
+                == 1
+        })
+    }
+}
+
+/// Line of DIMACS or iCNF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IcnfLine {
+    Clause(Vec<i64>),
+    /// XOR constraint, prefixed by `x`
+    Xor(Vec<i64>),
+    /// assumptions of an incremental call, prefixed by `a`
+    Assumptions(Vec<i64>),
+}
+
+/// Returns the lines of DIMACS, iCNF or GCNF without the comment lines and the blank ones.
+pub fn content_lines(s: &str) -> impl Iterator<Item = &str> {
+    s.lines()
+        .filter(|x| !x.trim().is_empty() && !x.starts_with('c'))
+}
+
+/// Parses iCNF: a `p inccnf` header followed by clauses, XOR constraints and `a` lines of assumptions,
+/// each of which is solved under the clauses before it.
+pub fn parse_icnf(s: &str) -> Vec<IcnfLine> {
+    let mut iter = content_lines(s);
+    assert_eq!(
+        iter.next()
+            .map(|x| x.split_whitespace().collect::<Vec<_>>()),
+        Some(vec!["p", "inccnf"])
+    );
+    parse_lines(iter).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Parses the content lines after the header of DIMACS or iCNF, where a line may span several lines until its 0.
+fn parse_lines<'s, I: Iterator<Item = &'s str>>(lines: I) -> Result<Vec<IcnfLine>, String> {
+    let mut res = vec![];
+    let mut xs = vec![];
+    // prefix of the current line
+    let mut prefix = None;
+    for t in lines.flat_map(|x| x.split_whitespace()) {
+        let t = match t.strip_prefix(|c| c == 'x' || c == 'a') {
+            Some(u) => {
+                if !xs.is_empty() || prefix.is_some() {
+                    return Err(format!("{} in the middle of a line", t));
+                }
+                prefix = t.chars().next();
+                if u.is_empty() {
+                    continue;
+                }
+                u
+            }
+            None => t,
+        };
+        let u = t
+            .parse::<i64>()
+            .map_err(|_| format!("malformed literal: {}", t))?;
+        if u != 0 {
+            xs.push(u);
+            continue;
+        }
+        let xs = std::mem::take(&mut xs);
+        res.push(match prefix.take() {
+            Some('x') => IcnfLine::Xor(xs),
+            Some('a') => IcnfLine::Assumptions(xs),
+            _ => IcnfLine::Clause(xs),
+        });
     }
+    Ok(res)
 }
 
 struct TaggedClause {
     clause: Clause,
-    learnt: bool,
+    /// LBD of a learnt clause when it was learnt
+    lbd: Option<usize>,
     watched: [Literal; 2],
 }
 
 impl TaggedClause {
-    fn new(clause: Clause, learnt: bool, watched: [Literal; 2]) -> TaggedClause {
+    fn new(clause: Clause, lbd: Option<usize>, watched: [Literal; 2]) -> TaggedClause {
         TaggedClause {
             clause,
-            learnt,
+            lbd,
             watched,
         }
     }
@@ -319,7 +512,10 @@ impl TaggedClause {
         &self.clause
     }
     fn learnt(&self) -> bool {
-        self.learnt
+        self.lbd.is_some()
+    }
+    fn lbd(&self) -> Option<usize> {
+        self.lbd
     }
     fn watched(&self) -> &[Literal; 2] {
         &self.watched
@@ -327,16 +523,16 @@ impl TaggedClause {
     fn watched_mut(&mut self) -> &mut [Literal; 2] {
         &mut self.watched
     }
-    fn to_dimacs(&self) -> String {
-        self.clause.to_dimacs()
-    }
 }
 
 #[derive(Debug)]
 enum AssignmentState {
     First,
-    Second,
+    /// The flipped decision, with the clause falsified by its first sign that implies it.
+    Second(Clause),
     Propageted(usize),
+    /// Propagated by a native constraint, with the clause that explains it.
+    Implied(Clause),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -350,10 +546,7 @@ impl VariableState {
         VariableState::NotAssigned
     }
     fn is_not_assigned(&self) -> bool {
-        match self {
-            VariableState::NotAssigned => true,
-            _ => false,
-        }
+        matches!(self, VariableState::NotAssigned)
     }
     fn sign(&self) -> Option<bool> {
         match self {
@@ -369,36 +562,766 @@ impl VariableState {
     }
 }
 
+/// Assignment made by `propagate` and kept until the next call,
+/// which only propagates the literals that do not start the last ones.
+struct Propagation {
+    /// literals of the last call that are assigned with their implications
+    literals: Vec<Literal>,
+    /// assigned variables in order
+    trail: Vec<usize>,
+    /// length of the trail before every literal
+    starts: Vec<usize>,
+    /// number of literals before the assignment of every variable
+    levels: Vec<usize>,
+    /// clause of every propagated variable, whose other literals are false
+    reasons: Vec<Option<Clause>>,
+    /// number of clauses checked, since the later ones may propagate under the kept assignment
+    n_clauses: usize,
+}
+
+/// Native constraint: the sum of the coefficients of its true literals is at most `bound`.
+/// Every coefficient is at most `bound`, and every variable occurs at most once.
+struct NativeConstraint {
+    /// terms by decreasing coefficients
+    terms: Vec<(Literal, u64)>,
+    bound: u64,
+    /// sum of the coefficients of the true literals, which every assignment updates
+    sum: u64,
+}
+
+/// XOR constraints as the rows of a matrix over their variables, where a row is the sum of
+/// its columns modulo 2. The matrix is kept in reduced row echelon form over the unassigned
+/// columns: every row has a basic column, which occurs in no other row and stays unassigned
+/// until the whole row is assigned, and watches another unassigned column. A row without such
+/// a column implies its basic column, which propagates the constraints completely.
+/// An assignment only touches the row of its column and the rows that watch it.
+struct XorMatrix {
+    /// variable of every column
+    variables: Vec<usize>,
+    rows: Vec<XorRow>,
+    /// value of every column
+    values: Vec<Option<bool>>,
+    /// row of every basic column
+    basic_rows: Vec<Option<usize>>,
+    /// rows that watch every column
+    watchers: Vec<Vec<usize>>,
+    /// assigned columns in order, with the rows that lost their watch at the assignment
+    trail: Vec<(usize, Vec<usize>)>,
+    /// columns implied since the last call of `propagate`, with the clauses that explain them
+    implied: Vec<(usize, Literal, Clause)>,
+    /// clause of a row whose columns are all assigned with the wrong parity
+    conflict: Option<Clause>,
+    /// whether the rows sum to 0 = 1
+    contradiction: bool,
+    /// whether rows have been added since the last elimination
+    dirty: bool,
+}
+
+struct XorRow {
+    /// bits of the columns
+    bits: Vec<u64>,
+    parity: bool,
+    basic: usize,
+    /// unassigned column other than the basic one, or `None` if the row implies its basic column
+    watch: Option<usize>,
+}
+
+fn has_column(bits: &[u64], column: usize) -> bool {
+    bits.get(column / 64).map_or(0, |x| x >> (column % 64) & 1) == 1
+}
+
+fn columns(bits: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    bits.iter().enumerate().flat_map(|(i, &word)| {
+        let mut word = word;
+        std::iter::from_fn(move || {
+            if word == 0 {
+                return None;
+            }
+            let column = i * 64 + word.trailing_zeros() as usize;
+            word &= word - 1;
+            Some(column)
+        })
+    })
+}
+
+impl XorMatrix {
+    fn new() -> XorMatrix {
+        XorMatrix {
+            variables: vec![],
+            rows: vec![],
+            values: vec![],
+            basic_rows: vec![],
+            watchers: vec![],
+            trail: vec![],
+            implied: vec![],
+            conflict: None,
+            contradiction: false,
+            dirty: false,
+        }
+    }
+    fn is_empty(&self) -> bool {
+        self.rows.is_empty() && !self.contradiction
+    }
+    /// Returns the column of a new variable.
+    fn push_column(&mut self, id: usize) -> usize {
+        self.variables.push(id);
+        self.values.push(None);
+        self.basic_rows.push(None);
+        self.watchers.push(vec![]);
+        self.variables.len() - 1
+    }
+    /// Adds a row, which is eliminated by the next `reset`.
+    fn push_row(&mut self, bits: Vec<u64>, parity: bool) {
+        self.rows.push(XorRow {
+            bits,
+            parity,
+            basic: 0,
+            watch: None,
+        });
+        self.dirty = true;
+    }
+    /// Unassigns every column, eliminates the added rows and queues the implications of the rows
+    /// with a single column.
+    fn reset(&mut self) {
+        let n_columns = self.variables.len();
+        self.values = vec![None; n_columns];
+        self.basic_rows = vec![None; n_columns];
+        self.watchers = vec![vec![]; n_columns];
+        self.trail.clear();
+        self.implied.clear();
+        self.conflict = None;
+        if self.dirty {
+            self.dirty = false;
+            self.eliminate();
+        }
+        for r in 0..self.rows.len() {
+            self.rows[r].watch = None;
+            self.basic_rows[self.rows[r].basic] = Some(r);
+            if !self.rewatch(r) {
+                self.imply(r);
+            }
+        }
+        if self.contradiction {
+            self.conflict = Some(Clause::new());
+        }
+    }
+    /// Gauss-Jordan elimination of the rows, which drops the zero rows.
+    fn eliminate(&mut self) {
+        let n_columns = self.variables.len();
+        let n_words = n_columns.div_ceil(64);
+        for row in self.rows.iter_mut() {
+            row.bits.resize(n_words, 0);
+        }
+        let mut n_pivots = 0;
+        for column in 0..n_columns {
+            let pivot = match (n_pivots..self.rows.len())
+                .find(|&i| has_column(&self.rows[i].bits, column))
+            {
+                Some(i) => i,
+                None => continue,
+            };
+            self.rows.swap(n_pivots, pivot);
+            self.rows[n_pivots].basic = column;
+            self.pivot(n_pivots, column);
+            n_pivots += 1;
+        }
+        self.contradiction |= self.rows[n_pivots..].iter().any(|x| x.parity);
+        self.rows.truncate(n_pivots);
+    }
+    /// Adds the row `r` to every other row with the column, and returns the changed rows.
+    fn pivot(&mut self, r: usize, column: usize) -> Vec<usize> {
+        let bits = self.rows[r].bits.clone();
+        let parity = self.rows[r].parity;
+        let mut changed = vec![];
+        for (i, row) in self.rows.iter_mut().enumerate() {
+            if i != r && has_column(&row.bits, column) {
+                for (x, y) in row.bits.iter_mut().zip(&bits) {
+                    *x ^= y;
+                }
+                row.parity ^= parity;
+                changed.push(i);
+            }
+        }
+        changed
+    }
+    /// Moves the watch of the row `r` to an unassigned column other than the basic one,
+    /// and returns false if there is none.
+    fn rewatch(&mut self, r: usize) -> bool {
+        if let Some(column) = self.rows[r].watch.take() {
+            let k = self.watchers[column].iter().position(|&x| x == r).unwrap();
+            self.watchers[column].swap_remove(k);
+        }
+        let row = &self.rows[r];
+        let watch = columns(&row.bits).find(|&x| x != row.basic && self.values[x].is_none());
+        match watch {
+            Some(column) => {
+                self.rows[r].watch = Some(column);
+                self.watchers[column].push(r);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Returns the parity of the unassigned columns of the row `r`, with a clause that falsifies
+    /// its assigned columns.
+    fn explain(&self, r: usize) -> (bool, Clause) {
+        let mut clause = Clause::new();
+        let mut rest = self.rows[r].parity;
+        for column in columns(&self.rows[r].bits) {
+            if let Some(sign) = self.values[column] {
+                rest ^= sign;
+                clause.push(Literal::new(self.variables[column], !sign));
+            }
+        }
+        (rest, clause)
+    }
+    /// Queues the implication of the basic column of the row `r`, whose other columns are assigned.
+    fn imply(&mut self, r: usize) {
+        let basic = self.rows[r].basic;
+        let (rest, clause) = self.explain(r);
+        let literal = Literal::new(self.variables[basic], rest);
+        let mut reason = Clause::new_from_vec(vec![literal]);
+        for &x in clause.iter() {
+            reason.push(x);
+        }
+        self.implied.push((basic, literal, reason));
+    }
+    fn assign(&mut self, column: usize, sign: bool) {
+        self.values[column] = Some(sign);
+        if self.dirty {
+            return;
+        }
+        // rows without a watch, which are restored by `unassign`
+        let mut lost = vec![];
+        if let Some(r) = self.basic_rows[column] {
+            // another unassigned column of the row becomes basic, other than the watch if possible
+            let row = &self.rows[r];
+            let unassigned = |x: &usize| self.values[*x].is_none();
+            let basic = columns(&row.bits)
+                .filter(unassigned)
+                .find(|&x| Some(x) != row.watch)
+                .or(row.watch);
+            match basic {
+                Some(basic) => {
+                    self.basic_rows[column] = None;
+                    self.basic_rows[basic] = Some(r);
+                    self.rows[r].basic = basic;
+                    for i in self.pivot(r, basic) {
+                        // the watch cancels out if it is a column of the row `r`
+                        if let Some(watch) = self.rows[i].watch {
+                            if !has_column(&self.rows[i].bits, watch) && !self.rewatch(i) {
+                                self.imply(i);
+                                lost.push(i);
+                            }
+                        }
+                    }
+                    if self.rows[r].watch == Some(basic) && !self.rewatch(r) {
+                        self.imply(r);
+                        lost.push(r);
+                    }
+                }
+                None => {
+                    let (rest, clause) = self.explain(r);
+                    if rest {
+                        self.conflict = Some(clause);
+                    }
+                }
+            }
+        }
+        for r in std::mem::take(&mut self.watchers[column]) {
+            self.rows[r].watch = None;
+            if !self.rewatch(r) {
+                self.imply(r);
+                lost.push(r);
+            }
+        }
+        self.trail.push((column, lost));
+    }
+    /// Unassigns the last assigned column.
+    fn unassign(&mut self, column: usize) {
+        self.values[column] = None;
+        if self.dirty {
+            return;
+        }
+        let (last, lost) = self.trail.pop().unwrap();
+        debug_assert_eq!(last, column);
+        for r in lost {
+            self.rows[r].watch = Some(column);
+            self.watchers[column].push(r);
+        }
+        self.implied.clear();
+        self.conflict = None;
+    }
+    /// Returns the literals implied since the last call, with the clauses that explain them,
+    /// or a clause whose literals are all false if a row has the wrong parity.
+    /// An explanation falsifies the assigned columns of a row, which is implied by the constraints.
+    fn propagate(&mut self) -> Result<Vec<(Literal, Clause)>, Clause> {
+        let implied = std::mem::take(&mut self.implied);
+        if let Some(clause) = self.conflict.take() {
+            return Err(clause);
+        }
+        let mut res = vec![];
+        for (column, literal, reason) in implied {
+            match self.values[column] {
+                None => res.push((literal, reason)),
+                Some(sign) if sign == literal.sign() => {}
+                Some(_) => return Err(reason),
+            }
+        }
+        Ok(res)
+    }
+}
+
+struct Progress<'a> {
+    interval: usize,
+    next: usize,
+    callback: Box<dyn FnMut(&Stats) + Send + 'a>,
+}
+
+type Export<'a> = Box<dyn FnMut(&[i64], usize) + Send + 'a>;
+type Import<'a> = Box<dyn FnMut() -> Vec<Vec<i64>> + Send + 'a>;
+
+/// Exchange of learnt clauses with other solvers of the same problem.
+struct Sharing<'a> {
+    /// called with every learnt clause and its LBD
+    export: Export<'a>,
+    /// returns the clauses learnt by the others, at every restart
+    import: Import<'a>,
+}
+
+/// DRUP proof of the search, which is passed to a callback step by step.
+struct Proof<'a> {
+    callback: Box<dyn FnMut(ProofStep) + Send + 'a>,
+    /// whether every variable assigned at level 0 has been added as a unit lemma
+    units: Vec<bool>,
+}
+
 pub struct SatSolver<'a> {
-    problem: &'a SatProblem,
     clauses: Vec<TaggedClause>,
     variables: Vec<VariableState>,
     watch: Vec<Vec<usize>>,
+    /// clauses of every variable, for the propagation of `propagate` without watches
+    clause_occurrences: Vec<Vec<usize>>,
+    /// number of clauses in `clause_occurrences`, which are appended until learnt clauses are deleted
+    n_indexed_clauses: usize,
+    propagation: Option<Propagation>,
+    natives: Vec<NativeConstraint>,
+    /// native constraints of every variable with the index of its term
+    native_occurrences: Vec<Vec<(usize, usize)>>,
+    xors: XorMatrix,
+    /// column of every variable in the XOR constraints
+    xor_columns: Vec<Option<usize>>,
+    /// saved phase of every variable, which its decisions take first
+    phases: Vec<Option<bool>>,
+    /// variables in the order of the decisions
+    order: Vec<usize>,
+    /// index of every variable in `order`
+    positions: Vec<usize>,
+    /// conflicts between restarts, multiplied by the Luby sequence
+    restart_interval: usize,
     dpll_stack: Vec<(usize, AssignmentState)>,
     decision_level: usize,
-    conflict_count: usize,
+    /// learnt clauses kept at a restart besides a third of the original clauses
+    max_learnt_clauses: usize,
+    assumptions: Vec<Literal>,
+    /// failed assumptions of the last call, or `None` unless it was unsatisfiable
+    failed: Option<Vec<Literal>>,
+    /// clause false at level 0 with which the search refuted the assumptions
+    final_conflict: Option<Clause>,
+    has_empty_clause: bool,
+    stats: Stats,
+    progress: Option<Progress<'a>>,
+    sharing: Option<Sharing<'a>>,
+    /// called at every conflict, and the search stops when it returns true
+    terminate: Option<Box<dyn FnMut() -> bool + Send + 'a>>,
+    /// whether the last call was stopped by `terminate`
+    interrupted: bool,
+    /// generator of the randomized heuristics, so that the same seed gives the same search
+    rng: StdRng,
+    proof: Option<Proof<'a>>,
 }
 
 impl<'a> SatSolver<'a> {
-    pub fn new(problem: &'a SatProblem) -> SatSolver {
-        let clauses: Vec<TaggedClause> = problem
-            .clauses
-            .iter()
-            .map(|x| TaggedClause::new(x.clone(), false, [x[0], x[0]]))
-            .collect();
-        SatSolver {
-            problem,
-            clauses,
+    pub fn new(problem: &SatProblem) -> SatSolver<'a> {
+        let mut solver = SatSolver {
+            clauses: vec![],
             variables: vec![VariableState::new(); problem.n_variables],
             watch: vec![vec![]; problem.n_variables],
+            clause_occurrences: vec![vec![]; problem.n_variables],
+            n_indexed_clauses: 0,
+            propagation: None,
+            natives: vec![],
+            native_occurrences: vec![vec![]; problem.n_variables],
+            xors: XorMatrix::new(),
+            xor_columns: vec![None; problem.n_variables],
+            phases: vec![None; problem.n_variables],
+            order: (0..problem.n_variables).collect(),
+            positions: (0..problem.n_variables).collect(),
+            restart_interval: RESTART_INTERVAL,
             dpll_stack: vec![],
             decision_level: 0,
-            conflict_count: 0,
+            max_learnt_clauses: 100,
+            assumptions: vec![],
+            failed: None,
+            final_conflict: None,
+            has_empty_clause: false,
+            stats: Stats::new(),
+            progress: None,
+            sharing: None,
+            terminate: None,
+            interrupted: false,
+            rng: StdRng::seed_from_u64(0),
+            proof: None,
+        };
+        for clause in problem.clauses.iter() {
+            solver.push_clause(clause.iter().cloned());
+        }
+        for xor in &problem.xors {
+            solver.push_xor(xor);
+        }
+        solver
+    }
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+    pub fn set_progress<F: FnMut(&Stats) + Send + 'a>(&mut self, interval: usize, f: F) {
+        assert!(interval > 0);
+        self.progress = Some(Progress {
+            interval,
+            next: self.stats.conflicts + interval,
+            callback: Box::new(f),
+        });
+    }
+    /// Exchanges learnt clauses with other solvers of the same clauses: `export` is called with
+    /// every learnt clause of DIMACS literals and its LBD, and the clauses that `import` returns
+    /// are added as learnt clauses at every restart.
+    pub fn set_clause_sharing<E, I>(&mut self, export: E, import: I)
+    where
+        E: FnMut(&[i64], usize) + Send + 'a,
+        I: FnMut() -> Vec<Vec<i64>> + Send + 'a,
+    {
+        self.sharing = Some(Sharing {
+            export: Box::new(export),
+            import: Box::new(import),
+        });
+    }
+    /// Sets a callback that is called at every conflict, and stops the search when it returns true.
+    /// The stopped call returns `None` as if it were unsatisfiable, and `interrupted` tells them apart.
+    pub fn set_terminate<F: FnMut() -> bool + Send + 'a>(&mut self, f: F) {
+        self.terminate = Some(Box::new(f));
+    }
+    /// Sets a callback that is called with every step of a DRUP proof, which ends with the empty clause
+    /// when a call without assumptions is unsatisfiable. The proof only covers clauses, and not native,
+    /// XOR or shared clauses, and the clauses added after the first call are taken as lemmas.
+    pub fn set_proof<F: FnMut(ProofStep) + Send + 'a>(&mut self, f: F) {
+        self.proof = Some(Proof {
+            callback: Box::new(f),
+            units: vec![],
+        });
+    }
+    /// Passes a lemma or a deletion to the callback of the proof, if there is one.
+    fn prove(&mut self, literals: &[Literal], delete: bool) {
+        if let Some(proof) = self.proof.as_mut() {
+            let literals: Vec<i64> = literals.iter().map(|x| x.to_i64()).collect();
+            (proof.callback)(if delete {
+                ProofStep::Delete(&literals)
+            } else {
+                ProofStep::Add(&literals)
+            });
+        }
+    }
+    /// Adds the variables assigned at level 0 to the proof as unit lemmas, before the clauses
+    /// that imply them are deleted. The assumptions are assigned at level 0 too, so it needs none.
+    fn prove_units(&mut self) {
+        let proof = match self.proof.as_mut() {
+            Some(proof) if self.assumptions.is_empty() => proof,
+            _ => return,
+        };
+        proof.units.resize(self.variables.len(), false);
+        for (id, variable) in self.variables.iter().enumerate() {
+            if let (Some(sign), false) = (variable.sign(), proof.units[id]) {
+                proof.units[id] = true;
+                let literal = Literal::new(id, sign).to_i64();
+                (proof.callback)(ProofStep::Add(&[literal]));
+            }
+        }
+    }
+    /// Returns whether the last call was stopped by the callback of `set_terminate`.
+    pub fn interrupted(&self) -> bool {
+        self.interrupted
+    }
+    /// Sets the conflicts between restarts, which is multiplied by the Luby sequence.
+    pub fn set_restart_interval(&mut self, interval: usize) {
+        assert!(interval > 0);
+        self.restart_interval = interval;
+    }
+    /// Sets the order of the decisions by a permutation of the variables from 1,
+    /// where the variables after them come in their order.
+    pub fn set_decision_order(&mut self, order: &[i64]) {
+        self.reserve_variables(order.len());
+        let mut seen = vec![false; self.n_variables()];
+        let mut res = vec![];
+        for &x in order {
+            let id = x as usize - 1;
+            assert!(!seen[id], "variable {} twice in the order", x);
+            seen[id] = true;
+            res.push(id);
+        }
+        res.extend((0..self.n_variables()).filter(|&id| !seen[id]));
+        for (i, &id) in res.iter().enumerate() {
+            self.positions[id] = i;
+        }
+        self.order = res;
+    }
+    /// Seeds the generator of the randomized heuristics, which is seeded by 0 by default.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+    /// Shuffles the order of the decisions by the generator of the seed.
+    pub fn shuffle_decision_order(&mut self) {
+        let mut order: Vec<i64> = (1..=self.n_variables() as i64).collect();
+        order.shuffle(&mut self.rng);
+        self.set_decision_order(&order);
+    }
+    /// Saves random phases of all the variables by the generator of the seed.
+    pub fn randomize_phases(&mut self) {
+        let phases: Vec<bool> = (0..self.n_variables()).map(|_| self.rng.gen()).collect();
+        self.set_phases(&phases);
+    }
+    pub fn n_variables(&self) -> usize {
+        self.variables.len()
+    }
+    fn reserve_variables(&mut self, n_variables: usize) {
+        while self.variables.len() < n_variables {
+            self.variables.push(VariableState::new());
+            self.watch.push(vec![]);
+            self.clause_occurrences.push(vec![]);
+            self.native_occurrences.push(vec![]);
+            self.xor_columns.push(None);
+            self.phases.push(None);
+            self.positions.push(self.order.len());
+            self.order.push(self.variables.len() - 1);
+        }
+    }
+    /// Returns a fresh DIMACS variable, for the auxiliary variables of an encoding.
+    pub fn new_variable(&mut self) -> i64 {
+        let id = self.n_variables();
+        self.reserve_variables(id + 1);
+        id as i64 + 1
+    }
+    /// Adds a clause of DIMACS literals, which is kept for all later calls of `solve`.
+    pub fn add_clause(&mut self, literals: &[i64]) {
+        self.push_clause(literals.iter().map(|&x| Literal::new_from_dimacs(x)));
+    }
+    fn push_clause<I: Iterator<Item = Literal>>(&mut self, literals: I) {
+        let mut clause = Clause::new();
+        for literal in literals {
+            self.reserve_variables(literal.id() + 1);
+            if clause.contains(&literal) {
+                continue;
+            }
+            if clause.iter().any(|&y| y.id() == literal.id()) {
+                // tautology
+                return;
+            }
+            clause.push(literal);
+        }
+        if clause.is_empty() {
+            self.has_empty_clause = true;
+            return;
+        }
+        let watched = [clause[0], clause[0]];
+        self.clauses.push(TaggedClause::new(clause, None, watched));
+    }
+    /// Adds a native constraint that the sum of the coefficients of the true DIMACS literals of `terms`
+    /// is at most `bound`. It adds no clauses or variables: the solver propagates it from its slack,
+    /// and explains every implication and conflict by a clause of the true literals that cause it.
+    pub fn add_native_at_most(&mut self, terms: &[(i64, u64)], bound: i64) {
+        use std::collections::BTreeMap;
+        // the coefficient of every variable, since `a ~x = a - a x`
+        let mut coefficients: BTreeMap<usize, i64> = BTreeMap::new();
+        let mut bound = bound;
+        for &(x, a) in terms {
+            let literal = Literal::new_from_dimacs(x);
+            self.reserve_variables(literal.id() + 1);
+            let a = a as i64;
+            let coefficient = coefficients.entry(literal.id()).or_insert(0);
+            if literal.sign() {
+                *coefficient += a;
+            } else {
+                *coefficient -= a;
+                bound -= a;
+            }
+        }
+        let mut native = NativeConstraint {
+            terms: vec![],
+            bound: 0,
+            sum: 0,
+        };
+        for (&id, &a) in &coefficients {
+            if a > 0 {
+                native.terms.push((Literal::new(id, true), a as u64));
+            } else if a < 0 {
+                native
+                    .terms
+                    .push((Literal::new(id, false), a.unsigned_abs()));
+                bound -= a;
+            }
+        }
+        if bound < 0 {
+            self.has_empty_clause = true;
+            return;
+        }
+        native.bound = bound as u64;
+        // a literal whose coefficient is larger than the bound is false
+        for &(literal, a) in &native.terms {
+            if a > native.bound {
+                self.push_clause(std::iter::once(Literal::new(literal.id(), !literal.sign())));
+            }
+        }
+        native.terms.retain(|x| x.1 <= bound as u64);
+        if native.terms.iter().map(|x| x.1).sum::<u64>() <= native.bound {
+            return;
+        }
+        native.terms.sort_by_key(|x| std::cmp::Reverse(x.1));
+        let native_id = self.natives.len();
+        for (k, &(literal, a)) in native.terms.iter().enumerate() {
+            self.native_occurrences[literal.id()].push((native_id, k));
+            if self.variables[literal.id()].sign() == Some(literal.sign()) {
+                native.sum += a;
+            }
+        }
+        self.natives.push(native);
+        // the kept assignment of `propagate` does not know the constraint
+        self.propagation = None;
+    }
+    /// Adds a native constraint that at most `k` of the DIMACS literals are true.
+    pub fn add_native_at_most_k(&mut self, literals: &[i64], k: usize) {
+        let terms: Vec<(i64, u64)> = literals.iter().map(|&x| (x, 1)).collect();
+        self.add_native_at_most(&terms, k as i64);
+    }
+    /// Propagates a native constraint under the current assignment. Returns the negations of the
+    /// unassigned literals whose coefficients exceed the slack, with the clauses that explain them,
+    /// or on a conflict a clause whose literals are all false. An explanation takes the true literal
+    /// `first` before the others, which come by decreasing coefficients, until their sum is large enough.
+    /// Only the terms whose coefficients exceed the slack are visited, and none while the slack is
+    /// at least the largest coefficient.
+    fn propagate_native(
+        &self,
+        native_id: usize,
+        first: Option<Literal>,
+    ) -> Result<Vec<(Literal, Clause)>, Clause> {
+        let native = &self.natives[native_id];
+        debug_assert_eq!(
+            native.sum,
+            native
+                .terms
+                .iter()
+                .filter(|x| self.variables[x.0.id()].sign() == Some(x.0.sign()))
+                .map(|x| x.1)
+                .sum::<u64>()
+        );
+        if native.sum <= native.bound && native.terms[0].1 <= native.bound - native.sum {
+            return Ok(vec![]);
+        }
+        // the negations of the first true literals whose sum exceeds `limit`
+        let explain = |mut clause: Clause, limit: u64| {
+            let mut trues: Vec<(Literal, u64)> = native
+                .terms
+                .iter()
+                .filter(|x| self.variables[x.0.id()].sign() == Some(x.0.sign()))
+                .cloned()
+                .collect();
+            // stable, so the others stay by decreasing coefficients
+            trues.sort_by_key(|&(x, _)| Some(x) != first);
+            let mut sum = 0;
+            for &(x, a) in &trues {
+                if sum > limit {
+                    break;
+                }
+                sum += a;
+                clause.push(Literal::new(x.id(), !x.sign()));
+            }
+            clause
+        };
+        if native.sum > native.bound {
+            return Err(explain(Clause::new(), native.bound));
+        }
+        let slack = native.bound - native.sum;
+        let mut res = vec![];
+        for &(x, a) in native.terms.iter().take_while(|x| x.1 > slack) {
+            if self.variables[x.id()].is_not_assigned() {
+                let negation = Literal::new(x.id(), !x.sign());
+                let clause = Clause::new_from_vec(vec![negation]);
+                res.push((negation, explain(clause, native.bound - a)));
+            }
+        }
+        Ok(res)
+    }
+    /// Adds an XOR constraint: an odd number of the DIMACS literals are true.
+    /// It is kept as a row of the eliminated XOR matrix rather than expanded into clauses.
+    pub fn add_xor(&mut self, literals: &[i64]) {
+        let xor: Vec<Literal> = literals
+            .iter()
+            .map(|&x| Literal::new_from_dimacs(x))
+            .collect();
+        self.push_xor(&xor);
+    }
+    fn push_xor(&mut self, xor: &[Literal]) {
+        let mut parity = true;
+        let mut ids = vec![];
+        for literal in xor {
+            self.reserve_variables(literal.id() + 1);
+            parity ^= !literal.sign();
+            // a variable twice cancels out
+            match ids.iter().position(|&x| x == literal.id()) {
+                Some(i) => {
+                    ids.swap_remove(i);
+                }
+                None => ids.push(literal.id()),
+            }
+        }
+        match ids.len() {
+            0 => {
+                if parity {
+                    self.has_empty_clause = true;
+                }
+                return;
+            }
+            1 => {
+                self.push_clause(std::iter::once(Literal::new(ids[0], parity)));
+                return;
+            }
+            _ => {}
+        }
+        let mut bits = vec![];
+        for id in ids {
+            let column = match self.xor_columns[id] {
+                Some(column) => column,
+                None => {
+                    let column = self.xors.push_column(id);
+                    self.xor_columns[id] = Some(column);
+                    column
+                }
+            };
+            bits.resize(bits.len().max(column / 64 + 1), 0);
+            bits[column / 64] |= 1 << (column % 64);
+        }
+        self.xors.push_row(bits, parity);
+        // the kept assignment of `propagate` does not know the constraint
+        self.propagation = None;
+    }
+    /// Saves the phases of the variables from 1, such as the best assignment of local search,
+    /// which the decisions take first instead of the signs from the occurrences.
+    pub fn set_phases(&mut self, phases: &[bool]) {
+        self.reserve_variables(phases.len());
+        for (phase, &x) in self.phases.iter_mut().zip(phases) {
+            *phase = Some(x);
         }
     }
     fn first_signs(&self) -> Vec<bool> {
-        let mut count = vec![0; self.problem.n_variables];
-        let mut total = vec![0; self.problem.n_variables];
+        let mut count = vec![0; self.n_variables()];
+        let mut total = vec![0; self.n_variables()];
         for clause in &self.clauses {
             for literal in clause.clause().iter() {
                 if literal.sign() {
@@ -408,8 +1331,10 @@ impl<'a> SatSolver<'a> {
             }
         }
         let mut res = vec![];
-        for i in 0..self.problem.n_variables {
-            if count[i] > total[i] / 2 {
+        for i in 0..self.n_variables() {
+            if let Some(phase) = self.phases[i] {
+                res.push(phase);
+            } else if count[i] > total[i] / 2 {
                 res.push(false);
             } else {
                 res.push(true);
@@ -418,6 +1343,7 @@ impl<'a> SatSolver<'a> {
         res
     }
     fn learn_clause(&mut self, clause: &Clause) {
+        self.prove(clause, false);
         let mut assigned_literals = vec![];
         let mut not_assigned_literals = vec![];
         for &literal in clause.iter() {
@@ -430,6 +1356,24 @@ impl<'a> SatSolver<'a> {
                 }
             }
         }
+        let lbd = {
+            let mut levels: Vec<usize> = assigned_literals.iter().map(|x| x.1).collect();
+            levels.sort();
+            levels.dedup();
+            levels.len()
+                + if not_assigned_literals.is_empty() {
+                    0
+                } else {
+                    1
+                }
+        };
+        self.stats.learnt_clauses += 1;
+        self.stats.learnt_literals += clause.len();
+        self.stats.add_lbd(lbd);
+        if let Some(sharing) = self.sharing.as_mut() {
+            let literals: Vec<i64> = clause.iter().map(|x| x.to_i64()).collect();
+            (sharing.export)(&literals, lbd);
+        }
         let clause_id = self.clauses.len();
         let (literal_1, literal_2) = if not_assigned_literals.len() >= 2 {
             let literal_1 = not_assigned_literals[0];
@@ -453,18 +1397,37 @@ impl<'a> SatSolver<'a> {
         };
         self.clauses.push(TaggedClause::new(
             clause.clone(),
-            true,
+            Some(lbd),
             [literal_1, literal_2],
         ));
         self.watch[literal_1.id()].push(clause_id);
         self.watch[literal_2.id()].push(clause_id);
     }
     pub fn assign_unit_clause(&mut self) -> bool {
+        for k in 0..self.assumptions.len() {
+            let literal = self.assumptions[k];
+            match self.variables[literal.id()].sign() {
+                Some(sign) => {
+                    if sign != literal.sign() {
+                        return false;
+                    }
+                }
+                None => {
+                    self.set_variable(
+                        literal.id(),
+                        VariableState::Assigned {
+                            sign: literal.sign(),
+                            decision_level: self.decision_level,
+                        },
+                    );
+                }
+            }
+        }
         loop {
             let mut updated = false;
-            'l1: for tagged_clause in &self.clauses {
+            'l1: for clause_id in 0..self.clauses.len() {
                 let mut unknowns = vec![];
-                for literal in tagged_clause.clause() {
+                for &literal in self.clauses[clause_id].clause() {
                     match self.variables[literal.id()].sign() {
                         Some(sign) => {
                             if sign == literal.sign() {
@@ -481,21 +1444,62 @@ impl<'a> SatSolver<'a> {
                 }
                 if unknowns.len() == 1 {
                     let literal = unknowns[0];
-                    self.variables[literal.id()] = VariableState::Assigned {
-                        sign: literal.sign(),
-                        decision_level: self.decision_level,
-                    };
+                    self.set_variable(
+                        literal.id(),
+                        VariableState::Assigned {
+                            sign: literal.sign(),
+                            decision_level: self.decision_level,
+                        },
+                    );
+                    self.stats.propagations += 1;
                     updated = true;
                 }
             }
+            for native_id in 0..self.natives.len() {
+                match self.propagate_native(native_id, None) {
+                    Ok(xs) => {
+                        for (literal, _) in xs {
+                            self.set_variable(
+                                literal.id(),
+                                VariableState::Assigned {
+                                    sign: literal.sign(),
+                                    decision_level: self.decision_level,
+                                },
+                            );
+                            self.stats.propagations += 1;
+                            updated = true;
+                        }
+                    }
+                    Err(_) => return false,
+                }
+            }
+            if !self.xors.is_empty() {
+                match self.xors.propagate() {
+                    Ok(xs) => {
+                        for (literal, _) in xs {
+                            self.set_variable(
+                                literal.id(),
+                                VariableState::Assigned {
+                                    sign: literal.sign(),
+                                    decision_level: self.decision_level,
+                                },
+                            );
+                            self.stats.propagations += 1;
+                            updated = true;
+                        }
+                    }
+                    Err(_) => return false,
+                }
+            }
             if !updated {
                 break;
             }
         }
         true
     }
+    /// Pushes the first unassigned variable from the position `i` of the order as a decision.
     fn try_next_assignment(&mut self, i: usize) -> bool {
-        for k in i..self.problem.n_variables {
+        for &k in &self.order[i..] {
             if self.variables[k].is_not_assigned() {
                 self.dpll_stack.push((k, AssignmentState::First));
                 self.decision_level += 1;
@@ -504,23 +1508,52 @@ impl<'a> SatSolver<'a> {
         }
         false
     }
-    fn try_backtrack(&mut self, clause_id: usize) -> bool {
-        self.conflict_count += 1;
-        // conflict
-        let mut clause = self.clauses[clause_id].clause().clone();
+    fn try_backtrack(&mut self, conflict: Clause) -> bool {
+        if let Some(terminate) = self.terminate.as_mut() {
+            if terminate() {
+                self.interrupted = true;
+                return false;
+            }
+        }
+        let start = Timer::start();
+        let res = self.analyze_conflict(conflict);
+        self.stats.add_time(Phase::Analysis, start.elapsed());
+        if let Some(progress) = self.progress.as_mut() {
+            if self.stats.conflicts >= progress.next {
+                progress.next += progress.interval;
+                (progress.callback)(&self.stats);
+            }
+        }
+        res
+    }
+    fn analyze_conflict(&mut self, conflict: Clause) -> bool {
+        self.stats.conflicts += 1;
+        let mut clause = conflict;
         while let Some((k, state)) = self.dpll_stack.pop() {
             match state {
                 AssignmentState::First => {
-                    self.dpll_stack.push((k, AssignmentState::Second));
+                    // the reason of the second branch is a lemma as well
+                    self.prove(&clause, false);
+                    self.dpll_stack.push((k, AssignmentState::Second(clause)));
                     return true;
                 }
-                AssignmentState::Second => {
-                    self.variables[k] = VariableState::NotAssigned;
+                AssignmentState::Second(reason) => {
+                    self.set_variable(k, VariableState::NotAssigned);
                     self.decision_level -= 1;
+                    // the clause stays false at the remaining levels, so that the final conflict
+                    // analysis gets a clause that is false at level 0
+                    if clause.get_index(k).is_some() {
+                        clause = Clause::resolution(&clause, &reason, k).unwrap_or(reason);
+                    }
                 }
-                AssignmentState::Propageted(clause_id) => {
-                    self.variables[k] = VariableState::NotAssigned;
-                    let t = Clause::resolution(&clause, &self.clauses[clause_id].clause());
+                state @ (AssignmentState::Propageted(_) | AssignmentState::Implied(_)) => {
+                    self.set_variable(k, VariableState::NotAssigned);
+                    let reason = match &state {
+                        AssignmentState::Propageted(clause_id) => self.clauses[*clause_id].clause(),
+                        AssignmentState::Implied(reason) => reason,
+                        _ => unreachable!(),
+                    };
+                    let t = Clause::resolution(&clause, reason, k);
                     if let Some(new_clause) = t {
                         clause = new_clause;
                         let num_current_decision_level = {
@@ -570,30 +1603,40 @@ impl<'a> SatSolver<'a> {
                                             self.learn_clause(&clause);
                                             return true;
                                         } else {
-                                            self.variables[k] = VariableState::NotAssigned;
+                                            self.set_variable(k, VariableState::NotAssigned);
                                             self.decision_level -= 1;
                                         }
                                     }
-                                    AssignmentState::Second => {
+                                    AssignmentState::Second(reason) => {
                                         if self.decision_level <= second_decision_level {
-                                            self.dpll_stack.push((k, AssignmentState::Second));
+                                            self.dpll_stack
+                                                .push((k, AssignmentState::Second(reason)));
                                             self.learn_clause(&clause);
                                             return true;
                                         } else {
-                                            self.variables[k] = VariableState::NotAssigned;
+                                            self.set_variable(k, VariableState::NotAssigned);
                                             self.decision_level -= 1;
                                         }
                                     }
-                                    AssignmentState::Propageted(_) => {
-                                        self.variables[k] = VariableState::NotAssigned;
+                                    AssignmentState::Propageted(_)
+                                    | AssignmentState::Implied(_) => {
+                                        self.set_variable(k, VariableState::NotAssigned);
                                     }
                                 }
                             }
                             assert_eq!(second_decision_level, 0);
                             self.learn_clause(&clause);
-                            self.assign_unit_clause();
-                            let t = self.try_next_assignment(0);
-                            assert!(t);
+                            if !self.assign_unit_clause() {
+                                // UNSAT
+                                return false;
+                            }
+                            // the literals falsified at level 0 have not been visited, so they are unwatched again
+                            for watch in self.watch.iter_mut() {
+                                watch.clear();
+                            }
+                            self.init_watch();
+                            // an empty stack means that every variable is assigned
+                            self.try_next_assignment(0);
                             return true;
                         }
                     }
@@ -601,69 +1644,656 @@ impl<'a> SatSolver<'a> {
             }
         }
         // UNSAT
+        self.final_conflict = Some(clause);
         false
     }
     fn init_watch(&mut self) {
         for (clause_id, tagged_clause) in self.clauses.iter_mut().enumerate() {
             let clause = tagged_clause.clause();
             if clause.len() >= 2 {
-                let mut xs = vec![false; clause.len()];
-                for (i, literal) in clause.iter().enumerate().take(2) {
-                    xs[i] = true;
+                // literals falsified at level 0 are never visited, so they must not be watched
+                let variables = &self.variables;
+                let mut literals: Vec<Literal> = clause.iter().cloned().collect();
+                literals.sort_by_key(|x| variables[x.id()].sign() == Some(!x.sign()));
+                for literal in literals.iter().take(2) {
                     self.watch[literal.id()].push(clause_id);
                 }
-                *tagged_clause.watched_mut() = [clause[0], clause[1]];
+                *tagged_clause.watched_mut() = [literals[0], literals[1]];
             } else {
                 *tagged_clause.watched_mut() = [clause[0], clause[0]];
             }
         }
     }
-    pub fn solve(&mut self) -> Option<SatAssignments> {
-        let success = self.assign_unit_clause();
+    /// Changes the state of a variable, and updates the sums of its native constraints
+    /// and the XOR matrix.
+    fn set_variable(&mut self, id: usize, state: VariableState) {
+        let old = self.variables[id].sign();
+        self.variables[id] = state;
+        let new = state.sign();
+        if old == new {
+            return;
+        }
+        if let Some(column) = self.xor_columns[id] {
+            if old.is_some() {
+                self.xors.unassign(column);
+            }
+            if let Some(sign) = new {
+                self.xors.assign(column, sign);
+            }
+        }
+        for &(native_id, k) in &self.native_occurrences[id] {
+            let native = &mut self.natives[native_id];
+            let (literal, a) = native.terms[k];
+            if old == Some(literal.sign()) {
+                native.sum -= a;
+            }
+            if new == Some(literal.sign()) {
+                native.sum += a;
+            }
+        }
+    }
+    fn reset(&mut self) {
+        for variable in self.variables.iter_mut() {
+            *variable = VariableState::NotAssigned;
+        }
+        for native in self.natives.iter_mut() {
+            native.sum = 0;
+        }
+        self.xors.reset();
+        for watch in self.watch.iter_mut() {
+            watch.clear();
+        }
+        self.dpll_stack.clear();
+        self.decision_level = 0;
+        self.propagation = None;
+    }
+    fn check_assignment(&self, assignment: &SatAssignments) -> bool {
+        self.clauses
+            .iter()
+            .all(|x| x.clause().iter().any(|y| assignment[y.id()] == y.sign()))
+            && self.natives.iter().all(|x| {
+                let sum: u64 = x
+                    .terms
+                    .iter()
+                    .filter(|y| assignment[y.0.id()] == y.0.sign())
+                    .map(|y| y.1)
+                    .sum();
+                sum <= x.bound
+            })
+            && !self.xors.contradiction
+            && self.xors.rows.iter().all(|row| {
+                let n_true = columns(&row.bits)
+                    .filter(|&column| assignment[self.xors.variables[column]])
+                    .count();
+                (n_true % 2 == 1) == row.parity
+            })
+    }
+    fn current_assignment(&self) -> SatAssignments {
+        let xs: Vec<bool> = self.variables.iter().map(|&x| x.sign().unwrap()).collect();
+        let res = SatAssignments::new_from_vec(xs);
+        assert!(self.check_assignment(&res));
+        res
+    }
+    /// Shrinks a model to the literals on `projection` that are needed to satisfy every clause.
+    /// The variables outside `projection` keep their values from `assignment`,
+    /// and so do the variables of native and XOR constraints, which a lifted literal could violate.
+    pub fn lift_model(
+        &self,
+        assignment: &SatAssignments,
+        projection: &[usize],
+    ) -> SatPartialAssignments {
+        let clauses: Vec<&Clause> = self
+            .clauses
+            .iter()
+            .filter(|x| !x.learnt())
+            .map(|x| x.clause())
+            .collect();
+        let mut fixed = vec![true; self.n_variables()];
+        let mut res = assignment.project(projection);
+        let mut n_satisfied: Vec<usize> = clauses
+            .iter()
+            .map(|x| x.iter().filter(|y| assignment[y.id()] == y.sign()).count())
+            .collect();
+        for &id in projection {
+            if !fixed[id]
+                || !self.native_occurrences[id].is_empty()
+                || self.xor_columns[id].is_some()
+            {
+                continue;
+            }
+            let literal = Literal::new(id, assignment[id]);
+            let removable = clauses
+                .iter()
+                .zip(n_satisfied.iter())
+                .all(|(x, &n)| n >= 2 || !x.contains(&literal));
+            if !removable {
+                continue;
+            }
+            fixed[id] = false;
+            res[id] = None;
+            for (x, n) in clauses.iter().zip(n_satisfied.iter_mut()) {
+                if x.contains(&literal) {
+                    *n -= 1;
+                }
+            }
+        }
+        assert!(clauses.iter().all(|x| x
+            .iter()
+            .any(|y| fixed[y.id()] && assignment[y.id()] == y.sign())));
+        res
+    }
+    pub fn solve(&mut self) -> Option<SatAssignments> {
+        self.solve_with_assumptions(&[])
+    }
+    /// Returns a subset of the assumptions of the last call of `solve_with_assumptions`
+    /// that is unsatisfiable together with the clauses, or an empty vector if they were satisfiable.
+    /// It is found by the final conflict analysis of that call, so it is cheap but not always minimal.
+    pub fn failed_assumptions(&self) -> Vec<i64> {
+        match &self.failed {
+            Some(core) => core.iter().map(|x| x.to_i64()).collect(),
+            None => vec![],
+        }
+    }
+    /// Shrinks the failed assumptions of the last call by dropping them one by one while the rest
+    /// stays unsatisfiable, and returns the minimal core, which `failed_assumptions` returns afterwards.
+    /// Unlike `failed_assumptions` it is costly: it solves once more for every assumption of the core,
+    /// and these calls keep their learnt clauses and statistics like any other call.
+    pub fn minimize_failed_assumptions(&mut self) -> Vec<i64> {
+        let mut core = match &self.failed {
+            Some(core) => core.clone(),
+            None => return vec![],
+        };
+        let assumptions = std::mem::take(&mut self.assumptions);
+        let mut i = 0;
+        while i < core.len() {
+            let mut rest = core.clone();
+            rest.remove(i);
+            let literals: Vec<i64> = rest.iter().map(|x| x.to_i64()).collect();
+            if self.solve_with_assumptions(&literals).is_none() {
+                // the core of the rest may drop more assumptions
+                core = self.failed.take().unwrap();
+            } else {
+                i += 1;
+            }
+        }
+        self.assumptions = assumptions;
+        self.failed = Some(core.clone());
+        core.iter().map(|x| x.to_i64()).collect()
+    }
+    /// Final conflict analysis: returns the assumptions on which the conflict of their propagation depends.
+    /// If the propagation finds no conflict, the search refuted the assumptions by `final_conflict`,
+    /// whose literals are false after it.
+    fn analyze_final(&mut self) -> Vec<Literal> {
+        let final_conflict = self.final_conflict.take();
+        let assumptions = self.assumptions.clone();
+        match self.propagate_literals(&assumptions) {
+            Ok(_) => {
+                // the assignment at level 0 may depend on learnt clauses deleted since,
+                // so a conflict of the imported clauses with it is explained by all the assumptions
+                let conflict = match final_conflict {
+                    Some(conflict) => conflict,
+                    None => return assumptions,
+                };
+                let propagation = self.propagation.take().unwrap();
+                let res = self.trace_conflict(&propagation, conflict, &assumptions);
+                self.propagation = Some(propagation);
+                res
+            }
+            Err(core) => core,
+        }
+    }
+    /// Propagates the DIMACS literals at level 0 with every clause, without deciding.
+    /// Returns the true literals afterwards, or on a conflict the literals of `literals` on which it depends.
+    /// The model counter learns the negations of these literals.
+    pub fn propagate(&mut self, literals: &[i64]) -> Result<Vec<i64>, Vec<i64>> {
+        let literals: Vec<Literal> = literals
+            .iter()
+            .map(|&x| Literal::new_from_dimacs(x))
+            .collect();
+        for literal in &literals {
+            self.reserve_variables(literal.id() + 1);
+        }
+        let to_dimacs = |xs: Vec<Literal>| xs.iter().map(|x| x.to_i64()).collect();
+        match self.propagate_literals(&literals) {
+            Ok(xs) => Ok(to_dimacs(xs)),
+            Err(xs) => Err(to_dimacs(xs)),
+        }
+    }
+    /// Propagates the literals at level 0 with the reasons of the assignments, and returns the true literals,
+    /// or the literals on which the conflict depends, in their order.
+    /// The assignment is kept, so that the literals in common with the last call are not propagated again.
+    fn propagate_literals(&mut self, literals: &[Literal]) -> Result<Vec<Literal>, Vec<Literal>> {
+        if self.has_empty_clause {
+            return Err(vec![]);
+        }
+        for clause_id in self.n_indexed_clauses..self.clauses.len() {
+            for literal in self.clauses[clause_id].clause().iter() {
+                self.clause_occurrences[literal.id()].push(clause_id);
+            }
+        }
+        self.n_indexed_clauses = self.clauses.len();
+        let mut propagation = match self.propagation.take() {
+            Some(mut x) => {
+                let n_common = x
+                    .literals
+                    .iter()
+                    .zip(literals)
+                    .take_while(|(x, y)| x == y)
+                    .count();
+                self.backtrack_propagation(&mut x, n_common);
+                x
+            }
+            None => {
+                self.reset();
+                Propagation {
+                    literals: vec![],
+                    trail: vec![],
+                    starts: vec![],
+                    levels: vec![],
+                    reasons: vec![],
+                    n_clauses: 0,
+                }
+            }
+        };
+        propagation.levels.resize(self.n_variables(), 0);
+        propagation.reasons.resize(self.n_variables(), None);
+        // the clauses added since the kept assignment propagate from the level at which
+        // all their literals but one are false, so the literals after it are propagated again
+        let mut n_kept = propagation.literals.len();
+        for clause_id in propagation.n_clauses..self.clauses.len() {
+            let clause = self.clauses[clause_id].clause();
+            let level_of = |x: &Literal, value: bool| match self.variables[x.id()].sign() {
+                Some(sign) if (sign == x.sign()) == value => propagation.levels[x.id()],
+                _ => usize::MAX,
+            };
+            let mut false_levels: Vec<usize> = clause.iter().map(|x| level_of(x, false)).collect();
+            false_levels.sort_unstable();
+            let level = if clause.len() == 1 {
+                0
+            } else {
+                false_levels[clause.len() - 2]
+            };
+            if level != usize::MAX && clause.iter().all(|x| level_of(x, true) > level) {
+                n_kept = n_kept.min(level);
+            }
+        }
+        self.backtrack_propagation(&mut propagation, n_kept);
+        // the clauses added since the kept assignment, which are all of them at first
+        let mut propagations = vec![];
+        let mut conflict = None;
+        'l1: for clause_id in propagation.n_clauses..self.clauses.len() {
+            let clause = self.clauses[clause_id].clause();
+            let mut unknown = None;
+            for &literal in clause.iter() {
+                match self.variables[literal.id()].sign() {
+                    Some(sign) if sign == literal.sign() => continue 'l1,
+                    Some(_) => {}
+                    None if unknown.is_some() => continue 'l1,
+                    None => unknown = Some(literal),
+                }
+            }
+            match unknown {
+                None => conflict = Some(clause.clone()),
+                Some(literal) => propagations.push((literal, clause.clone())),
+            }
+        }
+        propagation.n_clauses = self.clauses.len();
+        if conflict.is_none() {
+            let head = propagation.trail.len();
+            conflict = self.propagate_trail(&mut propagation, propagations, head);
+        }
+        if let Some(clause) = conflict {
+            // the conflict does not depend on the new literals, and nothing is kept
+            let core = self.trace_conflict(&propagation, clause, &propagation.literals);
+            self.reset();
+            return Err(core);
+        }
+        for &literal in &literals[propagation.literals.len()..] {
+            let i = propagation.literals.len();
+            propagation.starts.push(propagation.trail.len());
+            propagation.literals.push(literal);
+            let conflict = match self.variables[literal.id()].sign() {
+                // the conflict is traced from the variable
+                Some(sign) if sign != literal.sign() => Some(Clause::new_from_vec(vec![literal])),
+                Some(_) => None,
+                None => {
+                    let head = propagation.trail.len();
+                    self.assign_propagated(&mut propagation, literal, None);
+                    self.propagate_trail(&mut propagation, vec![], head)
+                }
+            };
+            if let Some(clause) = conflict {
+                let mut core = self.trace_conflict(&propagation, clause, &literals[..=i]);
+                if !core.contains(&literal) {
+                    core.push(literal);
+                }
+                self.backtrack_propagation(&mut propagation, i);
+                self.propagation = Some(propagation);
+                return Err(core);
+            }
+        }
+        let res = propagation
+            .trail
+            .iter()
+            .map(|&id| Literal::new(id, self.variables[id].sign().unwrap()))
+            .collect();
+        self.propagation = Some(propagation);
+        Ok(res)
+    }
+    /// Unassigns the implications of the literals after the first `n_literals` ones.
+    fn backtrack_propagation(&mut self, propagation: &mut Propagation, n_literals: usize) {
+        if n_literals == propagation.literals.len() {
+            return;
+        }
+        let start = propagation.starts[n_literals];
+        // in reverse order, as the XOR constraints undo their assignments
+        for &id in propagation.trail[start..].iter().rev() {
+            self.set_variable(id, VariableState::NotAssigned);
+        }
+        propagation.trail.truncate(start);
+        propagation.starts.truncate(n_literals);
+        propagation.literals.truncate(n_literals);
+    }
+    fn assign_propagated(
+        &mut self,
+        propagation: &mut Propagation,
+        literal: Literal,
+        reason: Option<Clause>,
+    ) {
+        self.set_variable(
+            literal.id(),
+            VariableState::Assigned {
+                sign: literal.sign(),
+                decision_level: 0,
+            },
+        );
+        propagation.levels[literal.id()] = propagation.literals.len();
+        propagation.reasons[literal.id()] = reason;
+        propagation.trail.push(literal.id());
+    }
+    /// Assigns the propagations and the implications of the trail from `head` until a fixpoint,
+    /// or returns a clause whose literals are all false.
+    fn propagate_trail(
+        &mut self,
+        propagation: &mut Propagation,
+        mut propagations: Vec<(Literal, Clause)>,
+        mut head: usize,
+    ) -> Option<Clause> {
+        loop {
+            for (literal, clause) in propagations.drain(..) {
+                match self.variables[literal.id()].sign() {
+                    // every literal of the clause is false
+                    Some(sign) if sign != literal.sign() => return Some(clause),
+                    Some(_) => {}
+                    None => self.assign_propagated(propagation, literal, Some(clause)),
+                }
+            }
+            if head == propagation.trail.len() {
+                // the implications of the XOR constraints are taken at the fixpoint of the others
+                if self.xors.is_empty() {
+                    return None;
+                }
+                match self.xors.propagate() {
+                    Ok(xs) if xs.is_empty() => return None,
+                    Ok(xs) => propagations = xs,
+                    Err(clause) => return Some(clause),
+                }
+                continue;
+            }
+            let id = propagation.trail[head];
+            head += 1;
+            // the clauses are propagated at once, so that the next ones see their literals
+            'l1: for k in 0..self.clause_occurrences[id].len() {
+                let clause_id = self.clause_occurrences[id][k];
+                let mut unknown = None;
+                for &literal in self.clauses[clause_id].clause().iter() {
+                    match self.variables[literal.id()].sign() {
+                        Some(sign) if sign == literal.sign() => continue 'l1,
+                        Some(_) => {}
+                        None if unknown.is_some() => continue 'l1,
+                        None => unknown = Some(literal),
+                    }
+                }
+                let clause = self.clauses[clause_id].clause().clone();
+                match unknown {
+                    None => return Some(clause),
+                    Some(literal) => self.assign_propagated(propagation, literal, Some(clause)),
+                }
+            }
+            let literal = Literal::new(id, self.variables[id].sign().unwrap());
+            for k in 0..self.native_occurrences[id].len() {
+                let (native_id, term) = self.native_occurrences[id][k];
+                if self.natives[native_id].terms[term].0 != literal {
+                    continue;
+                }
+                match self.propagate_native(native_id, Some(literal)) {
+                    Ok(xs) => {
+                        for (x, clause) in xs {
+                            self.assign_propagated(propagation, x, Some(clause));
+                        }
+                    }
+                    Err(clause) => return Some(clause),
+                }
+            }
+        }
+    }
+    /// Returns the literals of `literals` without reasons on which the variables of the conflict depend.
+    fn trace_conflict(
+        &self,
+        propagation: &Propagation,
+        conflict: Clause,
+        literals: &[Literal],
+    ) -> Vec<Literal> {
+        let mut seen = vec![false; self.n_variables()];
+        let mut stack: Vec<usize> = conflict.iter().map(|x| x.id()).collect();
+        while let Some(id) = stack.pop() {
+            if seen[id] {
+                continue;
+            }
+            seen[id] = true;
+            if let Some(clause) = &propagation.reasons[id] {
+                stack.extend(clause.iter().map(|x| x.id()).filter(|&x| x != id));
+            }
+        }
+        let mut res: Vec<Literal> = vec![];
+        for &literal in literals {
+            let id = literal.id();
+            if seen[id] && propagation.reasons[id].is_none() && !res.contains(&literal) {
+                res.push(literal);
+            }
+        }
+        res
+    }
+    /// Solves under DIMACS literals assumed to be true only during this call.
+    pub fn solve_with_assumptions(&mut self, assumptions: &[i64]) -> Option<SatAssignments> {
+        self.interrupted = false;
+        let res = self.solve_assumed(assumptions);
+        if res.is_none() && !self.interrupted && assumptions.is_empty() {
+            if let Some(conflict) = self.final_conflict.clone() {
+                self.prove(&conflict, false);
+            }
+            self.prove(&[], false);
+        }
+        self.failed = if res.is_none() && !self.interrupted {
+            Some(self.analyze_final())
+        } else {
+            None
+        };
+        res
+    }
+    fn solve_assumed(&mut self, assumptions: &[i64]) -> Option<SatAssignments> {
+        if self.has_empty_clause {
+            return None;
+        }
+        self.reset();
+        self.assumptions = assumptions
+            .iter()
+            .map(|&x| Literal::new_from_dimacs(x))
+            .collect();
+        for literal in self.assumptions.clone() {
+            self.reserve_variables(literal.id() + 1);
+        }
+        let start = Timer::start();
+        let success = self.assign_unit_clause();
+        self.init_watch();
+        self.stats.add_time(Phase::Simplify, start.elapsed());
         if !success {
             // UNSAT
             return None;
         }
 
+        let start = Timer::start();
+        let res = self.search();
+        self.stats.add_time(Phase::Search, start.elapsed());
+        res
+    }
+    /// Backtracks to level 0, deletes learnt clauses and imports the shared ones.
+    /// Returns false if the imported clauses are unsatisfiable at level 0.
+    fn restart(&mut self) -> bool {
+        self.stats.restarts += 1;
+        while let Some((k, _)) = self.dpll_stack.pop() {
+            self.set_variable(k, VariableState::NotAssigned);
+        }
+        self.decision_level = 0;
+        self.reduce_learnt_clauses();
+        if !self.import_clauses() {
+            return false;
+        }
+        for watch in self.watch.iter_mut() {
+            watch.clear();
+        }
         self.init_watch();
+        true
+    }
+    /// Adds the clauses learnt by the other solvers, whose LBD is taken as their length,
+    /// and propagates them at level 0. Returns false on a conflict or an empty clause,
+    /// and skips the tautologies.
+    fn import_clauses(&mut self) -> bool {
+        let clauses = match self.sharing.as_mut() {
+            Some(sharing) => (sharing.import)(),
+            None => return true,
+        };
+        if clauses.is_empty() {
+            return true;
+        }
+        'l: for literals in clauses {
+            let mut clause = Clause::new();
+            for &x in &literals {
+                let literal = Literal::new_from_dimacs(x);
+                if clause.contains(&literal) {
+                    continue;
+                }
+                if clause.iter().any(|&y| y.id() == literal.id()) {
+                    // tautology
+                    continue 'l;
+                }
+                clause.push(literal);
+            }
+            if clause.is_empty() {
+                self.has_empty_clause = true;
+                return false;
+            }
+            let lbd = clause.len();
+            let watched = [clause[0], clause[0]];
+            self.clauses
+                .push(TaggedClause::new(clause, Some(lbd), watched));
+        }
+        self.assign_unit_clause()
+    }
+    /// Deletes the half of the learnt clauses with the largest LBD when there are more than
+    /// `max_learnt_clauses` and a third of the original clauses, and keeps the ones of LBD at most 2.
+    /// It runs at level 0, where no clause is the reason of an assignment, so the ids can change.
+    fn reduce_learnt_clauses(&mut self) {
+        let mut learnt: Vec<(usize, usize, usize)> = self
+            .clauses
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| x.lbd().map(|lbd| (lbd, x.clause().len(), i)))
+            .collect();
+        let n_original_clauses = self.clauses.len() - learnt.len();
+        if learnt.len() <= self.max_learnt_clauses + n_original_clauses / 3 {
+            return;
+        }
+        learnt.sort_unstable();
+        let mut deleted = vec![false; self.clauses.len()];
+        for &(lbd, _, i) in &learnt[learnt.len() / 2..] {
+            if lbd > 2 {
+                deleted[i] = true;
+                self.stats.deleted_clauses += 1;
+            }
+        }
+        if self.proof.is_some() {
+            self.prove_units();
+            for (i, &deleted) in deleted.iter().enumerate() {
+                if deleted {
+                    let clause = self.clauses[i].clause().clone();
+                    self.prove(&clause, true);
+                }
+            }
+        }
+        let mut i = 0;
+        self.clauses.retain(|_| {
+            i += 1;
+            !deleted[i - 1]
+        });
+        for occurrences in self.clause_occurrences.iter_mut() {
+            occurrences.clear();
+        }
+        self.n_indexed_clauses = 0;
+        self.max_learnt_clauses += self.max_learnt_clauses / 10;
+    }
+    fn search(&mut self) -> Option<SatAssignments> {
         let first_signs = self.first_signs();
 
         if !self.try_next_assignment(0) {
             // end(SAT)
-            let xs: Vec<bool> = self.variables.iter().map(|&x| x.sign().unwrap()).collect();
-            let res = SatAssignments::new_from_vec(xs);
-            assert!(self.problem.check_assingemnt(&res));
-            return Some(res);
+            return Some(self.current_assignment());
         }
-        let mut loop_count = 0;
+        let mut n_restarts = 0;
+        let mut next_restart = self.stats.conflicts + self.restart_interval;
         'l1: loop {
-            if loop_count % 100 == 0 {
-                warn!(
-                    "clauses.len() = {}, conflict_count = {}",
-                    self.clauses.len(),
-                    self.conflict_count
-                );
-            }
-            loop_count += 1;
+            if self.stats.conflicts >= next_restart {
+                n_restarts += 1;
+                next_restart = self.stats.conflicts + self.restart_interval * luby(n_restarts + 1);
+                if !self.restart() {
+                    // UNSAT
+                    return None;
+                }
+                if !self.try_next_assignment(0) {
+                    // SAT
+                    return Some(self.current_assignment());
+                }
+            }
+            if self.dpll_stack.is_empty() {
+                // SAT
+                return Some(self.current_assignment());
+            }
             // try
-            assert!(!self.dpll_stack.is_empty());
             info!("dpll_stack_top: {:?}", self.dpll_stack.last());
             let i = self.dpll_stack.last().unwrap().0;
             match self.dpll_stack.last().unwrap().1 {
                 AssignmentState::First => {
-                    self.variables[i] = VariableState::Assigned {
-                        sign: first_signs[i],
-                        decision_level: self.decision_level,
-                    };
+                    self.set_variable(
+                        i,
+                        VariableState::Assigned {
+                            sign: first_signs[i],
+                            decision_level: self.decision_level,
+                        },
+                    );
+                    self.stats.decisions += 1;
                 }
-                AssignmentState::Second => {
-                    let old_sign = self.variables[i].sign().unwrap();
-                    self.variables[i] = VariableState::Assigned {
-                        sign: !old_sign,
-                        decision_level: self.decision_level,
-                    };
+                AssignmentState::Second(_) => {
+                    // the variable may still hold either sign after a backjump to this level,
+                    // so the second sign is taken from `first_signs` rather than flipped
+                    self.set_variable(
+                        i,
+                        VariableState::Assigned {
+                            sign: !first_signs[i],
+                            decision_level: self.decision_level,
+                        },
+                    );
                 }
-                AssignmentState::Propageted(_) => {
+                AssignmentState::Propageted(_) | AssignmentState::Implied(_) => {
                     panic!();
                 }
             }
@@ -674,7 +2304,40 @@ impl<'a> SatSolver<'a> {
             unit_propagation_stack.push_back(i);
             use std::collections::HashSet;
             let mut visited = HashSet::new();
-            while let Some(id) = unit_propagation_stack.pop_back() {
+            loop {
+                let id = match unit_propagation_stack.pop_back() {
+                    Some(id) => id,
+                    None => {
+                        // the implications of the XOR constraints are taken at the fixpoint of the others
+                        match self.xors.propagate() {
+                            Ok(xs) if xs.is_empty() => break,
+                            Ok(xs) => {
+                                for (x, reason) in xs {
+                                    self.set_variable(
+                                        x.id(),
+                                        VariableState::Assigned {
+                                            sign: x.sign(),
+                                            decision_level: self.decision_level,
+                                        },
+                                    );
+                                    self.dpll_stack
+                                        .push((x.id(), AssignmentState::Implied(reason)));
+                                    self.stats.propagations += 1;
+                                    unit_propagation_stack.push_back(x.id());
+                                }
+                                continue;
+                            }
+                            Err(conflict) => {
+                                if self.try_backtrack(conflict) {
+                                    continue 'l1;
+                                } else {
+                                    // UNSAT
+                                    return None;
+                                }
+                            }
+                        }
+                    }
+                };
                 if visited.contains(&id) {
                     continue;
                 }
@@ -719,26 +2382,27 @@ impl<'a> SatSolver<'a> {
                         assert!(watched[prev_i_literal_i].id() == id);
                         assert!(watched[prev_i_literal_i].id() != next_literal_id);
                         self.clauses[clause_id].watched[prev_i_literal_i] = *next_literal;
-                        self.watch[id] = self.watch[id]
-                            .iter()
-                            .filter(|&&x| x != clause_id)
-                            .cloned()
-                            .collect();
+                        self.watch[id].retain(|&x| x != clause_id);
                         self.watch[next_literal_id].push(clause_id);
                     } else {
                         let literal2 = watched[1 - prev_i_literal_i];
                         let id2 = literal2.id();
                         if self.variables[id2].is_not_assigned() {
-                            self.variables[id2] = VariableState::Assigned {
-                                sign: literal2.sign(),
-                                decision_level: self.decision_level,
-                            };
+                            self.set_variable(
+                                id2,
+                                VariableState::Assigned {
+                                    sign: literal2.sign(),
+                                    decision_level: self.decision_level,
+                                },
+                            );
                             self.dpll_stack
                                 .push((id2, AssignmentState::Propageted(clause_id)));
+                            self.stats.propagations += 1;
                             unit_propagation_stack.push_back(id2);
                         } else if self.variables[id2].sign().unwrap() != literal2.sign() {
                             // conflict
-                            let succeeded = self.try_backtrack(clause_id);
+                            let conflict = self.clauses[clause_id].clause().clone();
+                            let succeeded = self.try_backtrack(conflict);
                             if succeeded {
                                 continue 'l1;
                             } else {
@@ -748,26 +2412,91 @@ impl<'a> SatSolver<'a> {
                         }
                     }
                 }
+                // only a true literal decreases the slack of a native constraint
+                let literal = Literal::new(id, self.variables[id].sign().unwrap());
+                for k in 0..self.native_occurrences[id].len() {
+                    let (native_id, term) = self.native_occurrences[id][k];
+                    if self.natives[native_id].terms[term].0 != literal {
+                        continue;
+                    }
+                    match self.propagate_native(native_id, Some(literal)) {
+                        Ok(xs) => {
+                            for (x, reason) in xs {
+                                self.set_variable(
+                                    x.id(),
+                                    VariableState::Assigned {
+                                        sign: x.sign(),
+                                        decision_level: self.decision_level,
+                                    },
+                                );
+                                self.dpll_stack
+                                    .push((x.id(), AssignmentState::Implied(reason)));
+                                self.stats.propagations += 1;
+                                unit_propagation_stack.push_back(x.id());
+                            }
+                        }
+                        Err(conflict) => {
+                            if self.try_backtrack(conflict) {
+                                continue 'l1;
+                            } else {
+                                // UNSAT
+                                return None;
+                            }
+                        }
+                    }
+                }
             }
 
-            if !self.try_next_assignment(i) {
+            if !self.try_next_assignment(self.positions[i]) {
                 // SAT
-                let xs: Vec<bool> = self.variables.iter().map(|&x| x.sign().unwrap()).collect();
-                let res = SatAssignments::new_from_vec(xs);
-                assert!(self.problem.check_assingemnt(&res));
-                return Some(res);
+                return Some(self.current_assignment());
             }
         }
     }
 }
 
+/// Returns the i-th element of the Luby sequence 1, 1, 2, 1, 1, 2, 4, 1, ... from 1.
+fn luby(i: usize) -> usize {
+    assert!(i >= 1);
+    let mut i = i;
+    loop {
+        // the smallest k with i <= 2^k - 1
+        let mut k = 1;
+        while (1 << k) - 1 < i {
+            k += 1;
+        }
+        if i == (1 << k) - 1 {
+            return 1 << (k - 1);
+        }
+        i -= (1 << (k - 1)) - 1;
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct SatAssignments(Vec<bool>);
 
 impl SatAssignments {
-    fn new_from_vec(xs: Vec<bool>) -> SatAssignments {
+    pub(crate) fn new_from_vec(xs: Vec<bool>) -> SatAssignments {
         SatAssignments(xs)
     }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Drops the variables from `len`, such as the auxiliary variables of an encoding.
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len);
+    }
+    /// Restricts the assignment to the variables in `projection`.
+    pub fn project(&self, projection: &[usize]) -> SatPartialAssignments {
+        let mut res = SatPartialAssignments::new(self.0.len());
+        for &id in projection {
+            res[id] = Some(self.0[id]);
+        }
+        res
+    }
     pub fn to_dimacs(&self) -> String {
         let mut res = String::new();
         for i in 0..self.0.len() {
@@ -778,11 +2507,52 @@ impl SatAssignments {
                 res.push_str(&format!("{} ", i + 1));
             }
         }
-        res.push_str("0");
+        res.push('0');
         res
     }
 }
 
+/// An assignment where `None` means that the variable may take either value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SatPartialAssignments(Vec<Option<bool>>);
+
+impl SatPartialAssignments {
+    fn new(n_variables: usize) -> SatPartialAssignments {
+        SatPartialAssignments(vec![None; n_variables])
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn to_dimacs(&self) -> String {
+        let mut res = String::new();
+        for i in 0..self.0.len() {
+            match self.0[i] {
+                Some(true) => res.push_str(&format!("{} ", i + 1)),
+                Some(false) => res.push_str(&format!("-{} ", i + 1)),
+                None => {}
+            }
+        }
+        res.push('0');
+        res
+    }
+}
+
+impl<I: SliceIndex<[Option<bool>]>> Index<I> for SatPartialAssignments {
+    type Output = <I as SliceIndex<[Option<bool>]>>::Output;
+    fn index(&self, index: I) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl<I: SliceIndex<[Option<bool>]>> IndexMut<I> for SatPartialAssignments {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
 use std::ops::Index;
 use std::ops::IndexMut;
 use std::slice::SliceIndex;
@@ -805,10 +2575,11 @@ fn test_solve_sat_1() {
     let problem = SatProblem {
         n_variables: 1,
         clauses: Clauses::new_from_vec(vec![Clause::new_from_vec(vec![Literal::new(0, true)])]),
+        xors: vec![],
     };
     let mut solver = SatSolver::new(&problem);
     let res = solver.solve().unwrap();
-    assert!(problem.check_assingemnt(&res));
+    assert!(problem.check_assignment(&res));
 }
 
 #[test]
@@ -816,10 +2587,11 @@ fn test_solve_sat_2() {
     let problem = SatProblem {
         n_variables: 1,
         clauses: Clauses::new_from_vec(vec![Clause::new_from_vec(vec![Literal::new(0, false)])]),
+        xors: vec![],
     };
     let mut solver = SatSolver::new(&problem);
     let res = solver.solve().unwrap();
-    assert!(problem.check_assingemnt(&res));
+    assert!(problem.check_assignment(&res));
 }
 
 #[test]
@@ -830,10 +2602,11 @@ fn test_solve_sat_3() {
             Literal::new(0, true),
             Literal::new(1, false),
         ])]),
+        xors: vec![],
     };
     let mut solver = SatSolver::new(&problem);
     let res = solver.solve().unwrap();
-    assert!(problem.check_assingemnt(&res));
+    assert!(problem.check_assignment(&res));
 }
 
 #[test]
@@ -844,10 +2617,11 @@ fn test_solve_sat_4() {
             Literal::new(0, false),
             Literal::new(1, true),
         ])]),
+        xors: vec![],
     };
     let mut solver = SatSolver::new(&problem);
     let res = solver.solve().unwrap();
-    assert!(problem.check_assingemnt(&res));
+    assert!(problem.check_assignment(&res));
 }
 
 #[test]
@@ -858,10 +2632,11 @@ fn test_solve_sat_5() {
             Literal::new(0, false),
             Literal::new(1, true),
         ])]),
+        xors: vec![],
     };
     let mut solver = SatSolver::new(&problem);
     let res = solver.solve().unwrap();
-    assert!(problem.check_assingemnt(&res));
+    assert!(problem.check_assignment(&res));
 }
 
 #[test]
@@ -873,10 +2648,11 @@ fn test_solve_sat_6() {
             Literal::new(1, true),
             Literal::new(2, false),
         ])]),
+        xors: vec![],
     };
     let mut solver = SatSolver::new(&problem);
     let res = solver.solve().unwrap();
-    assert!(problem.check_assingemnt(&res));
+    assert!(problem.check_assignment(&res));
 }
 
 #[test]
@@ -887,6 +2663,7 @@ fn test_solve_sat_7() {
             Clause::new_from_vec(vec![Literal::new(0, true)]),
             Clause::new_from_vec(vec![Literal::new(0, false)]),
         ]),
+        xors: vec![],
     };
     let mut solver = SatSolver::new(&problem);
     let res = solver.solve();
@@ -920,20 +2697,580 @@ fn test_solve_sat_8() {
             ]),
             Clause::new_from_vec(vec![Literal::new(2, true)]),
         ]),
+        xors: vec![],
     };
     let mut solver = SatSolver::new(&problem);
     let res = solver.solve().unwrap();
-    assert!(problem.check_assingemnt(&res));
+    assert!(problem.check_assignment(&res));
 }
 
 #[test]
-#[ignore]
-fn test_solve_sat_9() {
+fn test_solve_against_brute_force() {
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
     for _ in 0..1000 {
-        let problem = SatProblem::gen_random_sat(10000, 10000, 4, 0.2);
-        // eprintln!("problem\n{}\n", problem.to_dimacs());
+        let n_variables = 10;
+        let clauses: Vec<Clause> = (0..rng.gen_range(10, 60))
+            .map(|_| {
+                // distinct variables
+                let mut ids: Vec<usize> = (0..n_variables).collect();
+                ids.shuffle(&mut rng);
+                let len = rng.gen_range(2, 6);
+                Clause::new_from_vec(
+                    ids[..len]
+                        .iter()
+                        .map(|&id| Literal::new(id, rng.gen()))
+                        .collect(),
+                )
+            })
+            .collect();
+        let problem = SatProblem {
+            n_variables,
+            clauses: Clauses::new_from_vec(clauses),
+            xors: vec![],
+        };
+        let expected = (0..1u32 << n_variables).any(|bits| {
+            problem.check_assignment(&SatAssignments::new_from_vec(
+                (0..n_variables).map(|i| bits >> i & 1 == 1).collect(),
+            ))
+        });
         let mut solver = SatSolver::new(&problem);
-        let res = solver.solve().unwrap();
-        assert!(problem.check_assingemnt(&res));
+        match solver.solve() {
+            Some(res) => assert!(problem.check_assignment(&res)),
+            None => assert!(!expected),
+        }
+    }
+}
+
+#[test]
+fn test_solve_with_assumptions() {
+    // (x1 or x2) and (not x1 or x3)
+    let problem = SatProblem::new_from_dimacs("p cnf 3 2\n1 2 0\n-1 3 0\n");
+    let mut solver = SatSolver::new(&problem);
+    let res = solver.solve_with_assumptions(&[1]).unwrap();
+    assert!(res[0] && res[2]);
+    assert!(solver.solve_with_assumptions(&[1, -3]).is_none());
+    assert!(solver.solve_with_assumptions(&[-1, -2]).is_none());
+    let res = solver.solve_with_assumptions(&[-3]).unwrap();
+    assert!(!res[0] && res[1]);
+}
+
+#[test]
+fn test_add_clause() {
+    let problem = SatProblem::new_from_dimacs("p cnf 2 1\n1 2 0\n");
+    let mut solver = SatSolver::new(&problem);
+    assert!(solver.solve().is_some());
+    solver.add_clause(&[-1]);
+    solver.add_clause(&[-2, 3]);
+    let res = solver.solve().unwrap();
+    assert_eq!(res.to_dimacs(), "-1 2 3 0");
+    solver.add_clause(&[-3]);
+    assert!(solver.solve().is_none());
+}
+
+#[test]
+fn test_failed_assumptions() {
+    // x1 -> x2, x2 -> x3
+    let problem = SatProblem::new_from_dimacs("p cnf 4 2\n-1 2 0\n-2 3 0\n");
+    let mut solver = SatSolver::new(&problem);
+    assert!(solver.solve_with_assumptions(&[4, 1, -2, -3]).is_none());
+    // the first clause is falsified by the propagation of the assumptions
+    assert_eq!(solver.failed_assumptions(), vec![1, -2]);
+    assert_eq!(solver.minimize_failed_assumptions(), vec![1, -2]);
+    assert!(solver.solve_with_assumptions(&[4, 1]).is_some());
+    assert!(solver.failed_assumptions().is_empty());
+    assert!(solver.solve_with_assumptions(&[2, -2]).is_none());
+    let mut core = solver.failed_assumptions();
+    core.sort();
+    assert_eq!(core, vec![-2, 2]);
+
+    // x1 makes x2 and x3 contradict, which only the search finds
+    let problem =
+        SatProblem::new_from_dimacs("p cnf 4 4\n-1 2 3 0\n-1 2 -3 0\n-1 -2 3 0\n-1 -2 -3 0\n");
+    let mut solver = SatSolver::new(&problem);
+    assert!(solver.solve_with_assumptions(&[4, 1]).is_none());
+    assert_eq!(solver.failed_assumptions(), vec![1]);
+}
+
+#[test]
+fn test_failed_assumptions_are_unsatisfiable() {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
+    for seed in 0..100 {
+        let problem = SatProblem::gen_random_sat(10, 40, 3, 0.5, seed);
+        let mut solver = SatSolver::new(&problem);
+        let mut assumptions = vec![];
+        for x in 1..=10 {
+            if rng.gen() {
+                assumptions.push(if rng.gen() { x } else { -x });
+            }
+        }
+        if solver.solve_with_assumptions(&assumptions).is_some() {
+            continue;
+        }
+        let core = solver.failed_assumptions();
+        assert!(core.iter().all(|x| assumptions.contains(x)));
+        let minimal = solver.minimize_failed_assumptions();
+        assert!(minimal.iter().all(|x| core.contains(x)));
+        assert!(SatSolver::new(&problem)
+            .solve_with_assumptions(&core)
+            .is_none());
+        for i in 0..minimal.len() {
+            let mut rest = minimal.clone();
+            rest.remove(i);
+            assert!(solver.solve_with_assumptions(&rest).is_some());
+        }
     }
 }
+
+#[test]
+fn test_luby() {
+    let xs: Vec<usize> = (1..16).map(luby).collect();
+    assert_eq!(xs, vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+}
+
+#[test]
+fn test_restarts_delete_learnt_clauses() {
+    // 7 pigeons in 6 holes
+    let var = |pigeon: usize, hole: usize| pigeon * 6 + hole + 1;
+    let mut s = String::from("p cnf 42 133\n");
+    for pigeon in 0..7 {
+        for hole in 0..6 {
+            s.push_str(&format!("{} ", var(pigeon, hole)));
+        }
+        s.push_str("0\n");
+    }
+    for hole in 0..6 {
+        for i in 0..7 {
+            for k in i + 1..7 {
+                s.push_str(&format!("-{} -{} 0\n", var(i, hole), var(k, hole)));
+            }
+        }
+    }
+    let problem = SatProblem::new_from_dimacs(&s);
+    let mut solver = SatSolver::new(&problem);
+    assert!(solver.solve().is_none());
+    let stats = solver.stats();
+    assert!(stats.restarts > 0);
+    assert!(stats.deleted_clauses > 0);
+    let n_learnt = solver.clauses.iter().filter(|x| x.lbd().is_some()).count();
+    assert_eq!(n_learnt, stats.learnt_clauses - stats.deleted_clauses);
+}
+
+#[test]
+fn test_import_clauses() {
+    // 6 pigeons in 6 holes
+    let var = |pigeon: usize, hole: usize| (pigeon * 6 + hole + 1) as i64;
+    let mut problem = SatProblem::new(36);
+    for pigeon in 0..6 {
+        let clause: Vec<i64> = (0..6).map(|hole| var(pigeon, hole)).collect();
+        problem.add_clause(&clause);
+    }
+    for hole in 0..6 {
+        for i in 0..6 {
+            for k in i + 1..6 {
+                problem.add_clause(&[-var(i, hole), -var(k, hole)]);
+            }
+        }
+    }
+    // the tautologies are skipped, and the duplicate literals are merged
+    let mut solver = SatSolver::new(&problem);
+    solver.set_restart_interval(1);
+    let mut imports = vec![vec![vec![1, -1], vec![-1, 2, -1, -2], vec![-1, -7, -1]]];
+    solver.set_clause_sharing(|_, _| {}, move || imports.pop().unwrap_or_default());
+    let model = solver.solve().unwrap();
+    assert!(problem.check_assignment(&model));
+    assert!(solver.stats().restarts > 0);
+    // the learnt clauses that the solver did not learn itself
+    let n_imported = solver.clauses.iter().filter(|x| x.learnt()).count()
+        + solver.stats().deleted_clauses
+        - solver.stats().learnt_clauses;
+    assert_eq!(n_imported, 1);
+    // an empty clause is UNSAT
+    let mut solver = SatSolver::new(&problem);
+    solver.set_restart_interval(1);
+    solver.set_clause_sharing(|_, _| {}, || vec![vec![]]);
+    assert!(solver.solve().is_none());
+    assert!(solver.stats().restarts > 0);
+}
+
+#[test]
+fn test_terminate() {
+    // 8 pigeons in 7 holes, which takes thousands of conflicts
+    let var = |pigeon: usize, hole: usize| (pigeon * 7 + hole + 1) as i64;
+    let mut solver = SatSolver::new(&SatProblem::new(0));
+    for pigeon in 0..8 {
+        let clause: Vec<i64> = (0..7).map(|hole| var(pigeon, hole)).collect();
+        solver.add_clause(&clause);
+    }
+    for hole in 0..7 {
+        for i in 0..8 {
+            for k in i + 1..8 {
+                solver.add_clause(&[-var(i, hole), -var(k, hole)]);
+            }
+        }
+    }
+    let mut n_calls = 0;
+    solver.set_terminate(move || {
+        n_calls += 1;
+        n_calls > 10
+    });
+    assert!(solver.solve().is_none());
+    assert!(solver.interrupted());
+    assert_eq!(solver.stats().conflicts, 10);
+    // the reversed order solves it as well
+    let order: Vec<i64> = (1..=56).rev().collect();
+    solver.set_decision_order(&order);
+    solver.set_terminate(|| false);
+    assert!(solver.solve().is_none());
+    assert!(!solver.interrupted());
+}
+
+#[test]
+fn test_native_at_most_k() {
+    // 4 pigeons in 3 holes, with a native constraint for every hole
+    let pigeon = |p: i64, h: i64| p * 3 + h + 1;
+    let mut solver = SatSolver::new(&SatProblem::new(12));
+    for p in 0..4 {
+        solver.add_clause(&[pigeon(p, 0), pigeon(p, 1), pigeon(p, 2)]);
+    }
+    for h in 0..3 {
+        let literals: Vec<i64> = (0..4).map(|p| pigeon(p, h)).collect();
+        solver.add_native_at_most_k(&literals, 1);
+    }
+    assert!(solver.solve().is_none());
+    assert!(solver.stats().conflicts > 0);
+
+    // x1 + x2 + x3 + x4 <= 2 together with x5 -> x4
+    let mut solver = SatSolver::new(&SatProblem::new(6));
+    solver.add_native_at_most_k(&[1, 2, 3, 4], 2);
+    solver.add_clause(&[-5, 4]);
+    assert!(solver.solve_with_assumptions(&[6, 1, 5, 2]).is_none());
+    let mut core = solver.failed_assumptions();
+    core.sort();
+    assert_eq!(core, vec![1, 2, 5]);
+    assert_eq!(solver.propagate(&[1, 2]), Ok(vec![1, 2, -3, -4, -5]));
+    assert_eq!(solver.propagate(&[1, 3, 4]), Err(vec![1, 3, 4]));
+    let model = solver.solve_with_assumptions(&[1, 5]).unwrap();
+    assert_eq!(&model.to_dimacs()[..12], "1 -2 -3 4 5 ");
+}
+
+#[test]
+fn test_native_constraints_against_brute_force() {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
+    for seed in 0..100 {
+        let n = 10;
+        let problem = SatProblem::gen_random_sat(n, 25, 3, 0.5, seed);
+        let mut solver = SatSolver::new(&problem);
+        let mut natives = vec![];
+        for _ in 0..3 {
+            let terms: Vec<(i64, u64)> = (1..=n as i64)
+                .filter_map(|x| match rng.gen_range(0, 4) {
+                    0 => None,
+                    k => Some((if k == 1 { x } else { -x }, rng.gen_range(1, 5))),
+                })
+                .collect();
+            let bound = rng.gen_range(0, 8);
+            solver.add_native_at_most(&terms, bound);
+            natives.push((terms, bound));
+        }
+        let value = |bits: u32, x: i64| (bits >> (x.abs() - 1) & 1 == 1) == (x > 0);
+        let satisfies = |bits: u32, assumptions: &[i64]| {
+            assumptions.iter().all(|&x| value(bits, x))
+                && problem
+                    .clauses
+                    .iter()
+                    .all(|c| c.iter().any(|y| value(bits, y.to_i64())))
+                && natives.iter().all(|(terms, bound)| {
+                    let sum: u64 = terms.iter().filter(|x| value(bits, x.0)).map(|x| x.1).sum();
+                    sum as i64 <= *bound
+                })
+        };
+        let assumptions: Vec<i64> = (1..=n as i64)
+            .filter_map(|x| match rng.gen_range(0, 6) {
+                0 => Some(x),
+                1 => Some(-x),
+                _ => None,
+            })
+            .collect();
+        let expected = (0..1u32 << n).any(|bits| satisfies(bits, &assumptions));
+        match solver.solve_with_assumptions(&assumptions) {
+            Some(model) => {
+                assert!(expected);
+                let bits = (0..n).filter(|&i| model[i]).map(|i| 1 << i).sum();
+                assert!(satisfies(bits, &assumptions));
+            }
+            None => {
+                assert!(!expected);
+                // the failed assumptions are unsatisfiable on their own
+                let core = solver.failed_assumptions();
+                assert!(core.iter().all(|x| assumptions.contains(x)));
+                assert!(!(0..1u32 << n).any(|bits| satisfies(bits, &core)));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_seed() {
+    // the same seed generates the same problem
+    let problem = SatProblem::gen_random_sat(200, 850, 3, 0.5, 7);
+    let dimacs = problem.to_dimacs();
+    assert_eq!(
+        SatProblem::gen_random_sat(200, 850, 3, 0.5, 7).to_dimacs(),
+        dimacs
+    );
+    assert_ne!(
+        SatProblem::gen_random_sat(200, 850, 3, 0.5, 8).to_dimacs(),
+        dimacs
+    );
+    // and the same search
+    let search = |seed: u64| {
+        let mut solver = SatSolver::new(&problem);
+        solver.set_seed(seed);
+        solver.shuffle_decision_order();
+        solver.randomize_phases();
+        let model = solver.solve();
+        let stats = solver.stats();
+        (model, stats.decisions, stats.propagations, stats.conflicts)
+    };
+    assert_eq!(search(1), search(1));
+    assert_ne!(search(1), search(2));
+}
+
+#[test]
+fn test_parse_xor() {
+    let s = "p cnf 4 3\n1 2 0\nx1 -2 3 0\nx -3 4 0\n";
+    let problem = SatProblem::new_from_dimacs(s);
+    assert_eq!(problem.n_clauses(), 1);
+    let xors: Vec<Vec<i64>> = problem.xors().collect();
+    assert_eq!(xors, vec![vec![1, -2, 3], vec![-3, 4]]);
+    assert_eq!(
+        SatProblem::new_from_dimacs(&problem.to_dimacs()).to_dimacs(),
+        problem.to_dimacs()
+    );
+}
+
+#[test]
+fn test_parse_blank_lines() {
+    let problem = SatProblem::new_from_dimacs("c comment\n\np cnf 3 2\n1 -2 0\n\n  \nx2 3 0\n\n");
+    assert_eq!(problem.clauses().collect::<Vec<_>>(), vec![vec![1, -2]]);
+    assert_eq!(problem.xors().collect::<Vec<_>>(), vec![vec![2, 3]]);
+    let lines = parse_icnf("\np inccnf\n\n1 2 0\n\na -1 0\n");
+    assert_eq!(
+        lines,
+        vec![
+            IcnfLine::Clause(vec![1, 2]),
+            IcnfLine::Assumptions(vec![-1])
+        ]
+    );
+}
+
+#[test]
+fn test_try_from_dimacs() {
+    let problem = SatProblem::try_from_dimacs("p cnf 2 2\n1 -2 0\nx1 2 0\n").unwrap();
+    assert_eq!(problem.to_dimacs(), "p cnf 2 2\n1 -2 0\nx1 2 0\n");
+    for s in &[
+        "",
+        "1 2 0\n",
+        "p cnf 2\n1 2 0\n",
+        "p cnf two 1\n1 2 0\n",
+        "p cnf 2 1\n1 y 0\n",
+        "p cnf 2 1\n1 x2 0\n",
+        "p cnf 2 2\n1 2 0\n",
+        "p cnf 2 1\na 1 0\n",
+    ] {
+        assert!(SatProblem::try_from_dimacs(s).is_err(), "{:?}", s);
+    }
+}
+
+#[test]
+fn test_parse_icnf() {
+    let lines = parse_icnf("c incremental\np inccnf\n1 2 0\na -1 0\n-2\n3 0\nx1 -3 0\na -1\n0\n");
+    assert_eq!(
+        lines,
+        vec![
+            IcnfLine::Clause(vec![1, 2]),
+            IcnfLine::Assumptions(vec![-1]),
+            IcnfLine::Clause(vec![-2, 3]),
+            IcnfLine::Xor(vec![1, -3]),
+            IcnfLine::Assumptions(vec![-1]),
+        ]
+    );
+    // every call sees the clauses before it
+    let mut solver = SatSolver::new(&SatProblem::new(0));
+    let mut results = vec![];
+    for line in lines {
+        match line {
+            IcnfLine::Clause(xs) => solver.add_clause(&xs),
+            IcnfLine::Xor(xs) => solver.add_xor(&xs),
+            IcnfLine::Assumptions(xs) => results.push(solver.solve_with_assumptions(&xs).is_some()),
+        }
+    }
+    assert_eq!(results, vec![true, false]);
+}
+
+#[test]
+fn test_xor_chain() {
+    // x1 xor ... xor xn is odd and every x(i) equals x(i + 1), which is refuted by elimination alone if n is even
+    let chain = |n: i64| {
+        let mut problem = SatProblem::new(n as usize);
+        problem.add_xor(&(1..=n).collect::<Vec<i64>>());
+        for i in 1..n {
+            problem.add_xor(&[i, -(i + 1)]);
+        }
+        problem
+    };
+    let mut solver = SatSolver::new(&chain(30));
+    assert!(solver.solve().is_none());
+    assert_eq!(solver.stats().decisions, 0);
+    let problem = chain(31);
+    let mut solver = SatSolver::new(&problem);
+    let model = solver.solve().unwrap();
+    assert!(problem.check_assignment(&model));
+    assert_eq!(solver.stats().decisions, 0);
+    assert_eq!(solver.propagate(&[-1]), Err(vec![-1]));
+}
+
+#[test]
+fn test_xor_against_brute_force() {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
+    for seed in 0..200 {
+        let n = 10;
+        let mut problem = SatProblem::gen_random_sat(n, 20, 3, 0.5, seed);
+        for _ in 0..rng.gen_range(1, 6) {
+            let xor: Vec<i64> = (1..=n as i64)
+                .filter_map(|x| match rng.gen_range(0, 6) {
+                    0 => Some(x),
+                    1 => Some(-x),
+                    _ => None,
+                })
+                .collect();
+            problem.add_xor(&xor);
+        }
+        let satisfies = |bits: u32, assumptions: &[i64]| {
+            let xs = (0..n).map(|i| bits >> i & 1 == 1).collect();
+            let assignment = SatAssignments::new_from_vec(xs);
+            assumptions
+                .iter()
+                .all(|&x| assignment[x.unsigned_abs() as usize - 1] == (x > 0))
+                && problem.check_assignment(&assignment)
+        };
+        let assumptions: Vec<i64> = (1..=n as i64)
+            .filter_map(|x| match rng.gen_range(0, 6) {
+                0 => Some(x),
+                1 => Some(-x),
+                _ => None,
+            })
+            .collect();
+        let expected = (0..1u32 << n).any(|bits| satisfies(bits, &assumptions));
+        let mut solver = SatSolver::new(&problem);
+        match solver.solve_with_assumptions(&assumptions) {
+            Some(model) => {
+                assert!(expected);
+                assert!(problem.check_assignment(&model));
+            }
+            None => {
+                assert!(!expected);
+                let core = solver.failed_assumptions();
+                assert!(!(0..1u32 << n).any(|bits| satisfies(bits, &core)));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_xor_propagation_against_brute_force() {
+    // the propagation of XOR constraints alone is complete, also when the kept assignment is
+    // backtracked to the literals in common with the last call
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(0);
+    for seed in 0..50 {
+        let n = 8;
+        let mut problem = SatProblem::new(n);
+        for _ in 0..rng.gen_range(1, 6) {
+            let xor: Vec<i64> = (1..=n as i64)
+                .filter_map(|x| match rng.gen_range(0, 6) {
+                    0 => Some(x),
+                    1 => Some(-x),
+                    _ => None,
+                })
+                .collect();
+            if xor.len() >= 2 {
+                problem.add_xor(&xor);
+            }
+        }
+        let models: Vec<u32> = (0..1u32 << n)
+            .filter(|&bits| {
+                let xs = (0..n).map(|i| bits >> i & 1 == 1).collect();
+                problem.check_assignment(&SatAssignments::new_from_vec(xs))
+            })
+            .collect();
+        let mut solver = SatSolver::new(&problem);
+        let mut literals: Vec<i64> = vec![];
+        for _ in 0..20 {
+            literals.truncate(rng.gen_range(0, literals.len() + 1));
+            for _ in 0..rng.gen_range(0, 4) {
+                let x = rng.gen_range(1, n as i64 + 1);
+                if !literals.iter().any(|y| y.abs() == x) {
+                    literals.push(if rng.gen() { x } else { -x });
+                }
+            }
+            let value = |bits: u32, x: i64| (bits >> (x.unsigned_abs() - 1) & 1 == 1) == (x > 0);
+            let extensions: Vec<u32> = models
+                .iter()
+                .copied()
+                .filter(|&bits| literals.iter().all(|&x| value(bits, x)))
+                .collect();
+            match solver.propagate(&literals) {
+                Ok(mut xs) => {
+                    assert!(!extensions.is_empty());
+                    let mut expected: Vec<i64> = (1..=n as i64)
+                        .flat_map(|x| [x, -x])
+                        .filter(|&x| extensions.iter().all(|&bits| value(bits, x)))
+                        .collect();
+                    xs.sort_unstable();
+                    expected.sort_unstable();
+                    assert_eq!(xs, expected);
+                }
+                Err(_) => assert!(extensions.is_empty()),
+            }
+            if seed % 2 == 0 {
+                // an assignment of the whole search in between
+                solver.solve();
+            }
+        }
+    }
+}
+
+#[test]
+fn test_expand_xors() {
+    // x1 xor ... xor x7 and x1 xor ~x2, whose models extend uniquely to the fresh variables
+    let mut problem = SatProblem::new(7);
+    problem.add_xor(&[1, 2, 3, 4, 5, 6, 7]);
+    problem.add_xor(&[1, -2]);
+    problem.add_clause(&[3, 4]);
+    let expanded = problem.expand_xors();
+    assert_eq!(expanded.xors().count(), 0);
+    let n = expanded.n_variables();
+    let count = |problem: &SatProblem, n: usize| {
+        (0..1u32 << n)
+            .filter(|&bits| {
+                let xs = (0..n).map(|i| bits >> i & 1 == 1).collect();
+                problem.check_assignment(&SatAssignments::new_from_vec(xs))
+            })
+            .count()
+    };
+    assert_eq!(count(&expanded, n), count(&problem, 7));
+    let model = SatSolver::new(&expanded).solve().unwrap();
+    let xs = (0..7).map(|i| model[i]).collect();
+    assert!(problem.check_assignment(&SatAssignments::new_from_vec(xs)));
+}
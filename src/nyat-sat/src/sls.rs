@@ -0,0 +1,298 @@
+use crate::sat::{SatAssignments, SatProblem};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlsAlgorithm {
+    /// ProbSAT of Balint and Schöning: a variable of a falsified clause is flipped with the
+    /// probability proportional to `(1 + break)^-cb`.
+    ProbSat { cb: f64 },
+    /// WalkSAT of Selman, Kautz and Cohen: a variable without breaks if there is one, or else
+    /// a random one with the probability `noise`, or else one with the fewest breaks.
+    WalkSat { noise: f64 },
+}
+
+impl SlsAlgorithm {
+    /// ProbSAT with the polynomial break function tuned for 3-SAT.
+    pub fn probsat() -> SlsAlgorithm {
+        SlsAlgorithm::ProbSat { cb: 2.38 }
+    }
+    pub fn walksat() -> SlsAlgorithm {
+        SlsAlgorithm::WalkSat { noise: 0.567 }
+    }
+}
+
+/// Stochastic local search over the clauses of a problem, which flips one variable at a time.
+/// The break count of a variable is the number of clauses where it has the only true literal,
+/// and its make count is the number of falsified clauses where it occurs, which are kept in a list.
+pub struct LocalSearch {
+    /// clauses without repeated literals or tautologies
+    clauses: Vec<Vec<i64>>,
+    /// clauses of every literal, at `2 * id` for the positive one and `2 * id + 1` for the negative one
+    occurrences: Vec<Vec<usize>>,
+    assignment: Vec<bool>,
+    /// number of true literals of every clause
+    n_true: Vec<usize>,
+    /// exclusive or of the variables of the true literals of every clause, which is the only one if there is one
+    true_variables: Vec<usize>,
+    breaks: Vec<usize>,
+    makes: Vec<usize>,
+    falsified: Vec<usize>,
+    /// index of every clause in `falsified`
+    falsified_index: Vec<Option<usize>>,
+    has_empty_clause: bool,
+    best: Vec<bool>,
+    best_n_falsified: usize,
+    flips: usize,
+    rng: StdRng,
+    /// number of variables of the problem, which are followed by the fresh variables of its XOR constraints
+    n_variables: usize,
+}
+
+fn index(literal: i64) -> usize {
+    2 * (literal.unsigned_abs() as usize - 1) + if literal > 0 { 0 } else { 1 }
+}
+
+impl LocalSearch {
+    /// Starts from a random assignment by a generator with `seed`.
+    pub fn new(problem: &SatProblem, seed: u64) -> LocalSearch {
+        // the XOR constraints are expanded into clauses, whose fresh variables are left out of the models
+        let n_variables = problem.n_variables();
+        let problem = &problem.expand_xors();
+        let n = problem.n_variables();
+        let mut res = LocalSearch {
+            clauses: vec![],
+            occurrences: vec![vec![]; 2 * n],
+            assignment: vec![],
+            n_true: vec![],
+            true_variables: vec![],
+            breaks: vec![],
+            makes: vec![],
+            falsified: vec![],
+            falsified_index: vec![],
+            has_empty_clause: false,
+            best: vec![],
+            best_n_falsified: usize::MAX,
+            flips: 0,
+            rng: StdRng::seed_from_u64(seed),
+            n_variables,
+        };
+        for mut clause in problem.clauses() {
+            clause.sort_unstable();
+            clause.dedup();
+            if clause.iter().any(|x| clause.binary_search(&-x).is_ok()) {
+                continue;
+            }
+            if clause.is_empty() {
+                res.has_empty_clause = true;
+            }
+            for &x in &clause {
+                res.occurrences[index(x)].push(res.clauses.len());
+            }
+            res.clauses.push(clause);
+        }
+        let assignment: Vec<bool> = (0..n).map(|_| res.rng.gen()).collect();
+        res.set_assignment(&assignment);
+        res
+    }
+    pub fn n_variables(&self) -> usize {
+        self.n_variables
+    }
+    pub fn flips(&self) -> usize {
+        self.flips
+    }
+    /// Returns the assignment with the fewest falsified clauses so far.
+    pub fn best_assignment(&self) -> &[bool] {
+        &self.best[..self.n_variables]
+    }
+    pub fn best_n_falsified(&self) -> usize {
+        self.best_n_falsified
+    }
+    /// Restarts from an assignment of every variable, including the fresh ones.
+    pub fn set_assignment(&mut self, assignment: &[bool]) {
+        assert_eq!(assignment.len(), self.occurrences.len() / 2);
+        let n_clauses = self.clauses.len();
+        self.assignment = assignment.to_vec();
+        self.n_true = vec![0; n_clauses];
+        self.true_variables = vec![0; n_clauses];
+        self.breaks = vec![0; self.assignment.len()];
+        self.makes = vec![0; self.assignment.len()];
+        self.falsified.clear();
+        self.falsified_index = vec![None; n_clauses];
+        for (i, clause) in self.clauses.iter().enumerate() {
+            for &x in clause {
+                let id = x.unsigned_abs() as usize - 1;
+                if self.assignment[id] == (x > 0) {
+                    self.n_true[i] += 1;
+                    self.true_variables[i] ^= id;
+                }
+            }
+            match self.n_true[i] {
+                0 => {
+                    self.falsified_index[i] = Some(self.falsified.len());
+                    self.falsified.push(i);
+                    for &x in clause {
+                        self.makes[x.unsigned_abs() as usize - 1] += 1;
+                    }
+                }
+                1 => self.breaks[self.true_variables[i]] += 1,
+                _ => {}
+            }
+        }
+        self.update_best();
+    }
+    fn update_best(&mut self) {
+        if self.falsified.len() < self.best_n_falsified {
+            self.best_n_falsified = self.falsified.len();
+            self.best = self.assignment.clone();
+        }
+    }
+    fn flip(&mut self, id: usize) {
+        self.flips += 1;
+        self.assignment[id] = !self.assignment[id];
+        let (made, broken) = if self.assignment[id] {
+            (2 * id, 2 * id + 1)
+        } else {
+            (2 * id + 1, 2 * id)
+        };
+        for k in 0..self.occurrences[made].len() {
+            let i = self.occurrences[made][k];
+            match self.n_true[i] {
+                0 => {
+                    self.remove_falsified(i);
+                    for &x in &self.clauses[i] {
+                        self.makes[x.unsigned_abs() as usize - 1] -= 1;
+                    }
+                    self.breaks[id] += 1;
+                }
+                1 => self.breaks[self.true_variables[i]] -= 1,
+                _ => {}
+            }
+            self.n_true[i] += 1;
+            self.true_variables[i] ^= id;
+        }
+        for k in 0..self.occurrences[broken].len() {
+            let i = self.occurrences[broken][k];
+            self.n_true[i] -= 1;
+            self.true_variables[i] ^= id;
+            match self.n_true[i] {
+                0 => {
+                    self.falsified_index[i] = Some(self.falsified.len());
+                    self.falsified.push(i);
+                    for &x in &self.clauses[i] {
+                        self.makes[x.unsigned_abs() as usize - 1] += 1;
+                    }
+                    self.breaks[id] -= 1;
+                }
+                1 => self.breaks[self.true_variables[i]] += 1,
+                _ => {}
+            }
+        }
+    }
+    fn remove_falsified(&mut self, i: usize) {
+        let k = self.falsified_index[i].take().unwrap();
+        self.falsified.swap_remove(k);
+        if k < self.falsified.len() {
+            self.falsified_index[self.falsified[k]] = Some(k);
+        }
+    }
+    /// Returns the variable to flip in a falsified clause.
+    fn pick(&mut self, algorithm: SlsAlgorithm, clause: usize) -> usize {
+        let ids: Vec<usize> = self.clauses[clause]
+            .iter()
+            .map(|x| x.unsigned_abs() as usize - 1)
+            .collect();
+        match algorithm {
+            SlsAlgorithm::ProbSat { cb } => {
+                let scores: Vec<f64> = ids
+                    .iter()
+                    .map(|&id| (1.0 + self.breaks[id] as f64).powf(-cb))
+                    .collect();
+                let mut r = self.rng.gen::<f64>() * scores.iter().sum::<f64>();
+                for (&id, &score) in ids.iter().zip(&scores) {
+                    if r < score {
+                        return id;
+                    }
+                    r -= score;
+                }
+                *ids.last().unwrap()
+            }
+            SlsAlgorithm::WalkSat { noise } => {
+                let min_break = ids.iter().map(|&id| self.breaks[id]).min().unwrap();
+                if min_break > 0 && self.rng.gen::<f64>() < noise {
+                    return ids[self.rng.gen_range(0, ids.len())];
+                }
+                // the ties are broken by the make counts, and then at random
+                let best: Vec<usize> = ids
+                    .iter()
+                    .cloned()
+                    .filter(|&id| self.breaks[id] == min_break)
+                    .collect();
+                let max_make = best.iter().map(|&id| self.makes[id]).max().unwrap();
+                let best: Vec<usize> = best
+                    .into_iter()
+                    .filter(|&id| self.makes[id] == max_make)
+                    .collect();
+                best[self.rng.gen_range(0, best.len())]
+            }
+        }
+    }
+    /// Flips until every clause is satisfied or `max_flips` more flips are done,
+    /// and returns the model if it is found.
+    pub fn run(&mut self, algorithm: SlsAlgorithm, max_flips: usize) -> Option<SatAssignments> {
+        if self.has_empty_clause {
+            return None;
+        }
+        for _ in 0..max_flips {
+            if self.falsified.is_empty() {
+                break;
+            }
+            let clause = self.falsified[self.rng.gen_range(0, self.falsified.len())];
+            let id = self.pick(algorithm, clause);
+            self.flip(id);
+            self.update_best();
+        }
+        if self.falsified.is_empty() {
+            Some(SatAssignments::new_from_vec(
+                self.assignment[..self.n_variables].to_vec(),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn test_local_search() {
+    use crate::sat::SatSolver;
+    for &algorithm in [SlsAlgorithm::probsat(), SlsAlgorithm::walksat()].iter() {
+        for seed in 0..20 {
+            // a planted solution makes it satisfiable
+            let problem = SatProblem::gen_random_sat(100, 380, 3, 0.5, seed);
+            let mut sls = LocalSearch::new(&problem, seed);
+            let model = sls.run(algorithm, 1_000_000).unwrap();
+            assert!(problem.check_assignment(&model));
+            assert_eq!(sls.best_n_falsified(), 0);
+            // the counts are the same as after a restart from the same assignment
+            let (breaks, makes) = (sls.breaks.clone(), sls.makes.clone());
+            let assignment = sls.assignment.clone();
+            sls.set_assignment(&assignment);
+            assert_eq!((breaks, makes), (sls.breaks.clone(), sls.makes.clone()));
+            // the model is the first one of the solver with it as the phases
+            let mut solver = SatSolver::new(&problem);
+            solver.set_phases(sls.best_assignment());
+            assert_eq!(solver.solve(), Some(model));
+            assert_eq!(solver.stats().conflicts, 0);
+        }
+    }
+    let problem = SatProblem::new_from_dimacs("p cnf 1 2\n1 0\n-1 0\n");
+    let mut sls = LocalSearch::new(&problem, 0);
+    assert!(sls.run(SlsAlgorithm::walksat(), 1000).is_none());
+    assert_eq!(sls.best_n_falsified(), 1);
+    // the fresh variables of the XOR constraints are left out of the model
+    let problem = SatProblem::new_from_dimacs("p cnf 6 2\nx1 2 3 4 5 6 0\n-1 -2 0\n");
+    let mut sls = LocalSearch::new(&problem, 0);
+    let model = sls.run(SlsAlgorithm::walksat(), 100_000).unwrap();
+    assert_eq!(sls.best_assignment().len(), 6);
+    assert!(problem.check_assignment(&model));
+}
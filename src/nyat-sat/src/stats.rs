@@ -0,0 +1,192 @@
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Parse,
+    Simplify,
+    Search,
+    Analysis,
+}
+
+impl Phase {
+    const ALL: [Phase; 4] = [
+        Phase::Parse,
+        Phase::Simplify,
+        Phase::Search,
+        Phase::Analysis,
+    ];
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Parse => "parse",
+            Phase::Simplify => "simplify",
+            Phase::Search => "search",
+            Phase::Analysis => "analysis",
+        }
+    }
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Measures the time of a phase, which is always zero on wasm32 because it has no clock.
+pub struct Timer {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: Instant,
+}
+
+impl Timer {
+    pub fn start() -> Timer {
+        Timer {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: Instant::now(),
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+    #[cfg(target_arch = "wasm32")]
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub decisions: usize,
+    pub propagations: usize,
+    pub conflicts: usize,
+    pub restarts: usize,
+    pub learnt_clauses: usize,
+    pub learnt_literals: usize,
+    pub deleted_clauses: usize,
+    lbd_sum: usize,
+    times: [Duration; 4],
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats::default()
+    }
+    pub fn add_lbd(&mut self, lbd: usize) {
+        self.lbd_sum += lbd;
+    }
+    pub fn average_lbd(&self) -> f64 {
+        if self.learnt_clauses == 0 {
+            0.0
+        } else {
+            self.lbd_sum as f64 / self.learnt_clauses as f64
+        }
+    }
+    pub fn add_time(&mut self, phase: Phase, time: Duration) {
+        self.times[phase.index()] += time;
+    }
+    pub fn time(&self, phase: Phase) -> Duration {
+        self.times[phase.index()]
+    }
+    pub fn total_time(&self) -> Duration {
+        // analysis is measured inside search
+        self.time(Phase::Parse) + self.time(Phase::Simplify) + self.time(Phase::Search)
+    }
+    pub fn progress_header() -> String {
+        let mut res = String::new();
+        res.push_str("c ========================[ Search Statistics ]========================\n");
+        res.push_str("c | Conflicts |  Decisions  Propagations |   Learnt  Literals  Avg LBD |\n");
+        res.push_str("c =====================================================================");
+        res
+    }
+    pub fn progress_line(&self) -> String {
+        format!(
+            "c | {:>9} | {:>10} {:>13} | {:>8} {:>9} {:>8.2} |",
+            self.conflicts,
+            self.decisions,
+            self.propagations,
+            self.learnt_clauses,
+            self.learnt_literals,
+            self.average_lbd()
+        )
+    }
+    pub fn progress_footer() -> String {
+        "c =====================================================================".to_string()
+    }
+    pub fn summary(&self) -> String {
+        let mut res = String::new();
+        res.push_str(&format!("c decisions       : {}\n", self.decisions));
+        res.push_str(&format!("c propagations    : {}\n", self.propagations));
+        res.push_str(&format!("c conflicts       : {}\n", self.conflicts));
+        res.push_str(&format!("c restarts        : {}\n", self.restarts));
+        res.push_str(&format!("c learnt clauses  : {}\n", self.learnt_clauses));
+        res.push_str(&format!("c learnt literals : {}\n", self.learnt_literals));
+        res.push_str(&format!("c deleted clauses : {}\n", self.deleted_clauses));
+        res.push_str(&format!("c average lbd     : {:.2}\n", self.average_lbd()));
+        for &phase in Phase::ALL.iter() {
+            res.push_str(&format!(
+                "c {:<15} : {:.3} s\n",
+                format!("{} time", phase.name()),
+                self.time(phase).as_secs_f64()
+            ));
+        }
+        res.push_str(&format!(
+            "c total time      : {:.3} s",
+            self.total_time().as_secs_f64()
+        ));
+        res
+    }
+    pub fn to_json(&self) -> String {
+        let mut res = String::new();
+        res.push_str("{\n");
+        res.push_str(&format!("  \"decisions\": {},\n", self.decisions));
+        res.push_str(&format!("  \"propagations\": {},\n", self.propagations));
+        res.push_str(&format!("  \"conflicts\": {},\n", self.conflicts));
+        res.push_str(&format!("  \"restarts\": {},\n", self.restarts));
+        res.push_str(&format!("  \"learnt_clauses\": {},\n", self.learnt_clauses));
+        res.push_str(&format!(
+            "  \"learnt_literals\": {},\n",
+            self.learnt_literals
+        ));
+        res.push_str(&format!(
+            "  \"deleted_clauses\": {},\n",
+            self.deleted_clauses
+        ));
+        res.push_str(&format!("  \"average_lbd\": {},\n", self.average_lbd()));
+        res.push_str("  \"time\": {\n");
+        for &phase in Phase::ALL.iter() {
+            res.push_str(&format!(
+                "    \"{}\": {},\n",
+                phase.name(),
+                self.time(phase).as_secs_f64()
+            ));
+        }
+        res.push_str(&format!(
+            "    \"total\": {}\n",
+            self.total_time().as_secs_f64()
+        ));
+        res.push_str("  }\n");
+        res.push_str("}\n");
+        res
+    }
+}
+
+#[test]
+fn test_stats_average_lbd() {
+    let mut stats = Stats::new();
+    assert_eq!(stats.average_lbd(), 0.0);
+    stats.learnt_clauses = 2;
+    stats.add_lbd(3);
+    stats.add_lbd(4);
+    assert_eq!(stats.average_lbd(), 3.5);
+}
+
+#[test]
+fn test_stats_to_json() {
+    let mut stats = Stats::new();
+    stats.conflicts = 7;
+    stats.add_time(Phase::Search, Duration::from_millis(500));
+    let json = stats.to_json();
+    assert!(json.starts_with("{\n"));
+    assert!(json.contains("\"conflicts\": 7,\n"));
+    assert!(json.contains("\"search\": 0.5,\n"));
+    assert!(json.trim_end().ends_with('}'));
+}
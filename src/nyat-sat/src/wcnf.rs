@@ -0,0 +1,190 @@
+use crate::sat::{SatAssignments, SatProblem};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoftClause {
+    pub weight: u64,
+    /// DIMACS literals
+    pub literals: Vec<i64>,
+}
+
+/// Weighted partial MaxSAT problem: the hard clauses must be satisfied,
+/// and the total weight of the falsified soft clauses is minimized.
+#[derive(Debug)]
+pub struct WcnfProblem {
+    n_variables: usize,
+    hard: SatProblem,
+    soft: Vec<SoftClause>,
+}
+
+impl WcnfProblem {
+    pub fn new(n_variables: usize) -> WcnfProblem {
+        WcnfProblem {
+            n_variables,
+            hard: SatProblem::new(n_variables),
+            soft: vec![],
+        }
+    }
+    pub fn n_variables(&self) -> usize {
+        self.n_variables
+    }
+    pub fn hard(&self) -> &SatProblem {
+        &self.hard
+    }
+    pub fn soft(&self) -> &[SoftClause] {
+        &self.soft
+    }
+    pub fn add_hard_clause(&mut self, literals: &[i64]) {
+        self.hard.add_clause(literals);
+        self.n_variables = self.n_variables.max(self.hard.n_variables());
+    }
+    /// Adds a hard XOR constraint: an odd number of the DIMACS literals are true.
+    pub fn add_hard_xor(&mut self, literals: &[i64]) {
+        self.hard.add_xor(literals);
+        self.n_variables = self.n_variables.max(self.hard.n_variables());
+    }
+    pub fn add_soft_clause(&mut self, weight: u64, literals: &[i64]) {
+        assert!(weight > 0);
+        for &x in literals {
+            self.n_variables = self.n_variables.max(x.unsigned_abs() as usize);
+        }
+        self.soft.push(SoftClause {
+            weight,
+            literals: literals.to_vec(),
+        });
+    }
+    /// Returns the weight of the soft clauses falsified by `assignment`,
+    /// or `None` if it falsifies a hard clause.
+    pub fn cost(&self, assignment: &SatAssignments) -> Option<u64> {
+        if !self.hard.check_assignment(assignment) {
+            return None;
+        }
+        let satisfied = |xs: &[i64]| {
+            xs.iter()
+                .any(|&x| assignment[x.unsigned_abs() as usize - 1] == (x > 0))
+        };
+        Some(
+            self.soft
+                .iter()
+                .filter(|x| !satisfied(&x.literals))
+                .map(|x| x.weight)
+                .sum(),
+        )
+    }
+    /// Parses either the `p wcnf` format, where the clauses of weight at least `top` are hard,
+    /// or the format of the MaxSAT Evaluation 2022, where hard clauses start with `h`.
+    pub fn new_from_wcnf(s: &str) -> WcnfProblem {
+        let has_header = s
+            .lines()
+            .any(|line| line.split_whitespace().next() == Some("p"));
+        if has_header {
+            WcnfProblem::new_from_old_wcnf(s)
+        } else {
+            WcnfProblem::new_from_new_wcnf(s)
+        }
+    }
+    fn new_from_old_wcnf(s: &str) -> WcnfProblem {
+        let s2 = {
+            let mut res = String::new();
+            for line in s.lines() {
+                if line.chars().take(1).next() == Some('c') {
+                    continue;
+                }
+                res.push_str(line);
+                res.push('\n');
+            }
+            res
+        };
+        let mut lines = s2.lines().skip_while(|line| line.trim().is_empty());
+        let header: Vec<&str> = lines.next().unwrap().split_whitespace().collect();
+        assert_eq!(header[0], "p");
+        assert_eq!(header[1], "wcnf");
+        let n_variables = header[2].parse::<usize>().unwrap();
+        let n_clauses = header[3].parse::<usize>().unwrap();
+        // without `top`, every clause is soft
+        let top = header
+            .get(4)
+            .map_or(u64::MAX, |x| x.parse::<u64>().unwrap());
+        let mut res = WcnfProblem::new(n_variables);
+        let mut weight = None;
+        let mut xs = vec![];
+        let mut n = 0;
+        for t in lines.flat_map(|line| line.split_whitespace()) {
+            let w = match weight {
+                Some(w) => w,
+                None => {
+                    weight = Some(t.parse::<u64>().unwrap());
+                    continue;
+                }
+            };
+            let u = t.parse::<i64>().unwrap();
+            if u != 0 {
+                xs.push(u);
+                continue;
+            }
+            if w >= top {
+                res.add_hard_clause(&xs);
+            } else {
+                res.add_soft_clause(w, &xs);
+            }
+            n += 1;
+            weight = None;
+            xs.clear();
+        }
+        assert_eq!(n, n_clauses);
+        res
+    }
+    fn new_from_new_wcnf(s: &str) -> WcnfProblem {
+        let mut res = WcnfProblem::new(0);
+        for line in s.lines() {
+            let mut iter = line.split_whitespace();
+            let weight = match iter.next() {
+                None | Some("c") => continue,
+                Some("h") => None,
+                Some(t) => Some(t.parse::<u64>().unwrap()),
+            };
+            let mut xs: Vec<i64> = iter.map(|t| t.parse::<i64>().unwrap()).collect();
+            assert_eq!(xs.pop(), Some(0));
+            match weight {
+                None => res.add_hard_clause(&xs),
+                Some(w) => res.add_soft_clause(w, &xs),
+            }
+        }
+        res
+    }
+}
+
+#[test]
+fn test_parse_old_wcnf() {
+    let s = "c comment\np wcnf 3 4 10\n10 1 2 0\n10 -1 -2 0\n3 1 0\n5 -2\n3 0\n";
+    let problem = WcnfProblem::new_from_wcnf(s);
+    assert_eq!(problem.n_variables(), 3);
+    assert_eq!(problem.hard().n_clauses(), 2);
+    assert_eq!(
+        problem.soft(),
+        &[
+            SoftClause {
+                weight: 3,
+                literals: vec![1]
+            },
+            SoftClause {
+                weight: 5,
+                literals: vec![-2, 3]
+            },
+        ]
+    );
+    // without top every clause is soft
+    let problem = WcnfProblem::new_from_wcnf("p wcnf 2 2\n1 1 0\n2 -1 2 0\n");
+    assert_eq!(problem.hard().n_clauses(), 0);
+    assert_eq!(problem.soft().len(), 2);
+}
+
+#[test]
+fn test_parse_new_wcnf() {
+    let s = "c comment\nh 1 2 0\nh -1 -2 0\n3 1 0\n5 -2 4 0\n";
+    let problem = WcnfProblem::new_from_wcnf(s);
+    assert_eq!(problem.n_variables(), 4);
+    assert_eq!(problem.hard().n_clauses(), 2);
+    assert_eq!(problem.soft().len(), 2);
+    assert_eq!(problem.soft()[1].weight, 5);
+    assert_eq!(problem.soft()[1].literals, vec![-2, 4]);
+}
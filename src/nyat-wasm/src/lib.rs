@@ -0,0 +1,110 @@
+//! WebAssembly bindings of the solver for JavaScript, built with wasm-bindgen.
+//! Literals are DIMACS literals in `Int32Array`s.
+
+use js_sys::Function;
+use nyat_sat::sat::{SatAssignments, SatProblem, SatSolver};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct Problem {
+    problem: SatProblem,
+}
+
+#[wasm_bindgen]
+impl Problem {
+    /// Parses a DIMACS CNF, which throws if it is malformed.
+    #[wasm_bindgen(js_name = fromDimacs)]
+    pub fn from_dimacs(dimacs: &str) -> Result<Problem, JsError> {
+        SatProblem::try_from_dimacs(dimacs)
+            .map(|problem| Problem { problem })
+            .map_err(|e| JsError::new(&e))
+    }
+    #[wasm_bindgen(getter, js_name = nVariables)]
+    pub fn n_variables(&self) -> usize {
+        self.problem.n_variables()
+    }
+    #[wasm_bindgen(getter, js_name = nClauses)]
+    pub fn n_clauses(&self) -> usize {
+        self.problem.clauses().count()
+    }
+}
+
+/// Callback of JavaScript, which is only called from the solver on the one thread of wasm32.
+struct Budget(Function);
+
+unsafe impl Send for Budget {}
+
+#[wasm_bindgen]
+pub struct Solver {
+    solver: SatSolver<'static>,
+    /// model of the last call, if it was satisfiable
+    model: Option<SatAssignments>,
+}
+
+#[wasm_bindgen]
+impl Solver {
+    #[wasm_bindgen(constructor)]
+    pub fn new(problem: &Problem) -> Solver {
+        Solver {
+            solver: SatSolver::new(&problem.problem),
+            model: None,
+        }
+    }
+    #[wasm_bindgen(js_name = addClause)]
+    pub fn add_clause(&mut self, clause: &[i32]) {
+        let clause: Vec<i64> = clause.iter().map(|&x| x as i64).collect();
+        self.solver.add_clause(&clause);
+    }
+    /// Returns true if satisfiable, false if unsatisfiable, or undefined if `budget` stopped it.
+    /// `budget` is called at every conflict, and the search stops when it returns true or throws,
+    /// e.g. `() => performance.now() > deadline`, since the solver has no clock on wasm32.
+    pub fn solve(&mut self, assumptions: &[i32], budget: Option<Function>) -> Option<bool> {
+        let assumptions: Vec<i64> = assumptions.iter().map(|&x| x as i64).collect();
+        if let Some(budget) = budget {
+            let budget = Budget(budget);
+            self.solver.set_terminate(move || {
+                let budget = &budget;
+                budget
+                    .0
+                    .call0(&JsValue::NULL)
+                    .map_or(true, |x| x.is_truthy())
+            });
+        }
+        self.model = self.solver.solve_with_assumptions(&assumptions);
+        // the callback is only for one call
+        self.solver.set_terminate(|| false);
+        if self.model.is_some() {
+            Some(true)
+        } else if self.solver.interrupted() {
+            None
+        } else {
+            Some(false)
+        }
+    }
+    /// Returns the model of the last call, or undefined unless it was satisfiable.
+    pub fn model(&self) -> Option<Vec<i32>> {
+        self.model.as_ref().map(|model| {
+            (0..model.len())
+                .map(|i| {
+                    if model[i] {
+                        i as i32 + 1
+                    } else {
+                        -(i as i32) - 1
+                    }
+                })
+                .collect()
+        })
+    }
+    #[wasm_bindgen(js_name = failedAssumptions)]
+    pub fn failed_assumptions(&self) -> Vec<i32> {
+        self.solver
+            .failed_assumptions()
+            .into_iter()
+            .map(|x| x as i32)
+            .collect()
+    }
+    #[wasm_bindgen(getter)]
+    pub fn conflicts(&self) -> usize {
+        self.solver.stats().conflicts
+    }
+}
@@ -0,0 +1,57 @@
+use js_sys::Function;
+use nyat_wasm::{Problem, Solver};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn pigeonhole(n_holes: i32) -> String {
+    let var = |pigeon: i32, hole: i32| pigeon * n_holes + hole + 1;
+    let mut clauses = vec![];
+    for pigeon in 0..=n_holes {
+        let clause: Vec<String> = (0..n_holes).map(|h| var(pigeon, h).to_string()).collect();
+        clauses.push(clause.join(" "));
+    }
+    for hole in 0..n_holes {
+        for i in 0..=n_holes {
+            for k in i + 1..=n_holes {
+                clauses.push(format!("-{} -{}", var(i, hole), var(k, hole)));
+            }
+        }
+    }
+    let mut res = format!("p cnf {} {}\n", (n_holes + 1) * n_holes, clauses.len());
+    for clause in clauses {
+        res.push_str(&clause);
+        res.push_str(" 0\n");
+    }
+    res
+}
+
+#[wasm_bindgen_test]
+fn test_solve() {
+    let problem = Problem::from_dimacs("p cnf 3 2\n1 2 0\n-1 3 0\n").unwrap();
+    assert_eq!(problem.n_variables(), 3);
+    assert_eq!(problem.n_clauses(), 2);
+    let mut solver = Solver::new(&problem);
+    assert_eq!(solver.solve(&[-3], None), Some(true));
+    assert_eq!(solver.model(), Some(vec![-1, 2, -3]));
+    solver.add_clause(&[-2]);
+    assert_eq!(solver.solve(&[-3], None), Some(false));
+    assert_eq!(solver.model(), None);
+    assert_eq!(solver.failed_assumptions(), vec![-3]);
+    assert_eq!(solver.solve(&[], None), Some(true));
+    // malformed DIMACS throws
+    assert!(Problem::from_dimacs("p dnf 1 1\n").is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_budget() {
+    let problem = Problem::from_dimacs(&pigeonhole(7)).unwrap();
+    let mut solver = Solver::new(&problem);
+    let budget = Function::new_no_args("return true;");
+    assert_eq!(solver.solve(&[], Some(budget)), None);
+    // it is called at the first conflict before the analysis
+    assert_eq!(solver.conflicts(), 0);
+    // a callback that throws stops the search too
+    let budget = Function::new_no_args("throw new Error();");
+    assert_eq!(solver.solve(&[], Some(budget)), None);
+    // the budget is only for one call
+    assert_eq!(solver.solve(&[], None), Some(false));
+}
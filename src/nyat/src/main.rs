@@ -1,22 +1,696 @@
-#[macro_use]
-extern crate log;
 extern crate env_logger;
 
+use nyat_sat::aiger::Aig;
+use nyat_sat::cube::{conquer, to_icnf, Cuber};
+use nyat_sat::encodings::TseitinEncoding;
+use nyat_sat::formula::Formula;
+use nyat_sat::mcs::{Marco, MarcoResult, McsAlgorithm, McsEnumerator};
+use nyat_sat::mus::{parse_gcnf, subproblem, MusExtractor};
+use nyat_sat::pb::{ClausalEncoding, PbEncoding, PbProblem, PbResult};
+use nyat_sat::portfolio::Portfolio;
 use nyat_sat::sat::*;
+use nyat_sat::sls::{LocalSearch, SlsAlgorithm};
+use nyat_sat::stats::{Phase, Stats};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{Duration, Instant};
+
+const PROGRESS_INTERVAL: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Cdcl,
+    /// local search only, which cannot prove unsatisfiability
+    Sls,
+    /// local search, whose best assignment is the phases of CDCL
+    Hybrid,
+    /// cube-and-conquer
+    Cube,
+}
+
+struct Options {
+    input: String,
+    stats_json: Option<String>,
+    all_models: bool,
+    max_models: Option<usize>,
+    opb: bool,
+    pb_encoding: Option<PbEncoding>,
+    aiger: bool,
+    formula: bool,
+    icnf: bool,
+    /// whether a minimal unsatisfiable subset of the clauses is written instead of a model
+    mus: bool,
+    /// whether the minimal correction sets are enumerated instead of a model
+    mcs: bool,
+    mcs_algorithm: Option<McsAlgorithm>,
+    /// whether the MUSes are enumerated together with the minimal correction sets
+    marco: bool,
+    tseitin: TseitinEncoding,
+    mode: Mode,
+    sls_algorithm: Option<SlsAlgorithm>,
+    /// noise of the local search, which is the exponent cb of the break function of ProbSAT
+    noise: Option<f64>,
+    max_flips: Option<usize>,
+    threads: usize,
+    cube_depth: Option<usize>,
+    /// path of the iCNF of the cubes, which are written instead of solved
+    cubes: Option<String>,
+    /// seed of every random generator, so that a run can be repeated
+    seed: u64,
+    /// whether the decision order and the phases are random
+    randomize: bool,
+    restart_interval: Option<usize>,
+    /// path of the DRUP proof of unsatisfiability
+    proof: Option<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Options {
+        let mut input = None;
+        let mut stats_json = None;
+        let mut all_models = false;
+        let mut max_models = None;
+        let mut opb = false;
+        let mut pb_encoding = None;
+        let mut aiger = false;
+        let mut formula = false;
+        let mut icnf = false;
+        let mut mus = false;
+        let mut mcs = false;
+        let mut mcs_algorithm = None;
+        let mut marco = false;
+        let mut tseitin = TseitinEncoding::Full;
+        let mut mode = Mode::Cdcl;
+        let mut sls_algorithm = None;
+        let mut noise = None;
+        let mut max_flips = None;
+        let mut threads = 1;
+        let mut cube_depth = None;
+        let mut cubes = None;
+        let mut seed = 0;
+        let mut randomize = false;
+        let mut restart_interval = None;
+        let mut proof = None;
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--stats-json" => {
+                    stats_json = Some(iter.next().expect("--stats-json needs a path").clone());
+                }
+                "--all" => {
+                    all_models = true;
+                }
+                "--max-models" => {
+                    let n = iter.next().expect("--max-models needs a number");
+                    max_models = Some(n.parse::<usize>().unwrap());
+                }
+                "--opb" => {
+                    opb = true;
+                }
+                "--pb-encoding" => {
+                    pb_encoding = Some(match iter.next().map(|x| x.as_str()) {
+                        Some("bdd") => PbEncoding::Clauses(ClausalEncoding::Bdd),
+                        Some("adder") => PbEncoding::Clauses(ClausalEncoding::Adder),
+                        Some("gte") => PbEncoding::Clauses(ClausalEncoding::GeneralizedTotalizer),
+                        Some("sorter") => PbEncoding::Clauses(ClausalEncoding::SortingNetwork),
+                        Some("native") => PbEncoding::Native,
+                        x => panic!(
+                            "--pb-encoding needs bdd, adder, gte, sorter or native: {:?}",
+                            x
+                        ),
+                    });
+                }
+                "--aiger" => {
+                    aiger = true;
+                }
+                "--formula" => {
+                    formula = true;
+                }
+                "--icnf" => {
+                    icnf = true;
+                }
+                "--mus" => {
+                    mus = true;
+                }
+                "--mcs" => {
+                    mcs = true;
+                }
+                "--mcs-algorithm" => {
+                    mcs_algorithm = Some(match iter.next().map(|x| x.as_str()) {
+                        Some("linear") => McsAlgorithm::LinearSearch,
+                        Some("cld") => McsAlgorithm::Cld,
+                        x => panic!("--mcs-algorithm needs linear or cld: {:?}", x),
+                    });
+                }
+                "--marco" => {
+                    marco = true;
+                }
+                "--plaisted-greenbaum" => {
+                    tseitin = TseitinEncoding::PlaistedGreenbaum;
+                }
+                "--mode" => {
+                    mode = match iter.next().map(|x| x.as_str()) {
+                        Some("cdcl") => Mode::Cdcl,
+                        Some("sls") => Mode::Sls,
+                        Some("hybrid") => Mode::Hybrid,
+                        Some("cube") => Mode::Cube,
+                        x => panic!("--mode needs cdcl, sls, hybrid or cube: {:?}", x),
+                    };
+                }
+                "--sls-algorithm" => {
+                    sls_algorithm = Some(match iter.next().map(|x| x.as_str()) {
+                        Some("probsat") => SlsAlgorithm::probsat(),
+                        Some("walksat") => SlsAlgorithm::walksat(),
+                        x => panic!("--sls-algorithm needs probsat or walksat: {:?}", x),
+                    });
+                }
+                "--noise" => {
+                    let x = iter.next().expect("--noise needs a number");
+                    noise = Some(x.parse::<f64>().unwrap());
+                }
+                "--max-flips" => {
+                    let n = iter.next().expect("--max-flips needs a number");
+                    max_flips = Some(n.parse::<usize>().unwrap());
+                }
+                "--threads" => {
+                    let n = iter.next().expect("--threads needs a number");
+                    threads = n.parse::<usize>().unwrap();
+                    assert!(threads > 0, "--threads needs a positive number");
+                }
+                "--cube-depth" => {
+                    let n = iter.next().expect("--cube-depth needs a number");
+                    cube_depth = Some(n.parse::<usize>().unwrap());
+                }
+                "--cubes" => {
+                    cubes = Some(iter.next().expect("--cubes needs a path").clone());
+                }
+                "--seed" => {
+                    let n = iter.next().expect("--seed needs a number");
+                    seed = n.parse::<u64>().unwrap();
+                }
+                "--randomize" => {
+                    randomize = true;
+                }
+                "--restart-interval" => {
+                    let n = iter.next().expect("--restart-interval needs a number");
+                    restart_interval = Some(n.parse::<usize>().unwrap());
+                }
+                "--proof" => {
+                    proof = Some(iter.next().expect("--proof needs a path").clone());
+                }
+                _ => {
+                    assert!(input.is_none(), "unexpected argument: {}", arg);
+                    input = Some(arg.clone());
+                }
+            }
+        }
+        Options {
+            input: input.expect("no input file"),
+            stats_json,
+            all_models,
+            max_models,
+            opb,
+            pb_encoding,
+            aiger,
+            formula,
+            icnf,
+            mus,
+            mcs,
+            mcs_algorithm,
+            marco,
+            tseitin,
+            mode,
+            sls_algorithm,
+            noise,
+            max_flips,
+            threads,
+            cube_depth,
+            cubes,
+            seed,
+            randomize,
+            restart_interval,
+            proof,
+        }
+    }
+    /// Returns the algorithm of the local search with the noise.
+    fn sls_algorithm(&self) -> SlsAlgorithm {
+        let algorithm = self.sls_algorithm.unwrap_or_else(SlsAlgorithm::probsat);
+        match (algorithm, self.noise) {
+            (SlsAlgorithm::ProbSat { .. }, Some(x)) => SlsAlgorithm::ProbSat { cb: x },
+            (SlsAlgorithm::WalkSat { .. }, Some(x)) => SlsAlgorithm::WalkSat { noise: x },
+            (algorithm, None) => algorithm,
+        }
+    }
+    /// Returns an error for an option that the chosen way of solving would ignore.
+    fn check(&self) -> Result<(), String> {
+        let models = self.all_models || self.max_models.is_some();
+        let sls = self.mode == Mode::Sls || self.mode == Mode::Hybrid;
+        // an option of one way of solving needs it to be chosen
+        let need = |given: bool, option: &str, chosen: bool, solving: &str| {
+            if given && !chosen {
+                Err(format!("{} needs {}", option, solving))
+            } else {
+                Ok(())
+            }
+        };
+        let cube = self.mode == Mode::Cube;
+        let tseitin = self.tseitin != TseitinEncoding::Full;
+        need(
+            self.sls_algorithm.is_some(),
+            "--sls-algorithm",
+            sls,
+            "--mode sls or hybrid",
+        )?;
+        need(self.noise.is_some(), "--noise", sls, "--mode sls or hybrid")?;
+        need(
+            self.max_flips.is_some(),
+            "--max-flips",
+            sls,
+            "--mode sls or hybrid",
+        )?;
+        need(self.cubes.is_some(), "--cubes", cube, "--mode cube")?;
+        need(
+            self.cube_depth.is_some(),
+            "--cube-depth",
+            cube,
+            "--mode cube",
+        )?;
+        need(
+            self.pb_encoding.is_some(),
+            "--pb-encoding",
+            self.opb,
+            "--opb",
+        )?;
+        need(
+            tseitin,
+            "--plaisted-greenbaum",
+            self.aiger || self.formula,
+            "--aiger or --formula",
+        )?;
+        need(
+            self.mcs_algorithm.is_some(),
+            "--mcs-algorithm",
+            self.mcs || self.marco,
+            "--mcs or --marco",
+        )?;
+        let format = [
+            (self.aiger, "--aiger"),
+            (self.opb, "--opb"),
+            (self.formula, "--formula"),
+            (self.icnf, "--icnf"),
+            (self.mus, "--mus"),
+            (self.mcs, "--mcs"),
+            (self.marco, "--marco"),
+        ]
+        .iter()
+        .find(|x| x.0)
+        .map(|x| x.1);
+        let (solving, options) = if let Some(format) = format {
+            let options = vec![
+                (self.stats_json.is_some(), "--stats-json"),
+                (self.proof.is_some(), "--proof"),
+                (self.threads > 1, "--threads"),
+                (self.mode != Mode::Cdcl, "--mode"),
+                (models, "--all or --max-models"),
+                (self.randomize, "--randomize"),
+                (self.restart_interval.is_some(), "--restart-interval"),
+            ];
+            (format, options)
+        } else if self.mode == Mode::Cube {
+            let options = vec![
+                (self.stats_json.is_some(), "--stats-json"),
+                (self.proof.is_some(), "--proof"),
+                (models, "--all or --max-models"),
+                (self.randomize, "--randomize"),
+                (self.restart_interval.is_some(), "--restart-interval"),
+            ];
+            ("--mode cube", options)
+        } else if self.threads > 1 {
+            let options = vec![
+                (self.mode != Mode::Cdcl, "--mode sls or hybrid"),
+                (self.proof.is_some(), "--proof"),
+                (models, "--all or --max-models"),
+                (self.randomize, "--randomize"),
+                (self.restart_interval.is_some(), "--restart-interval"),
+            ];
+            ("--threads", options)
+        } else if sls {
+            // a model of the local search is printed as soon as it is found
+            let options = vec![
+                (self.stats_json.is_some(), "--stats-json"),
+                (models, "--all or --max-models"),
+                (self.mode == Mode::Sls && self.proof.is_some(), "--proof"),
+                (self.mode == Mode::Sls && self.randomize, "--randomize"),
+                (
+                    self.mode == Mode::Sls && self.restart_interval.is_some(),
+                    "--restart-interval",
+                ),
+            ];
+            let mode = if self.mode == Mode::Sls {
+                "--mode sls"
+            } else {
+                "--mode hybrid"
+            };
+            (mode, options)
+        } else if self.proof.is_some() {
+            // the enumeration blocks the models by clauses that are not implied
+            ("--proof", vec![(models, "--all or --max-models")])
+        } else {
+            return Ok(());
+        };
+        match options.iter().find(|x| x.0) {
+            Some((_, option)) => Err(format!("{} is not supported with {}", option, solving)),
+            None => Ok(()),
+        }
+    }
+}
 
 fn main() {
     env_logger::init();
 
     let args: Vec<String> = std::env::args().collect();
-    let s = std::fs::read_to_string(args[1].as_str()).unwrap();
+    let options = Options::parse(&args);
+    if let Err(message) = options.check() {
+        eprintln!("error: {}", message);
+        std::process::exit(1);
+    }
+    let start = Instant::now();
+    if options.aiger {
+        // the binary format is not UTF-8
+        let bytes = std::fs::read(options.input.as_str()).unwrap();
+        solve_aiger(&bytes, options.tseitin);
+        return;
+    }
+    let s = std::fs::read_to_string(options.input.as_str()).unwrap();
+    if options.opb {
+        solve_opb(
+            &s,
+            options
+                .pb_encoding
+                .unwrap_or(PbEncoding::Clauses(ClausalEncoding::Bdd)),
+        );
+        return;
+    }
+    if options.formula {
+        solve_formula(&s, options.tseitin);
+        return;
+    }
+    if options.icnf {
+        solve_icnf(&s);
+        return;
+    }
+    if options.mus {
+        solve_mus(&s);
+        return;
+    }
+    if options.mcs || options.marco {
+        solve_mcs(
+            &s,
+            options.mcs_algorithm.unwrap_or(McsAlgorithm::Cld),
+            options.marco,
+        );
+        return;
+    }
     let problem = SatProblem::new_from_dimacs(s.as_str());
+    let parse_time = start.elapsed();
+    if options.mode == Mode::Cube {
+        solve_cubes(&problem, &options);
+        return;
+    }
+    if options.threads > 1 {
+        solve_portfolio(&problem, &options, parse_time);
+        return;
+    }
     let mut solver = SatSolver::new(&problem);
+    solver.set_seed(options.seed);
+    if options.randomize {
+        solver.shuffle_decision_order();
+        solver.randomize_phases();
+    }
+    if let Some(interval) = options.restart_interval {
+        solver.set_restart_interval(interval);
+    }
+    if let Some(path) = &options.proof {
+        let mut writer = BufWriter::new(File::create(path).unwrap());
+        solver.set_proof(move |step| writeln!(writer, "{}", step.to_drup()).unwrap());
+    }
+    if options.mode != Mode::Cdcl {
+        let mut sls = LocalSearch::new(&problem, options.seed);
+        let model = sls.run(
+            options.sls_algorithm(),
+            options.max_flips.unwrap_or(1_000_000),
+        );
+        println!(
+            "c flips: {}, falsified clauses: {}",
+            sls.flips(),
+            sls.best_n_falsified()
+        );
+        if let Some(model) = model {
+            println!("SAT");
+            println!("{}", model.to_dimacs());
+            return;
+        }
+        if options.mode == Mode::Sls {
+            println!("UNKNOWN");
+            return;
+        }
+        solver.set_phases(sls.best_assignment());
+    }
+    println!("{}", Stats::progress_header());
+    solver.set_progress(PROGRESS_INTERVAL, |stats| {
+        println!("{}", stats.progress_line())
+    });
+    if options.all_models || options.max_models.is_some() {
+        let max_models = options.max_models.unwrap_or(usize::MAX);
+        let mut n_models = 0;
+        for model in solver.enumerate().take(max_models) {
+            println!("v {}", model.to_dimacs());
+            n_models += 1;
+        }
+        println!("{}", Stats::progress_footer());
+        print_stats(&solver, parse_time, options.stats_json);
+        println!("c models: {}", n_models);
+        return;
+    }
     let assignment = solver.solve();
+    println!("{}", Stats::progress_footer());
+    print_stats(&solver, parse_time, options.stats_json);
     eprintln!("{:?}", assignment);
-    if assignment.is_some() {
+    if let Some(assignment) = assignment {
         println!("SAT");
-        println!("{}", assignment.unwrap().to_dimacs());
+        println!("{}", assignment.to_dimacs());
     } else {
         println!("UNSAT");
     }
 }
+
+fn print_stats(solver: &SatSolver, parse_time: Duration, stats_json: Option<String>) {
+    print_summary(solver.stats().clone(), parse_time, stats_json);
+}
+
+fn print_summary(mut stats: Stats, parse_time: Duration, stats_json: Option<String>) {
+    stats.add_time(Phase::Parse, parse_time);
+    println!("{}", stats.summary());
+    if let Some(path) = stats_json {
+        std::fs::write(path, stats.to_json()).unwrap();
+    }
+}
+
+/// Solves by a portfolio of solvers in threads, and prints the statistics of the one that answered.
+fn solve_portfolio(problem: &SatProblem, options: &Options, parse_time: Duration) {
+    let mut portfolio = Portfolio::new(options.threads);
+    portfolio.set_seed(options.seed);
+    let (assignment, stats) = portfolio.solve(problem);
+    print_summary(stats, parse_time, options.stats_json.clone());
+    if let Some(assignment) = assignment {
+        println!("SAT");
+        println!("{}", assignment.to_dimacs());
+    } else {
+        println!("UNSAT");
+    }
+}
+
+/// Splits the problem into cubes by lookahead, and solves them in threads or writes them as iCNF.
+fn solve_cubes(problem: &SatProblem, options: &Options) {
+    let cubes = Cuber::new(problem).cubes(options.cube_depth.unwrap_or(10));
+    println!("c cubes: {}", cubes.len());
+    if let Some(path) = &options.cubes {
+        std::fs::write(path, to_icnf(problem, &cubes)).unwrap();
+        return;
+    }
+    if let Some(assignment) = conquer(problem, &cubes, options.threads) {
+        println!("SAT");
+        println!("{}", assignment.to_dimacs());
+    } else {
+        println!("UNSAT");
+    }
+}
+
+/// Solves whether an output of a circuit can be true, and prints the values of its inputs and latches.
+fn solve_aiger(bytes: &[u8], encoding: TseitinEncoding) {
+    let aig = Aig::parse(bytes);
+    let cnf = aig.to_sat_problem(encoding);
+    let mut solver = SatSolver::new(cnf.problem());
+    let bits =
+        |xs: Vec<bool>| -> String { xs.iter().map(|&x| if x { '1' } else { '0' }).collect() };
+    match solver.solve() {
+        Some(model) => {
+            println!("SAT");
+            println!("inputs: {}", bits(cnf.input_vector(&model)));
+            println!("latches: {}", bits(cnf.latch_vector(&model)));
+        }
+        None => println!("UNSAT"),
+    }
+}
+
+/// Solves an iCNF problem incrementally: every line of assumptions is solved under the clauses before it,
+/// and prints SAT with a model, or UNSAT with the failed assumptions.
+fn solve_icnf(s: &str) {
+    let mut solver = SatSolver::new(&SatProblem::new(0));
+    for line in parse_icnf(s) {
+        match line {
+            IcnfLine::Clause(xs) => solver.add_clause(&xs),
+            IcnfLine::Xor(xs) => solver.add_xor(&xs),
+            IcnfLine::Assumptions(xs) => match solver.solve_with_assumptions(&xs) {
+                Some(model) => {
+                    println!("SAT");
+                    println!("{}", model.to_dimacs());
+                }
+                None => {
+                    let failed: Vec<String> = solver
+                        .failed_assumptions()
+                        .iter()
+                        .map(|x| x.to_string())
+                        .collect();
+                    println!("UNSAT");
+                    println!("failed: {}", failed.join(" "));
+                }
+            },
+        }
+    }
+    println!("{}", solver.stats().summary());
+}
+
+/// Parses DIMACS, where every clause is its own group, or GCNF with a `p gcnf` header,
+/// and returns the problem, the groups of the clauses and whether it is GCNF.
+fn parse_groups(s: &str) -> (SatProblem, Vec<usize>, bool) {
+    let gcnf = content_lines(s)
+        .next()
+        .is_some_and(|x| x.split_whitespace().nth(1) == Some("gcnf"));
+    if gcnf {
+        let (problem, groups) = parse_gcnf(s);
+        (problem, groups, true)
+    } else {
+        let problem = SatProblem::new_from_dimacs(s);
+        let groups = (1..=problem.n_clauses()).collect();
+        (problem, groups, false)
+    }
+}
+
+fn join(xs: &[usize]) -> String {
+    let xs: Vec<String> = xs.iter().map(|x| x.to_string()).collect();
+    xs.join(" ")
+}
+
+/// Extracts a MUS of DIMACS, or a group-MUS of GCNF, and prints SAT with a model, or UNSAT with
+/// the indices of the clauses and the groups of the MUS, followed by its DIMACS.
+fn solve_mus(s: &str) {
+    let (problem, groups, gcnf) = parse_groups(s);
+    let mut mus = MusExtractor::new_with_groups(&problem, &groups);
+    match mus.extract() {
+        Some(clauses) => {
+            let groups: Vec<usize> = clauses.iter().map(|&i| groups[i]).collect();
+            println!("UNSAT");
+            println!("c clauses: {}", join(&clauses));
+            if gcnf {
+                let mut groups: Vec<usize> = groups.into_iter().filter(|&g| g != 0).collect();
+                groups.sort_unstable();
+                groups.dedup();
+                println!("c groups: {}", join(&groups));
+            }
+            println!("c solves: {}, rotated: {}", mus.n_solves(), mus.n_rotated());
+            print!("{}", subproblem(&problem, &clauses).to_dimacs());
+        }
+        None => {
+            let model = SatSolver::new(&problem).solve().unwrap();
+            println!("SAT");
+            println!("{}", model.to_dimacs());
+        }
+    }
+}
+
+/// Enumerates the MCSes of DIMACS or the group-MCSes of GCNF, together with the MUSes by MARCO if `marco`
+/// is set, and prints every one of them as the indices of its clauses, or as its groups for GCNF.
+fn solve_mcs(s: &str, algorithm: McsAlgorithm, marco: bool) {
+    let (problem, groups, gcnf) = parse_groups(s);
+    let (mut n_mcses, mut n_muses) = (0, 0);
+    if marco {
+        let mut marco = Marco::new_with_groups(&problem, &groups);
+        marco.set_algorithm(algorithm);
+        while let Some(x) = if gcnf {
+            marco.next_groups()
+        } else {
+            marco.next()
+        } {
+            match x {
+                MarcoResult::Mcs(xs) => {
+                    n_mcses += 1;
+                    println!("mcs: {}", join(&xs));
+                }
+                MarcoResult::Mus(xs) => {
+                    n_muses += 1;
+                    println!("mus: {}", join(&xs));
+                }
+            }
+        }
+    } else {
+        let mut mcses = McsEnumerator::new_with_groups(&problem, &groups);
+        mcses.set_algorithm(algorithm);
+        while let Some(xs) = if gcnf {
+            mcses.next_groups()
+        } else {
+            mcses.next()
+        } {
+            n_mcses += 1;
+            println!("mcs: {}", join(&xs));
+        }
+    }
+    println!("c mcses: {}, muses: {}", n_mcses, n_muses);
+}
+
+/// Solves a formula in the syntax of `Formula::parse`, and prints a model of its variables.
+fn solve_formula(s: &str, encoding: TseitinEncoding) {
+    let formula = Formula::parse(s);
+    let problem = formula.to_sat_problem(encoding);
+    match SatSolver::new(&problem).solve() {
+        Some(mut model) => {
+            assert!(formula.evaluate(&model));
+            model.truncate(formula.max_variable());
+            println!("SAT");
+            println!("{}", model.to_dimacs());
+        }
+        None => println!("UNSAT"),
+    }
+}
+
+/// Solves an OPB problem, with the output of the PB competition.
+fn solve_opb(s: &str, encoding: PbEncoding) {
+    let problem = PbProblem::new_from_opb(s);
+    let model_line = |model: &SatAssignments| {
+        let literals: Vec<String> = (0..model.len())
+            .map(|i| format!("{}x{}", if model[i] { "" } else { "-" }, i + 1))
+            .collect();
+        format!("v {}", literals.join(" "))
+    };
+    match problem.solve(encoding, |value| println!("o {}", value)) {
+        PbResult::Satisfiable(model) => {
+            println!("s SATISFIABLE");
+            println!("{}", model_line(&model));
+        }
+        PbResult::Optimum { model, .. } => {
+            println!("s OPTIMUM FOUND");
+            println!("{}", model_line(&model));
+        }
+        PbResult::Unsatisfiable => println!("s UNSATISFIABLE"),
+    }
+}